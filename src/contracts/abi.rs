@@ -1,4 +1,7 @@
 use serde::Deserialize;
+use web3::ethabi::param_type::Reader;
+use web3::ethabi::{decode, encode, ParamType, Token};
+use web3::signing::keccak256;
 
 /// Struct representing a function from an ABI (Application Binary Interface).
 /// 
@@ -8,7 +11,7 @@ use serde::Deserialize;
 /// - `outputs`: A list of output parameter types for the function.
 /// - `payable`: Indicates whether the function accepts Ether.
 /// - `constant`: Indicates whether the function is constant (i.e., does not change state).
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct AbiFunction {
     pub name: String,
     pub inputs: Vec<String>,
@@ -17,6 +20,51 @@ pub struct AbiFunction {
     pub constant: bool,
 }
 
+impl AbiFunction {
+    /// Returns the canonical signature of the function, e.g. `transfer(address,uint256)`.
+    pub fn signature(&self) -> String {
+        format!("{}({})", self.name, self.inputs.join(","))
+    }
+
+    /// Returns the 4-byte selector of the function.
+    pub fn selector(&self) -> [u8; 4] {
+        let hash = keccak256(self.signature().as_bytes());
+        [hash[0], hash[1], hash[2], hash[3]]
+    }
+
+    /// Encodes a call to the function, checking the parameters against its input types.
+    ///
+    /// # Errors
+    /// - If an input type cannot be parsed or the parameters do not match the input types.
+    pub fn encode_input(&self, params: &[Token]) -> Result<Vec<u8>, String> {
+        let types = parse_param_types(&self.inputs)?;
+        if !Token::types_check(params, &types) {
+            return Err(format!("Parameters do not match the inputs of {}.", self.signature()));
+        }
+
+        let mut calldata = self.selector().to_vec();
+        calldata.extend(encode(params));
+        Ok(calldata)
+    }
+
+    /// Decodes the data returned by a call to the function into its output types.
+    ///
+    /// # Errors
+    /// - If an output type cannot be parsed or the data does not decode.
+    pub fn decode_output(&self, data: &[u8]) -> Result<Vec<Token>, String> {
+        let types = parse_param_types(&self.outputs)?;
+        decode(&types, data).map_err(|e| format!("Failed to decode output of {}: {}", self.signature(), e))
+    }
+}
+
+/// Parses Solidity type names such as `uint256` or `(address,bytes4[])[]` into ABI parameter types.
+pub fn parse_param_types(types: &[String]) -> Result<Vec<ParamType>, String> {
+    types
+        .iter()
+        .map(|name| Reader::read(name).map_err(|e| format!("Invalid ABI type '{}': {}", name, e)))
+        .collect()
+}
+
 /// Parses the given ABI JSON string and returns a vector of `AbiFunction`.
 /// 
 /// # Arguments
@@ -40,6 +88,18 @@ pub fn parse_abi(abi_json: &str) -> Result<Vec<AbiFunction>, String> {
     Ok(parsed)
 }
 
+/// ABI-encodes a call to the function with the given canonical signature.
+pub fn encode_call(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut calldata = keccak256(signature.as_bytes())[..4].to_vec();
+    calldata.extend(encode(tokens));
+    calldata
+}
+
+/// Formats bytes as lowercase hex without a `0x` prefix.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
 // Unit test example
 #[cfg(test)]
 mod tests {
@@ -78,4 +138,43 @@ mod tests {
         assert_eq!(functions[0].name, "transfer");
         assert_eq!(functions[0].inputs, vec!["address", "uint256"]);
     }
+
+    #[test]
+    fn test_function_selector() {
+        let function = AbiFunction {
+            name: "transfer".to_string(),
+            inputs: vec!["address".to_string(), "uint256".to_string()],
+            outputs: vec!["bool".to_string()],
+            payable: false,
+            constant: false,
+        };
+        assert_eq!(function.signature(), "transfer(address,uint256)");
+        assert_eq!(function.selector(), [0xa9, 0x05, 0x9c, 0xbb]);
+    }
+
+    #[test]
+    fn test_encode_and_decode_roundtrip() {
+        let function = AbiFunction {
+            name: "balanceOf".to_string(),
+            inputs: vec!["address".to_string()],
+            outputs: vec!["uint256".to_string()],
+            payable: false,
+            constant: true,
+        };
+
+        let calldata = function.encode_input(&[Token::Address(Default::default())]).unwrap();
+        assert_eq!(calldata[..4], [0x70, 0xa0, 0x82, 0x31]);
+        assert!(function.encode_input(&[Token::Bool(true)]).is_err());
+
+        let output = encode(&[Token::Uint(42.into())]);
+        assert_eq!(function.decode_output(&output).unwrap(), vec![Token::Uint(42.into())]);
+        assert!(function.decode_output(&[0x01]).is_err());
+    }
+
+    #[test]
+    fn test_encode_call_selector() {
+        let calldata = encode_call("upgradeTo(address)", &[Token::Address(Default::default())]);
+        assert_eq!(calldata[..4], [0x36, 0x59, 0xcf, 0xe6]);
+        assert_eq!(calldata.len(), 36);
+    }
 }
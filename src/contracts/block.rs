@@ -0,0 +1,97 @@
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use web3::types::{BlockId, BlockNumber, H256, U64};
+
+/// The block a read is executed against.
+///
+/// Unlike `web3`'s `BlockNumber`, this covers the `safe` and `finalized` tags and block
+/// hashes (EIP-1898), which every read path of `interaction` accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlockTag {
+    /// The most recent block.
+    #[default]
+    Latest,
+    /// The most recent block that is unlikely to be reorged.
+    Safe,
+    /// The most recent finalized block.
+    Finalized,
+    /// The pending state, including transactions in the mempool.
+    Pending,
+    /// The genesis block.
+    Earliest,
+    /// A block by number.
+    Number(U64),
+    /// A block by hash. The node rejects the read if it does not know the block.
+    Hash(H256),
+}
+
+impl Serialize for BlockTag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            BlockTag::Latest => serializer.serialize_str("latest"),
+            BlockTag::Safe => serializer.serialize_str("safe"),
+            BlockTag::Finalized => serializer.serialize_str("finalized"),
+            BlockTag::Pending => serializer.serialize_str("pending"),
+            BlockTag::Earliest => serializer.serialize_str("earliest"),
+            BlockTag::Number(number) => number.serialize(serializer),
+            BlockTag::Hash(hash) => json!({ "blockHash": hash }).serialize(serializer),
+        }
+    }
+}
+
+impl From<u64> for BlockTag {
+    fn from(number: u64) -> Self {
+        BlockTag::Number(U64::from(number))
+    }
+}
+
+impl From<U64> for BlockTag {
+    fn from(number: U64) -> Self {
+        BlockTag::Number(number)
+    }
+}
+
+impl From<H256> for BlockTag {
+    fn from(hash: H256) -> Self {
+        BlockTag::Hash(hash)
+    }
+}
+
+impl From<BlockNumber> for BlockTag {
+    fn from(number: BlockNumber) -> Self {
+        match number {
+            BlockNumber::Latest => BlockTag::Latest,
+            BlockNumber::Pending => BlockTag::Pending,
+            BlockNumber::Earliest => BlockTag::Earliest,
+            BlockNumber::Number(number) => BlockTag::Number(number),
+        }
+    }
+}
+
+impl From<BlockId> for BlockTag {
+    fn from(id: BlockId) -> Self {
+        match id {
+            BlockId::Hash(hash) => BlockTag::Hash(hash),
+            BlockId::Number(number) => number.into(),
+        }
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_block_tags() {
+        assert_eq!(json!(BlockTag::default()), json!("latest"));
+        assert_eq!(json!(BlockTag::Safe), json!("safe"));
+        assert_eq!(json!(BlockTag::Finalized), json!("finalized"));
+        assert_eq!(json!(BlockTag::from(17_000_000)), json!("0x1036640"));
+        assert_eq!(
+            json!(BlockTag::from(H256::repeat_byte(0xab))),
+            json!({ "blockHash": format!("0x{}", "ab".repeat(32)) })
+        );
+        assert_eq!(BlockTag::from(BlockId::Number(BlockNumber::Pending)), BlockTag::Pending);
+    }
+}
@@ -0,0 +1,794 @@
+use crate::framework::logging::{log_info, log_warn};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt;
+
+/// Errors that can occur while analyzing bytecode.
+#[derive(Debug)]
+pub enum BytecodeError {
+    EmptyBytecode,
+    UnknownSelector,
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::EmptyBytecode => write!(f, "empty bytecode"),
+            BytecodeError::UnknownSelector => write!(f, "no function with this selector in the dispatcher"),
+        }
+    }
+}
+
+impl std::error::Error for BytecodeError {}
+
+/// The most path states explored per function before the analysis gives up on an exact bound.
+const MAX_STATES: usize = 100_000;
+
+/// The EVM stack limit; a path that pushes past it halts exceptionally.
+const STACK_LIMIT: usize = 1024;
+
+/// A gas cost that depends on runtime state and is not part of the static bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DynamicCost {
+    /// `SLOAD`/`SSTORE` pay 2,100 more for a slot not accessed yet in the transaction (EIP-2929).
+    ColdStorageAccess,
+    /// `SSTORE` pays up to 20,000 when setting a zero slot, and may earn a refund.
+    StorageWrite,
+    /// Account access (`BALANCE`, `EXTCODE*`, calls) pays 2,500 more for a cold address.
+    ColdAccountAccess,
+    /// Touching memory beyond its current size costs `3 * words + words² / 512`.
+    MemoryExpansion,
+    /// `*COPY` opcodes pay 3 per copied word.
+    Copy,
+    /// `KECCAK256` pays 6 per hashed word.
+    Keccak,
+    /// `LOG*` pays 8 per byte of data.
+    LogData,
+    /// `EXP` pays 50 per byte of the exponent.
+    Exponent,
+    /// Calls forward gas to the callee and pay for value transfers and new accounts.
+    Call,
+    /// `CREATE`/`CREATE2` pay for init code, hashing and the deployed code.
+    Create,
+    /// `INVALID` consumes all remaining gas.
+    ConsumesAllGas,
+}
+
+/// Static facts about an opcode.
+struct OpInfo {
+    name: &'static str,
+    /// The minimum gas charged, e.g. the warm access cost for `SLOAD`.
+    gas: u64,
+    pops: usize,
+    pushes: usize,
+    dynamic: &'static [DynamicCost],
+}
+
+const fn op(name: &'static str, gas: u64, pops: usize, pushes: usize, dynamic: &'static [DynamicCost]) -> Option<OpInfo> {
+    Some(OpInfo { name, gas, pops, pushes, dynamic })
+}
+
+const PUSH_NAMES: [&str; 32] = [
+    "PUSH1", "PUSH2", "PUSH3", "PUSH4", "PUSH5", "PUSH6", "PUSH7", "PUSH8", "PUSH9", "PUSH10", "PUSH11", "PUSH12",
+    "PUSH13", "PUSH14", "PUSH15", "PUSH16", "PUSH17", "PUSH18", "PUSH19", "PUSH20", "PUSH21", "PUSH22", "PUSH23",
+    "PUSH24", "PUSH25", "PUSH26", "PUSH27", "PUSH28", "PUSH29", "PUSH30", "PUSH31", "PUSH32",
+];
+const DUP_NAMES: [&str; 16] = [
+    "DUP1", "DUP2", "DUP3", "DUP4", "DUP5", "DUP6", "DUP7", "DUP8", "DUP9", "DUP10", "DUP11", "DUP12", "DUP13", "DUP14",
+    "DUP15", "DUP16",
+];
+const SWAP_NAMES: [&str; 16] = [
+    "SWAP1", "SWAP2", "SWAP3", "SWAP4", "SWAP5", "SWAP6", "SWAP7", "SWAP8", "SWAP9", "SWAP10", "SWAP11", "SWAP12",
+    "SWAP13", "SWAP14", "SWAP15", "SWAP16",
+];
+const LOG_NAMES: [&str; 5] = ["LOG0", "LOG1", "LOG2", "LOG3", "LOG4"];
+
+/// Returns the static facts of an opcode as of the Cancun fork, or `None` if it is undefined.
+fn op_info(opcode: u8) -> Option<OpInfo> {
+    match opcode {
+        0x00 => op("STOP", 0, 0, 0, &[]),
+        0x01 => op("ADD", 3, 2, 1, &[]),
+        0x02 => op("MUL", 5, 2, 1, &[]),
+        0x03 => op("SUB", 3, 2, 1, &[]),
+        0x04 => op("DIV", 5, 2, 1, &[]),
+        0x05 => op("SDIV", 5, 2, 1, &[]),
+        0x06 => op("MOD", 5, 2, 1, &[]),
+        0x07 => op("SMOD", 5, 2, 1, &[]),
+        0x08 => op("ADDMOD", 8, 3, 1, &[]),
+        0x09 => op("MULMOD", 8, 3, 1, &[]),
+        0x0a => op("EXP", 10, 2, 1, &[DynamicCost::Exponent]),
+        0x0b => op("SIGNEXTEND", 5, 2, 1, &[]),
+        0x10 => op("LT", 3, 2, 1, &[]),
+        0x11 => op("GT", 3, 2, 1, &[]),
+        0x12 => op("SLT", 3, 2, 1, &[]),
+        0x13 => op("SGT", 3, 2, 1, &[]),
+        0x14 => op("EQ", 3, 2, 1, &[]),
+        0x15 => op("ISZERO", 3, 1, 1, &[]),
+        0x16 => op("AND", 3, 2, 1, &[]),
+        0x17 => op("OR", 3, 2, 1, &[]),
+        0x18 => op("XOR", 3, 2, 1, &[]),
+        0x19 => op("NOT", 3, 1, 1, &[]),
+        0x1a => op("BYTE", 3, 2, 1, &[]),
+        0x1b => op("SHL", 3, 2, 1, &[]),
+        0x1c => op("SHR", 3, 2, 1, &[]),
+        0x1d => op("SAR", 3, 2, 1, &[]),
+        0x20 => op("KECCAK256", 30, 2, 1, &[DynamicCost::Keccak, DynamicCost::MemoryExpansion]),
+        0x30 => op("ADDRESS", 2, 0, 1, &[]),
+        0x31 => op("BALANCE", 100, 1, 1, &[DynamicCost::ColdAccountAccess]),
+        0x32 => op("ORIGIN", 2, 0, 1, &[]),
+        0x33 => op("CALLER", 2, 0, 1, &[]),
+        0x34 => op("CALLVALUE", 2, 0, 1, &[]),
+        0x35 => op("CALLDATALOAD", 3, 1, 1, &[]),
+        0x36 => op("CALLDATASIZE", 2, 0, 1, &[]),
+        0x37 => op("CALLDATACOPY", 3, 3, 0, &[DynamicCost::Copy, DynamicCost::MemoryExpansion]),
+        0x38 => op("CODESIZE", 2, 0, 1, &[]),
+        0x39 => op("CODECOPY", 3, 3, 0, &[DynamicCost::Copy, DynamicCost::MemoryExpansion]),
+        0x3a => op("GASPRICE", 2, 0, 1, &[]),
+        0x3b => op("EXTCODESIZE", 100, 1, 1, &[DynamicCost::ColdAccountAccess]),
+        0x3c => op("EXTCODECOPY", 100, 4, 0, &[DynamicCost::ColdAccountAccess, DynamicCost::Copy, DynamicCost::MemoryExpansion]),
+        0x3d => op("RETURNDATASIZE", 2, 0, 1, &[]),
+        0x3e => op("RETURNDATACOPY", 3, 3, 0, &[DynamicCost::Copy, DynamicCost::MemoryExpansion]),
+        0x3f => op("EXTCODEHASH", 100, 1, 1, &[DynamicCost::ColdAccountAccess]),
+        0x40 => op("BLOCKHASH", 20, 1, 1, &[]),
+        0x41 => op("COINBASE", 2, 0, 1, &[]),
+        0x42 => op("TIMESTAMP", 2, 0, 1, &[]),
+        0x43 => op("NUMBER", 2, 0, 1, &[]),
+        0x44 => op("PREVRANDAO", 2, 0, 1, &[]),
+        0x45 => op("GASLIMIT", 2, 0, 1, &[]),
+        0x46 => op("CHAINID", 2, 0, 1, &[]),
+        0x47 => op("SELFBALANCE", 5, 0, 1, &[]),
+        0x48 => op("BASEFEE", 2, 0, 1, &[]),
+        0x49 => op("BLOBHASH", 3, 1, 1, &[]),
+        0x4a => op("BLOBBASEFEE", 2, 0, 1, &[]),
+        0x50 => op("POP", 2, 1, 0, &[]),
+        0x51 => op("MLOAD", 3, 1, 1, &[DynamicCost::MemoryExpansion]),
+        0x52 => op("MSTORE", 3, 2, 0, &[DynamicCost::MemoryExpansion]),
+        0x53 => op("MSTORE8", 3, 2, 0, &[DynamicCost::MemoryExpansion]),
+        0x54 => op("SLOAD", 100, 1, 1, &[DynamicCost::ColdStorageAccess]),
+        0x55 => op("SSTORE", 100, 2, 0, &[DynamicCost::ColdStorageAccess, DynamicCost::StorageWrite]),
+        0x56 => op("JUMP", 8, 1, 0, &[]),
+        0x57 => op("JUMPI", 10, 2, 0, &[]),
+        0x58 => op("PC", 2, 0, 1, &[]),
+        0x59 => op("MSIZE", 2, 0, 1, &[]),
+        0x5a => op("GAS", 2, 0, 1, &[]),
+        0x5b => op("JUMPDEST", 1, 0, 0, &[]),
+        0x5c => op("TLOAD", 100, 1, 1, &[]),
+        0x5d => op("TSTORE", 100, 2, 0, &[]),
+        0x5e => op("MCOPY", 3, 3, 0, &[DynamicCost::Copy, DynamicCost::MemoryExpansion]),
+        0x5f => op("PUSH0", 2, 0, 1, &[]),
+        0x60..=0x7f => op(PUSH_NAMES[(opcode - 0x60) as usize], 3, 0, 1, &[]),
+        0x80..=0x8f => op(DUP_NAMES[(opcode - 0x80) as usize], 3, 0, 1, &[]),
+        0x90..=0x9f => op(SWAP_NAMES[(opcode - 0x90) as usize], 3, 0, 0, &[]),
+        0xa0..=0xa4 => {
+            let topics = (opcode - 0xa0) as usize;
+            op(LOG_NAMES[topics], 375 + 375 * topics as u64, 2 + topics, 0, &[DynamicCost::LogData, DynamicCost::MemoryExpansion])
+        }
+        0xf0 => op("CREATE", 32_000, 3, 1, &[DynamicCost::Create, DynamicCost::MemoryExpansion]),
+        0xf1 => op("CALL", 100, 7, 1, &[DynamicCost::ColdAccountAccess, DynamicCost::Call, DynamicCost::MemoryExpansion]),
+        0xf2 => op("CALLCODE", 100, 7, 1, &[DynamicCost::ColdAccountAccess, DynamicCost::Call, DynamicCost::MemoryExpansion]),
+        0xf3 => op("RETURN", 0, 2, 0, &[DynamicCost::MemoryExpansion]),
+        0xf4 => op("DELEGATECALL", 100, 6, 1, &[DynamicCost::ColdAccountAccess, DynamicCost::Call, DynamicCost::MemoryExpansion]),
+        0xf5 => op("CREATE2", 32_000, 4, 1, &[DynamicCost::Create, DynamicCost::Keccak, DynamicCost::MemoryExpansion]),
+        0xfa => op("STATICCALL", 100, 6, 1, &[DynamicCost::ColdAccountAccess, DynamicCost::Call, DynamicCost::MemoryExpansion]),
+        0xfd => op("REVERT", 0, 2, 0, &[DynamicCost::MemoryExpansion]),
+        0xfe => op("INVALID", 0, 0, 0, &[DynamicCost::ConsumesAllGas]),
+        0xff => op("SELFDESTRUCT", 5_000, 1, 0, &[DynamicCost::ColdAccountAccess]),
+        _ => None,
+    }
+}
+
+/// A disassembled instruction.
+///
+/// # Fields
+/// - `pc`: The offset of the opcode in the bytecode.
+/// - `opcode`: The opcode byte.
+/// - `name`: The mnemonic, or `UNKNOWN` for undefined opcodes.
+/// - `push_data`: The immediate bytes of a `PUSH`, which may be cut short at the end of the code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub pc: usize,
+    pub opcode: u8,
+    pub name: &'static str,
+    pub push_data: Vec<u8>,
+}
+
+impl Instruction {
+    /// Returns the static gas of the instruction; undefined opcodes behave like `INVALID`.
+    pub fn static_gas(&self) -> u64 {
+        op_info(self.opcode).map_or(0, |info| info.gas)
+    }
+
+    /// Returns the costs of the instruction that depend on runtime state.
+    pub fn dynamic_costs(&self) -> &'static [DynamicCost] {
+        op_info(self.opcode).map_or(&[DynamicCost::ConsumesAllGas], |info| info.dynamic)
+    }
+
+    /// Returns the value of a `PUSH` as an offset, or `None` if it is too large to be one.
+    fn push_value(&self) -> Option<usize> {
+        let split = self.push_data.len().saturating_sub(std::mem::size_of::<usize>());
+        let (high, low) = self.push_data.split_at(split);
+        if high.iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        Some(low.iter().fold(0, |value, byte| (value << 8) | *byte as usize))
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04x}: {}", self.pc, self.name)?;
+        if (0x60..=0x7f).contains(&self.opcode) {
+            write!(f, " 0x")?;
+            for byte in &self.push_data {
+                write!(f, "{:02x}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits bytecode into instructions.
+pub fn disassemble(code: &[u8]) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut pc = 0;
+    while pc < code.len() {
+        let opcode = code[pc];
+        let immediate = if (0x60..=0x7f).contains(&opcode) { (opcode - 0x5f) as usize } else { 0 };
+        let end = (pc + 1 + immediate).min(code.len());
+        instructions.push(Instruction {
+            pc,
+            opcode,
+            name: op_info(opcode).map_or("UNKNOWN", |info| info.name),
+            push_data: code[pc + 1..end].to_vec(),
+        });
+        pc += 1 + immediate;
+    }
+    instructions
+}
+
+/// How a basic block ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Terminator {
+    /// `STOP`, `RETURN` or `SELFDESTRUCT`: the call succeeds.
+    Success,
+    /// `REVERT`, `INVALID` or an undefined opcode: the call fails.
+    Failure,
+    /// `JUMP`: continues at the target.
+    Jump,
+    /// `JUMPI`: continues at the target or the next block.
+    ConditionalJump,
+    /// Runs into the `JUMPDEST` that starts the next block.
+    FallThrough,
+}
+
+/// A straight-line run of instructions with a single entry and a single exit.
+///
+/// # Fields
+/// - `start`: The offset of the first instruction.
+/// - `instructions`: The instructions of the block.
+/// - `static_gas`: The sum of the static gas of the instructions.
+/// - `dynamic_costs`: The runtime-dependent costs of the instructions.
+/// - `terminator`: How the block ends.
+/// - `jump_targets`: The targets of a `JUMP`/`JUMPI` pushed right before it, if known.
+#[derive(Debug, Clone)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub instructions: Vec<Instruction>,
+    pub static_gas: u64,
+    pub dynamic_costs: BTreeSet<DynamicCost>,
+    pub terminator: Terminator,
+    pub jump_targets: Vec<usize>,
+}
+
+impl BasicBlock {
+    /// Returns the offset just after the last instruction, where the fall-through block starts.
+    pub fn end(&self) -> usize {
+        self.instructions.last().map_or(self.start, |last| last.pc + 1 + last.push_data.len())
+    }
+}
+
+/// The basic blocks of a contract and the jumps between them.
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+    pub blocks: BTreeMap<usize, BasicBlock>,
+    /// Offsets of valid `JUMPDEST`s.
+    pub jump_destinations: BTreeSet<usize>,
+}
+
+impl ControlFlowGraph {
+    /// Builds the graph of disassembled code. Blocks start at `JUMPDEST`s and after jumps
+    /// and halting instructions.
+    pub fn build(instructions: &[Instruction]) -> Self {
+        let jump_destinations: BTreeSet<usize> =
+            instructions.iter().filter(|i| i.opcode == 0x5b).map(|i| i.pc).collect();
+
+        let mut blocks = BTreeMap::new();
+        let mut current: Vec<Instruction> = Vec::new();
+        for instruction in instructions {
+            if instruction.opcode == 0x5b && !current.is_empty() {
+                let block = Self::block(std::mem::take(&mut current), &jump_destinations, Terminator::FallThrough);
+                blocks.insert(block.start, block);
+            }
+            current.push(instruction.clone());
+            let terminator = match instruction.opcode {
+                0x00 | 0xf3 | 0xff => Some(Terminator::Success),
+                0xfd | 0xfe => Some(Terminator::Failure),
+                0x56 => Some(Terminator::Jump),
+                0x57 => Some(Terminator::ConditionalJump),
+                opcode if op_info(opcode).is_none() => Some(Terminator::Failure),
+                _ => None,
+            };
+            if let Some(terminator) = terminator {
+                let block = Self::block(std::mem::take(&mut current), &jump_destinations, terminator);
+                blocks.insert(block.start, block);
+            }
+        }
+        if !current.is_empty() {
+            // Running off the end of the code is an implicit STOP.
+            let block = Self::block(current, &jump_destinations, Terminator::Success);
+            blocks.insert(block.start, block);
+        }
+        Self { blocks, jump_destinations }
+    }
+
+    fn block(instructions: Vec<Instruction>, jump_destinations: &BTreeSet<usize>, terminator: Terminator) -> BasicBlock {
+        let static_gas = instructions.iter().map(Instruction::static_gas).sum();
+        let dynamic_costs = instructions.iter().flat_map(|i| i.dynamic_costs().iter().copied()).collect();
+        let jump_targets = match (terminator, instructions.len()) {
+            (Terminator::Jump | Terminator::ConditionalJump, len) if len >= 2 => Some(&instructions[len - 2])
+                .filter(|push| (0x5f..=0x7f).contains(&push.opcode))
+                .and_then(Instruction::push_value)
+                .filter(|target| jump_destinations.contains(target))
+                .into_iter()
+                .collect(),
+            _ => Vec::new(),
+        };
+        BasicBlock { start: instructions[0].pc, instructions, static_gas, dynamic_costs, terminator, jump_targets }
+    }
+}
+
+/// The static gas bounds of one external function.
+///
+/// # Fields
+/// - `selector`: The 4-byte function selector.
+/// - `entry`: The offset the dispatcher jumps to.
+/// - `dispatch_gas`: The gas spent in the dispatcher before reaching `entry`.
+/// - `min_gas`: The cheapest successful path, dispatcher included; `None` if every path reverts.
+/// - `max_gas`: The most expensive successful path; `None` if it is unbounded (loops) or
+///   could not be determined (jumps to computed targets).
+/// - `dynamic_costs`: Runtime-dependent costs on the successful paths, which come on top of the bounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionGas {
+    pub selector: [u8; 4],
+    pub entry: usize,
+    pub dispatch_gas: u64,
+    pub min_gas: Option<u64>,
+    pub max_gas: Option<u64>,
+    pub dynamic_costs: BTreeSet<DynamicCost>,
+}
+
+impl FunctionGas {
+    /// Returns the selector as `0x`-prefixed hex.
+    pub fn selector_hex(&self) -> String {
+        format!("0x{:02x}{:02x}{:02x}{:02x}", self.selector[0], self.selector[1], self.selector[2], self.selector[3])
+    }
+}
+
+/// The result of [`analyze_bytecode`].
+#[derive(Debug, Clone)]
+pub struct GasAnalysis {
+    pub instructions: Vec<Instruction>,
+    pub cfg: ControlFlowGraph,
+    /// The functions found in the dispatcher, in order of appearance.
+    pub functions: Vec<FunctionGas>,
+}
+
+impl GasAnalysis {
+    /// Returns the bounds of the function with a selector.
+    pub fn function(&self, selector: [u8; 4]) -> Result<&FunctionGas, BytecodeError> {
+        self.functions
+            .iter()
+            .find(|function| function.selector == selector)
+            .ok_or(BytecodeError::UnknownSelector)
+    }
+}
+
+/// Disassembles runtime bytecode, builds its control-flow graph and computes static gas
+/// bounds for every function of the Solidity dispatcher.
+///
+/// Only the static cost of each instruction is counted (warm access costs for `SLOAD`,
+/// calls and account access). Cold access, memory expansion, copies, hashing, logs and
+/// storage writes are listed per function as dynamic components.
+///
+/// # Arguments
+/// * `code` - The runtime bytecode of the contract.
+///
+/// # Returns
+/// Result<GasAnalysis, BytecodeError> - Returns the analysis, otherwise returns an error for empty code.
+pub fn analyze_bytecode(code: &[u8]) -> Result<GasAnalysis, BytecodeError> {
+    if code.is_empty() {
+        return Err(BytecodeError::EmptyBytecode);
+    }
+    let instructions = disassemble(code);
+    let cfg = ControlFlowGraph::build(&instructions);
+
+    let mut functions = Vec::new();
+    for (selector, entry) in dispatcher_entries(&instructions, &cfg) {
+        let dispatch_gas = Explorer::new(&cfg, Goal::Block(entry)).bounds(0).min.unwrap_or_default();
+        let bounds = Explorer::new(&cfg, Goal::Success).bounds(entry);
+        if bounds.truncated {
+            log_warn(&format!("Gave up on an exact upper bound for selector {:02x?}.", selector));
+        }
+        functions.push(FunctionGas {
+            selector,
+            entry,
+            dispatch_gas,
+            min_gas: bounds.min.map(|min| dispatch_gas + min),
+            max_gas: bounds.max.filter(|_| !bounds.truncated).map(|max| dispatch_gas + max),
+            dynamic_costs: bounds.dynamic_costs,
+        });
+    }
+    log_info(&format!(
+        "Analyzed {} instructions in {} blocks; found {} functions.",
+        instructions.len(),
+        cfg.blocks.len(),
+        functions.len()
+    ));
+    Ok(GasAnalysis { instructions, cfg, functions })
+}
+
+/// Finds the `PUSH4 selector (DUP) EQ PUSH target JUMPI` checks of a Solidity dispatcher.
+fn dispatcher_entries(instructions: &[Instruction], cfg: &ControlFlowGraph) -> Vec<([u8; 4], usize)> {
+    let mut entries = Vec::new();
+    for (index, instruction) in instructions.iter().enumerate() {
+        if instruction.opcode != 0x63 || instruction.push_data.len() != 4 {
+            continue;
+        }
+        let eq = match instructions.get(index + 1..index + 3) {
+            Some([next, ..]) if next.opcode == 0x14 => index + 1,
+            Some([next, after]) if (0x80..=0x8f).contains(&next.opcode) && after.opcode == 0x14 => index + 2,
+            _ => continue,
+        };
+        if let Some([push, jumpi]) = instructions.get(eq + 1..eq + 3) {
+            if (0x60..=0x7f).contains(&push.opcode) && jumpi.opcode == 0x57 {
+                if let Some(target) = push.push_value().filter(|t| cfg.jump_destinations.contains(t)) {
+                    let selector = [
+                        instruction.push_data[0],
+                        instruction.push_data[1],
+                        instruction.push_data[2],
+                        instruction.push_data[3],
+                    ];
+                    if !entries.iter().any(|(known, _)| *known == selector) {
+                        entries.push((selector, target));
+                    }
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// A stack slot during exploration: a constant that may be a jump target, or anything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Slot {
+    Known(usize),
+    Unknown,
+}
+
+/// Where an exploration ends.
+#[derive(Debug, Clone, Copy)]
+enum Goal {
+    /// A successful halt; the cost of the halting block is included.
+    Success,
+    /// The start of a block; its cost is not included.
+    Block(usize),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Bounds {
+    min: Option<u64>,
+    max: Option<u64>,
+    /// A loop or an unresolved jump was found, so `max` is not an upper bound.
+    unbounded: bool,
+    /// The state budget ran out.
+    truncated: bool,
+    dynamic_costs: BTreeSet<DynamicCost>,
+}
+
+/// Walks the paths of the graph, tracking which stack slots hold constants so that jumps to
+/// pushed return addresses (internal function calls) resolve to the right block.
+///
+/// Paths are walked depth-first with an explicit stack of [`Frame`]s rather than recursion, so
+/// long paths cannot overflow the thread's stack.
+struct Explorer<'a> {
+    cfg: &'a ControlFlowGraph,
+    goal: Goal,
+    memo: HashMap<(usize, Vec<Slot>), Bounds>,
+    on_path: HashSet<(usize, Vec<Slot>)>,
+    visited: usize,
+}
+
+/// A block on the current path, waiting for the bounds of its successors.
+struct Frame {
+    key: (usize, Vec<Slot>),
+    /// The tracked stack after the block's instructions.
+    stack: Vec<Slot>,
+    successors: Vec<usize>,
+    next: usize,
+    bounds: Bounds,
+}
+
+/// The outcome of entering a block: known bounds, or a frame whose successors need exploring.
+enum Visit {
+    Done(Bounds),
+    Block(Frame),
+}
+
+impl<'a> Explorer<'a> {
+    fn new(cfg: &'a ControlFlowGraph, goal: Goal) -> Self {
+        Self { cfg, goal, memo: HashMap::new(), on_path: HashSet::new(), visited: 0 }
+    }
+
+    fn bounds(&mut self, start: usize) -> Bounds {
+        let mut frames = Vec::new();
+        let mut result = match self.enter(start, Vec::new()) {
+            Visit::Done(bounds) => Some(bounds),
+            Visit::Block(frame) => {
+                frames.push(frame);
+                None
+            }
+        };
+
+        while let Some(frame) = frames.last_mut() {
+            if let Some(next) = result.take() {
+                combine(&mut frame.bounds, next);
+            }
+            if frame.next < frame.successors.len() {
+                let successor = frame.successors[frame.next];
+                frame.next += 1;
+                let stack = frame.stack.clone();
+                match self.enter(successor, stack) {
+                    Visit::Done(bounds) => result = Some(bounds),
+                    Visit::Block(frame) => frames.push(frame),
+                }
+            } else {
+                let frame = frames.pop().expect("frame on the path");
+                result = Some(self.finish(frame));
+            }
+        }
+        result.unwrap_or_default()
+    }
+
+    /// Runs a block on the tracked stack, returning its bounds directly when they are known.
+    fn enter(&mut self, start: usize, stack: Vec<Slot>) -> Visit {
+        if let Goal::Block(target) = self.goal {
+            if start == target {
+                return Visit::Done(Bounds { min: Some(0), max: Some(0), ..Default::default() });
+            }
+        }
+        let block = match self.cfg.blocks.get(&start) {
+            Some(block) => block,
+            None => return Visit::Done(Bounds::default()),
+        };
+        let key = (start, stack);
+        if let Some(bounds) = self.memo.get(&key) {
+            return Visit::Done(bounds.clone());
+        }
+        if self.on_path.contains(&key) {
+            // A loop: the path can repeat any number of times.
+            return Visit::Done(Bounds { unbounded: true, ..Default::default() });
+        }
+        self.visited += 1;
+        if self.visited > MAX_STATES {
+            return Visit::Done(Bounds { truncated: true, ..Default::default() });
+        }
+
+        let mut stack = key.1.clone();
+        let mut jump_target = None;
+        for instruction in &block.instructions {
+            jump_target = step(instruction, &mut stack);
+        }
+        if stack.len() > STACK_LIMIT {
+            // The stack overflows, so the path fails.
+            return Visit::Done(Bounds::default());
+        }
+
+        let mut successors = Vec::new();
+        let mut unresolved = false;
+        match block.terminator {
+            Terminator::Success | Terminator::Failure => {}
+            Terminator::Jump | Terminator::ConditionalJump => {
+                match jump_target {
+                    Some(Slot::Known(target)) if self.cfg.jump_destinations.contains(&target) => successors.push(target),
+                    Some(Slot::Known(_)) => {}
+                    _ => unresolved = true,
+                }
+                if block.terminator == Terminator::ConditionalJump {
+                    successors.push(block.end());
+                }
+            }
+            Terminator::FallThrough => successors.push(block.end()),
+        }
+
+        let mut bounds = match (block.terminator, self.goal) {
+            (Terminator::Success, Goal::Success) => Bounds { min: Some(0), max: Some(0), ..Default::default() },
+            _ => Bounds::default(),
+        };
+        bounds.unbounded = unresolved;
+
+        self.on_path.insert(key.clone());
+        Visit::Block(Frame { key, stack, successors, next: 0, bounds })
+    }
+
+    /// Adds the cost of a block to the combined bounds of its successors.
+    fn finish(&mut self, frame: Frame) -> Bounds {
+        let Frame { key, mut bounds, .. } = frame;
+        self.on_path.remove(&key);
+        let block = &self.cfg.blocks[&key.0];
+
+        if bounds.min.is_some() {
+            bounds.min = bounds.min.map(|min| min + block.static_gas);
+            bounds.max = bounds.max.map(|max| max + block.static_gas);
+            bounds.dynamic_costs.extend(block.dynamic_costs.iter().copied());
+        }
+        if bounds.unbounded {
+            bounds.max = None;
+        }
+        if !bounds.unbounded && !bounds.truncated {
+            self.memo.insert(key, bounds.clone());
+        }
+        bounds
+    }
+}
+
+/// Merges the bounds of one successor into the bounds of its block.
+fn combine(bounds: &mut Bounds, next: Bounds) {
+    bounds.unbounded |= next.unbounded;
+    bounds.truncated |= next.truncated;
+    if let Some(min) = next.min {
+        bounds.min = Some(bounds.min.map_or(min, |current| current.min(min)));
+        bounds.max = Some(bounds.max.map_or(next.max.unwrap_or(min), |current| current.max(next.max.unwrap_or(min))));
+        bounds.dynamic_costs.extend(next.dynamic_costs);
+    }
+}
+
+/// Applies an instruction to the tracked stack. Returns the popped jump target for jumps.
+fn step(instruction: &Instruction, stack: &mut Vec<Slot>) -> Option<Slot> {
+    let pop = |stack: &mut Vec<Slot>| stack.pop().unwrap_or(Slot::Unknown);
+    match instruction.opcode {
+        0x5f..=0x7f => {
+            let value = instruction.push_value().map_or(Slot::Unknown, Slot::Known);
+            stack.push(value);
+            None
+        }
+        0x80..=0x8f => {
+            let depth = (instruction.opcode - 0x80) as usize + 1;
+            let value = stack.len().checked_sub(depth).map_or(Slot::Unknown, |index| stack[index]);
+            stack.push(value);
+            None
+        }
+        0x90..=0x9f => {
+            let depth = (instruction.opcode - 0x90) as usize + 1;
+            if let Some(index) = stack.len().checked_sub(depth + 1) {
+                let top = stack.len() - 1;
+                stack.swap(index, top);
+            } else if let Some(top) = stack.last_mut() {
+                // The other slot is below what is tracked.
+                *top = Slot::Unknown;
+            }
+            None
+        }
+        0x56 | 0x57 => {
+            let target = pop(stack);
+            if instruction.opcode == 0x57 {
+                pop(stack);
+            }
+            Some(target)
+        }
+        opcode => {
+            let (pops, pushes) = op_info(opcode).map_or((0, 0), |info| (info.pops, info.pushes));
+            for _ in 0..pops {
+                pop(stack);
+            }
+            stack.extend(std::iter::repeat_n(Slot::Unknown, pushes));
+            None
+        }
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORE: [u8; 4] = [0xaa, 0xbb, 0xcc, 0xdd];
+    const CALL_TWICE: [u8; 4] = [0x11, 0x22, 0x33, 0x44];
+    const LOOP: [u8; 4] = [0x55, 0x66, 0x77, 0x88];
+
+    /// A contract with a Solidity-style dispatcher and three functions: one storing a
+    /// constant, one calling an internal function twice, and one looping.
+    fn contract() -> Vec<u8> {
+        vec![
+            0x60, 0x00, 0x35, 0x60, 0xe0, 0x1c, // 0x00: selector = calldata[0..4] >> 224
+            0x80, 0x63, 0xaa, 0xbb, 0xcc, 0xdd, 0x14, 0x60, 0x27, 0x57, // 0x06: DUP1 PUSH4 EQ PUSH1 JUMPI
+            0x80, 0x63, 0x11, 0x22, 0x33, 0x44, 0x14, 0x60, 0x2e, 0x57, // 0x10
+            0x63, 0x55, 0x66, 0x77, 0x88, 0x81, 0x14, 0x60, 0x41, 0x57, // 0x1a: PUSH4 DUP2 EQ PUSH1 JUMPI
+            0x5f, 0x80, 0xfd, // 0x24: revert(0, 0)
+            0x5b, 0x60, 0x2a, 0x60, 0x00, 0x55, 0x00, // 0x27: sstore(0, 42)
+            0x5b, 0x60, 0x34, 0x60, 0x3c, 0x56, // 0x2e: call 0x3c, returning to 0x34
+            0x5b, 0x60, 0x3a, 0x60, 0x3c, 0x56, // 0x34: call 0x3c, returning to 0x3a
+            0x5b, 0x00, // 0x3a: stop
+            0x5b, 0x5f, 0x54, 0x50, 0x56, // 0x3c: sload(0), return
+            0x5b, 0x5f, 0x35, 0x60, 0x41, 0x57, // 0x41: loop while calldata[0..32] != 0
+            0x00, // 0x47: stop
+        ]
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let instructions = disassemble(&[0x60, 0x80, 0x60, 0x40, 0x52, 0x7f, 0x01]);
+        assert_eq!(instructions.len(), 4);
+        assert_eq!(instructions[0].to_string(), "0000: PUSH1 0x80");
+        assert_eq!(instructions[2].name, "MSTORE");
+        // A PUSH32 cut short by the end of the code.
+        assert_eq!(instructions[3].push_data, vec![0x01]);
+        assert_eq!(instructions[3].static_gas(), 3);
+    }
+
+    #[test]
+    fn test_control_flow_graph() {
+        let cfg = ControlFlowGraph::build(&disassemble(&contract()));
+        let starts: Vec<usize> = cfg.blocks.keys().copied().collect();
+        assert_eq!(starts, vec![0x00, 0x10, 0x1a, 0x24, 0x27, 0x2e, 0x34, 0x3a, 0x3c, 0x41, 0x47]);
+        assert_eq!(cfg.blocks[&0x00].static_gas, 34);
+        assert_eq!(cfg.blocks[&0x00].jump_targets, vec![0x27]);
+        assert_eq!(cfg.blocks[&0x24].terminator, Terminator::Failure);
+        // The internal function returns to an address taken from the stack.
+        assert_eq!(cfg.blocks[&0x3c].terminator, Terminator::Jump);
+        assert!(cfg.blocks[&0x3c].jump_targets.is_empty());
+    }
+
+    #[test]
+    fn test_function_bounds() {
+        let analysis = analyze_bytecode(&contract()).unwrap();
+        assert_eq!(analysis.functions.len(), 3);
+
+        let store = analysis.function(STORE).unwrap();
+        assert_eq!(store.selector_hex(), "0xaabbccdd");
+        assert_eq!(store.entry, 0x27);
+        assert_eq!(store.dispatch_gas, 34);
+        assert_eq!(store.min_gas, Some(141));
+        assert_eq!(store.max_gas, Some(141));
+        assert!(store.dynamic_costs.contains(&DynamicCost::ColdStorageAccess));
+        assert!(store.dynamic_costs.contains(&DynamicCost::StorageWrite));
+
+        // Both calls return to their own call site rather than forming a loop.
+        let call_twice = analysis.function(CALL_TWICE).unwrap();
+        assert_eq!(call_twice.dispatch_gas, 56);
+        assert_eq!(call_twice.min_gas, Some(313));
+        assert_eq!(call_twice.max_gas, Some(313));
+        assert!(!call_twice.dynamic_costs.contains(&DynamicCost::StorageWrite));
+
+        let looping = analysis.function(LOOP).unwrap();
+        assert_eq!(looping.min_gas, Some(97));
+        assert_eq!(looping.max_gas, None);
+
+        assert!(matches!(analysis.function([0; 4]), Err(BytecodeError::UnknownSelector)));
+    }
+
+    #[test]
+    fn test_stack_growing_loop() {
+        // The function pushes a value on every iteration until the stack overflows, so every
+        // state is new; the walk must end at the stack limit instead of exhausting the thread.
+        let code = [0x63, 0xaa, 0xbb, 0xcc, 0xdd, 0x14, 0x60, 0x0a, 0x57, 0x00, 0x5b, 0x5f, 0x60, 0x0a, 0x56];
+        let analysis = analyze_bytecode(&code).unwrap();
+        let function = analysis.function(STORE).unwrap();
+        assert_eq!(function.entry, 0x0a);
+        assert_eq!(function.min_gas, None);
+        assert_eq!(function.max_gas, None);
+    }
+
+    #[test]
+    fn test_empty_bytecode() {
+        assert!(matches!(analyze_bytecode(&[]), Err(BytecodeError::EmptyBytecode)));
+    }
+}
@@ -1,12 +1,93 @@
 use crate::framework::logging::{log_info, log_error};
-use web3::types::Address;
+use crate::error::Error;
+use crate::contracts::abi::{encode_call, to_hex, AbiFunction};
+use crate::contracts::gas::{build_typed_transaction, GasOptimizationError, TypedTransactionOptions};
+use crate::contracts::nonce::{NonceError, NonceManager};
+use crate::contracts::offline::{prepare_transaction, OfflineError, OfflineTransaction};
+use crate::contracts::proposal::{SafeTransaction, TimelockOperation};
+use web3::ethabi::{decode, ParamType, Token};
+use web3::types::{Address, Bytes, CallRequest, TransactionRequest, H256, U256};
+use web3::{Transport, Web3};
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
+use std::fmt;
 
 /// Errors that can occur during contract updates.
 #[derive(Debug)]
 pub enum UpdateError {
     InvalidAddress,
     UpdateFailed,
+    SelectorClash,
+    FacetQueryFailed,
+    /// The planned cut would remove the `diamondCut` selector and lock the diamond.
+    CutRemoval,
+    /// The node rejected the upgrade transaction.
+    Rejected(Box<Error>),
+    /// Preparing the upgrade for offline signing failed.
+    Offline(OfflineError),
+    /// Filling in the fees of the upgrade failed.
+    Gas(GasOptimizationError),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::InvalidAddress => write!(f, "invalid address"),
+            UpdateError::UpdateFailed => write!(f, "update failed"),
+            UpdateError::SelectorClash => write!(f, "a function selector is provided by more than one facet"),
+            UpdateError::FacetQueryFailed => write!(f, "failed to read the facets of the diamond"),
+            UpdateError::CutRemoval => write!(f, "the cut would remove the diamondCut function"),
+            UpdateError::Rejected(error) => write!(f, "upgrade rejected: {}", error),
+            UpdateError::Offline(error) => write!(f, "offline preparation failed: {}", error),
+            UpdateError::Gas(error) => write!(f, "fee calculation failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UpdateError::Rejected(error) => Some(error.as_ref()),
+            UpdateError::Offline(error) => Some(error),
+            UpdateError::Gas(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// How an upgrade is handed to the account that owns the proxy.
+///
+/// Proxies owned by a multisig or a timelock cannot be upgraded directly; these modes
+/// produce a proposal payload that a signer submits elsewhere.
+#[derive(Debug, Clone)]
+pub enum ProposalMode {
+    /// The proxy admin is a Safe, which calls the proxy directly.
+    Safe { safe: Address, chain_id: U256, nonce: U256 },
+    /// The proxy admin is a `TimelockController`.
+    Timelock { timelock: Address, predecessor: H256, salt: H256, delay: U256 },
+    /// The proxy admin is a timelock whose proposer and executor is a Safe.
+    /// `nonce` is used for the `schedule` transaction and `nonce + 1` for `execute`.
+    SafeTimelock {
+        safe: Address,
+        chain_id: U256,
+        nonce: U256,
+        timelock: Address,
+        predecessor: H256,
+        salt: H256,
+        delay: U256,
+    },
+}
+
+/// An upgrade proposal produced instead of executing the upgrade.
+#[derive(Debug, Clone)]
+pub enum UpgradeProposal {
+    Safe(SafeTransaction),
+    Timelock(TimelockOperation),
+    SafeTimelock {
+        operation: TimelockOperation,
+        schedule: Box<SafeTransaction>,
+        execute: Box<SafeTransaction>,
+    },
 }
 
 /// Updates a smart contract with security checks and error handling.
@@ -45,6 +126,424 @@ pub async fn update_contract(
     }
 }
 
+/// Returns the target and calldata of the call that upgrades a proxy.
+fn upgrade_call(
+    proxy_address: &str,
+    new_implementation: &str,
+    proxy_admin: Option<&str>,
+) -> Result<(Address, Vec<u8>), UpdateError> {
+    let proxy = Address::from_str(proxy_address).map_err(|_| UpdateError::InvalidAddress)?;
+    let implementation = Address::from_str(new_implementation).map_err(|_| UpdateError::InvalidAddress)?;
+
+    match proxy_admin {
+        Some(admin) => Ok((
+            Address::from_str(admin).map_err(|_| UpdateError::InvalidAddress)?,
+            encode_call(
+                "upgrade(address,address)",
+                &[Token::Address(proxy), Token::Address(implementation)],
+            ),
+        )),
+        None => Ok((proxy, encode_call("upgradeTo(address)", &[Token::Address(implementation)]))),
+    }
+}
+
+/// Upgrades a proxy directly from a node-managed admin account, taking the nonce from a shared manager.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `proxy_address` - The address of the upgradeable proxy.
+/// * `new_implementation` - The address of the already deployed implementation.
+/// * `proxy_admin` - The `ProxyAdmin` contract for transparent proxies, if any.
+/// * `sender_address` - The admin account sending the upgrade.
+/// * `options` - The transaction type and gas strategy; the node picks the fees when `None`.
+///
+/// # Returns
+/// Result<H256, UpdateError> - Returns the transaction hash, otherwise returns an error.
+pub async fn send_upgrade<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    proxy_address: &str,
+    new_implementation: &str,
+    proxy_admin: Option<&str>,
+    sender_address: &str,
+    options: Option<&TypedTransactionOptions>,
+) -> Result<H256, UpdateError> {
+    let mut transaction = upgrade_request(proxy_address, new_implementation, proxy_admin, sender_address)?;
+    if let Some(options) = options {
+        transaction = build_typed_transaction(web3, transaction, options).await.map_err(UpdateError::Gas)?;
+    }
+
+    log_info(&format!("Updating contract at address: {}", proxy_address));
+
+    nonces.send_transaction(web3, transaction).await.map_err(|e| {
+        log_error("Contract update failed.");
+        match e {
+            NonceError::Rejected(error) => UpdateError::Rejected(error),
+            _ => UpdateError::UpdateFailed,
+        }
+    })
+}
+
+/// Prepares a proxy upgrade for offline signing instead of sending it.
+///
+/// # Arguments
+/// * `web3` - The connection to the node, used only to read chain state.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `proxy_address` - The address of the proxy to upgrade.
+/// * `new_implementation` - The address of the new implementation contract.
+/// * `proxy_admin` - The `ProxyAdmin` contract that owns the proxy, if any.
+/// * `sender_address` - The address sending the upgrade.
+/// * `options` - The transaction type and fee options.
+///
+/// # Returns
+/// Result<OfflineTransaction, UpdateError> - Returns the unsigned transaction, otherwise returns an error.
+pub async fn prepare_upgrade<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    proxy_address: &str,
+    new_implementation: &str,
+    proxy_admin: Option<&str>,
+    sender_address: &str,
+    options: &TypedTransactionOptions,
+) -> Result<OfflineTransaction, UpdateError> {
+    let transaction = upgrade_request(proxy_address, new_implementation, proxy_admin, sender_address)?;
+    let description = format!("Upgrade proxy {} to implementation {}", proxy_address, new_implementation);
+    prepare_transaction(web3, nonces, transaction, options, &description)
+        .await
+        .map_err(UpdateError::Offline)
+}
+
+fn upgrade_request(
+    proxy_address: &str,
+    new_implementation: &str,
+    proxy_admin: Option<&str>,
+    sender_address: &str,
+) -> Result<TransactionRequest, UpdateError> {
+    let sender = Address::from_str(sender_address).map_err(|_| UpdateError::InvalidAddress)?;
+    let (target, data) = upgrade_call(proxy_address, new_implementation, proxy_admin)?;
+
+    Ok(TransactionRequest {
+        from: sender,
+        to: Some(target),
+        data: Some(Bytes(data)),
+        ..Default::default()
+    })
+}
+
+/// Builds an upgrade proposal for a proxy instead of executing the upgrade.
+///
+/// # Arguments
+/// * `proxy_address` - The address of the upgradeable proxy.
+/// * `new_implementation` - The address of the already deployed implementation.
+/// * `proxy_admin` - The `ProxyAdmin` contract for transparent proxies, if any. When set,
+///   the proposal calls `ProxyAdmin.upgrade(proxy, implementation)`, otherwise `proxy.upgradeTo(implementation)`.
+/// * `mode` - The multisig and/or timelock setup that owns the proxy.
+///
+/// # Returns
+/// Result<UpgradeProposal, UpdateError> - Returns the proposal payload, otherwise returns an error.
+pub fn propose_upgrade(
+    proxy_address: &str,
+    new_implementation: &str,
+    proxy_admin: Option<&str>,
+    mode: &ProposalMode,
+) -> Result<UpgradeProposal, UpdateError> {
+    let (target, data) = upgrade_call(proxy_address, new_implementation, proxy_admin)?;
+
+    log_info(&format!(
+        "Building upgrade proposal for proxy {} to implementation {}",
+        proxy_address, new_implementation
+    ));
+
+    let proposal = match mode {
+        ProposalMode::Safe { safe, chain_id, nonce } => {
+            UpgradeProposal::Safe(SafeTransaction::new(*safe, *chain_id, target, data, *nonce))
+        }
+        ProposalMode::Timelock { timelock, predecessor, salt, delay } => {
+            UpgradeProposal::Timelock(TimelockOperation {
+                timelock: *timelock,
+                target,
+                value: U256::zero(),
+                data,
+                predecessor: *predecessor,
+                salt: *salt,
+                delay: *delay,
+            })
+        }
+        ProposalMode::SafeTimelock { safe, chain_id, nonce, timelock, predecessor, salt, delay } => {
+            let operation = TimelockOperation {
+                timelock: *timelock,
+                target,
+                value: U256::zero(),
+                data,
+                predecessor: *predecessor,
+                salt: *salt,
+                delay: *delay,
+            };
+            let schedule = SafeTransaction::new(*safe, *chain_id, *timelock, operation.schedule_calldata(), *nonce);
+            let execute = SafeTransaction::new(
+                *safe,
+                *chain_id,
+                *timelock,
+                operation.execute_calldata(),
+                *nonce + U256::one(),
+            );
+            UpgradeProposal::SafeTimelock { operation, schedule: Box::new(schedule), execute: Box::new(execute) }
+        }
+    };
+
+    Ok(proposal)
+}
+
+/// The function a diamond exposes to apply facet cuts.
+const DIAMOND_CUT_SIGNATURE: &str = "diamondCut((address,uint8,bytes4[])[],address,bytes)";
+
+/// A facet currently registered in an EIP-2535 diamond, as reported by `facets()`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Facet {
+    pub address: Address,
+    pub selectors: Vec<[u8; 4]>,
+}
+
+/// A deployed facet the diamond should route to after the upgrade.
+///
+/// # Fields
+/// - `name`: A human-readable name used in log messages.
+/// - `address`: The address the facet is deployed at.
+/// - `functions`: The ABI functions the facet exposes through the diamond.
+#[derive(Debug, Clone)]
+pub struct FacetArtifact {
+    pub name: String,
+    pub address: Address,
+    pub functions: Vec<AbiFunction>,
+}
+
+/// The `FacetCutAction` enum of EIP-2535.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FacetCutAction {
+    Add = 0,
+    Replace = 1,
+    Remove = 2,
+}
+
+/// A single entry of the `diamondCut` argument.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetCut {
+    pub facet_address: Address,
+    pub action: FacetCutAction,
+    pub selectors: Vec<[u8; 4]>,
+}
+
+/// The cuts needed to move a diamond from its current facet set to a target set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiamondCut {
+    pub diamond: Address,
+    pub cuts: Vec<FacetCut>,
+}
+
+impl DiamondCut {
+    /// Returns `true` when the diamond already matches the target facets.
+    pub fn is_empty(&self) -> bool {
+        self.cuts.is_empty()
+    }
+
+    /// Returns the calldata for `diamondCut((address,uint8,bytes4[])[],address,bytes)`.
+    ///
+    /// # Arguments
+    /// * `init` - The contract `delegatecall`ed after the cut, or zero for none.
+    /// * `init_calldata` - The calldata passed to `init`.
+    pub fn calldata(&self, init: Address, init_calldata: Vec<u8>) -> Vec<u8> {
+        let cuts = self
+            .cuts
+            .iter()
+            .map(|cut| {
+                Token::Tuple(vec![
+                    Token::Address(cut.facet_address),
+                    Token::Uint(U256::from(cut.action as u8)),
+                    Token::Array(cut.selectors.iter().map(|s| Token::FixedBytes(s.to_vec())).collect()),
+                ])
+            })
+            .collect();
+
+        encode_call(
+            DIAMOND_CUT_SIGNATURE,
+            &[Token::Array(cuts), Token::Address(init), Token::Bytes(init_calldata)],
+        )
+    }
+
+    /// Builds the transaction that applies the cut, sent by the diamond owner.
+    pub fn transaction(&self, from: Address, init: Address, init_calldata: Vec<u8>) -> TransactionRequest {
+        TransactionRequest {
+            from,
+            to: Some(self.diamond),
+            data: Some(Bytes(self.calldata(init, init_calldata))),
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads the current facets of a diamond through the loupe `facets()` function.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `diamond_address` - The address of the diamond.
+///
+/// # Returns
+/// Result<Vec<Facet>, UpdateError> - Returns the registered facets, otherwise returns an error.
+pub async fn read_diamond_facets<T: Transport>(
+    web3: &Web3<T>,
+    diamond_address: &str,
+) -> Result<Vec<Facet>, UpdateError> {
+    let diamond = Address::from_str(diamond_address).map_err(|_| UpdateError::InvalidAddress)?;
+
+    let request = CallRequest {
+        to: Some(diamond),
+        data: Some(Bytes(encode_call("facets()", &[]))),
+        ..Default::default()
+    };
+    let output = web3.eth().call(request, None).await.map_err(|e| {
+        log_error(&format!("Loupe call to {} failed: {}", diamond_address, e));
+        UpdateError::FacetQueryFailed
+    })?;
+
+    let facet_type = ParamType::Array(Box::new(ParamType::Tuple(vec![
+        ParamType::Address,
+        ParamType::Array(Box::new(ParamType::FixedBytes(4))),
+    ])));
+    let tokens = decode(&[facet_type], &output.0).map_err(|_| UpdateError::FacetQueryFailed)?;
+
+    let facets = match tokens.into_iter().next() {
+        Some(Token::Array(entries)) => entries
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Token::Tuple(fields) => match (fields.first(), fields.get(1)) {
+                    (Some(Token::Address(address)), Some(Token::Array(selectors))) => Some(Facet {
+                        address: *address,
+                        selectors: selectors
+                            .iter()
+                            .filter_map(|s| match s {
+                                Token::FixedBytes(bytes) if bytes.len() == 4 => {
+                                    Some([bytes[0], bytes[1], bytes[2], bytes[3]])
+                                }
+                                _ => None,
+                            })
+                            .collect(),
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => return Err(UpdateError::FacetQueryFailed),
+    };
+
+    Ok(facets)
+}
+
+/// Computes the `diamondCut` that moves a diamond from its current facets to the target facets.
+///
+/// Selectors routed to the diamond itself are immutable functions and are left untouched.
+/// Removing the `diamondCut` selector makes the diamond impossible to upgrade again, so it
+/// is refused unless `allow_cut_removal` is set.
+///
+/// # Arguments
+/// * `diamond` - The address of the diamond.
+/// * `current` - The facets currently registered, see [`read_diamond_facets`].
+/// * `target` - The facets the diamond should route to afterwards.
+/// * `allow_cut_removal` - Whether the cut may remove the `diamondCut` selector.
+///
+/// # Returns
+/// Result<DiamondCut, UpdateError> - Returns the cut, `SelectorClash` if two target
+/// functions share a selector, or `CutRemoval` if the cut would remove `diamondCut`.
+pub fn plan_diamond_cut(
+    diamond: Address,
+    current: &[Facet],
+    target: &[FacetArtifact],
+    allow_cut_removal: bool,
+) -> Result<DiamondCut, UpdateError> {
+    // Selector -> (facet name, function signature, facet address) for the target set.
+    let mut wanted: BTreeMap<[u8; 4], (&str, String, Address)> = BTreeMap::new();
+    for artifact in target {
+        for function in &artifact.functions {
+            let signature = function.signature();
+            if let Some((other_facet, other_signature, _)) = wanted.get(&function.selector()) {
+                log_error(&format!(
+                    "Selector clash: {} in facet {} and {} in facet {} share selector 0x{}",
+                    signature,
+                    artifact.name,
+                    other_signature,
+                    other_facet,
+                    to_hex(&function.selector())
+                ));
+                return Err(UpdateError::SelectorClash);
+            }
+            wanted.insert(function.selector(), (&artifact.name, signature, artifact.address));
+        }
+    }
+
+    let installed: HashMap<[u8; 4], Address> = current
+        .iter()
+        .flat_map(|facet| facet.selectors.iter().map(move |s| (*s, facet.address)))
+        .collect();
+
+    let mut grouped: BTreeMap<(FacetCutAction, Address), Vec<[u8; 4]>> = BTreeMap::new();
+    for (selector, (_, _, address)) in &wanted {
+        match installed.get(selector) {
+            None => grouped.entry((FacetCutAction::Add, *address)).or_default().push(*selector),
+            Some(existing) if *existing == diamond => {
+                log_error(&format!("Selector 0x{} is immutable in the diamond.", to_hex(selector)));
+                return Err(UpdateError::SelectorClash);
+            }
+            Some(existing) if existing != address => {
+                grouped.entry((FacetCutAction::Replace, *address)).or_default().push(*selector)
+            }
+            Some(_) => {}
+        }
+    }
+    let cut_selector = &encode_call(DIAMOND_CUT_SIGNATURE, &[])[..4];
+    for (selector, address) in &installed {
+        if !wanted.contains_key(selector) && *address != diamond {
+            if selector == cut_selector && !allow_cut_removal {
+                log_error("Refusing to remove the diamondCut selector from the diamond.");
+                return Err(UpdateError::CutRemoval);
+            }
+            grouped.entry((FacetCutAction::Remove, Address::zero())).or_default().push(*selector);
+        }
+    }
+
+    let cuts = grouped
+        .into_iter()
+        .map(|((action, facet_address), mut selectors)| {
+            selectors.sort();
+            FacetCut { facet_address, action, selectors }
+        })
+        .collect::<Vec<_>>();
+
+    log_info(&format!("Planned diamond cut with {} facet cut(s).", cuts.len()));
+    Ok(DiamondCut { diamond, cuts })
+}
+
+/// Reads the current facets of a diamond and computes the cut needed to reach the target facets.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `diamond_address` - The address of the diamond.
+/// * `target` - The facets the diamond should route to afterwards.
+/// * `allow_cut_removal` - Whether the cut may remove the `diamondCut` selector.
+///
+/// # Returns
+/// Result<DiamondCut, UpdateError> - Returns the cut, otherwise returns an error.
+pub async fn update_diamond<T: Transport>(
+    web3: &Web3<T>,
+    diamond_address: &str,
+    target: &[FacetArtifact],
+    allow_cut_removal: bool,
+) -> Result<DiamondCut, UpdateError> {
+    let diamond = Address::from_str(diamond_address).map_err(|_| UpdateError::InvalidAddress)?;
+    let current = read_diamond_facets(web3, diamond_address).await?;
+    plan_diamond_cut(diamond, &current, target, allow_cut_removal)
+}
+
 // Unit test example
 #[cfg(test)]
 mod tests {
@@ -67,4 +566,135 @@ mod tests {
         let result = update_contract("0x1234567890abcdef1234567890abcdef12345678", &[0x60, 0x80, 0x60, 0x40]).await;
         assert!(matches!(result, Ok(())));
     }
+
+    #[test]
+    fn test_propose_upgrade_invalid_implementation() {
+        let mode = ProposalMode::Safe { safe: Address::zero(), chain_id: U256::one(), nonce: U256::zero() };
+        let result = propose_upgrade("0x1234567890abcdef1234567890abcdef12345678", "invalid", None, &mode);
+        assert!(matches!(result, Err(UpdateError::InvalidAddress)));
+    }
+
+    #[test]
+    fn test_propose_upgrade_safe() {
+        let mode = ProposalMode::Safe { safe: Address::repeat_byte(1), chain_id: U256::one(), nonce: U256::from(3) };
+        let result = propose_upgrade(
+            "0x1234567890abcdef1234567890abcdef12345678",
+            "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd",
+            None,
+            &mode,
+        );
+        match result {
+            Ok(UpgradeProposal::Safe(transaction)) => {
+                assert_eq!(transaction.data[..4], [0x36, 0x59, 0xcf, 0xe6]);
+                assert_eq!(transaction.nonce, U256::from(3));
+            }
+            other => panic!("unexpected proposal: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_propose_upgrade_safe_timelock() {
+        let mode = ProposalMode::SafeTimelock {
+            safe: Address::repeat_byte(1),
+            chain_id: U256::one(),
+            nonce: U256::from(3),
+            timelock: Address::repeat_byte(2),
+            predecessor: H256::zero(),
+            salt: H256::zero(),
+            delay: U256::from(86_400),
+        };
+        let result = propose_upgrade(
+            "0x1234567890abcdef1234567890abcdef12345678",
+            "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd",
+            Some("0x000000000000000000000000000000000000beef"),
+            &mode,
+        );
+        match result {
+            Ok(UpgradeProposal::SafeTimelock { operation, schedule, execute }) => {
+                assert_eq!(operation.data[..4], [0x99, 0xa8, 0x8e, 0xc4]);
+                assert_eq!(schedule.to, Address::repeat_byte(2));
+                assert_eq!(schedule.data, operation.schedule_calldata());
+                assert_eq!(execute.nonce, U256::from(4));
+            }
+            other => panic!("unexpected proposal: {:?}", other),
+        }
+    }
+
+    fn function(name: &str) -> AbiFunction {
+        AbiFunction {
+            name: name.to_string(),
+            inputs: vec![],
+            outputs: vec![],
+            payable: false,
+            constant: false,
+        }
+    }
+
+    #[test]
+    fn test_plan_diamond_cut() {
+        let old_facet = Address::repeat_byte(1);
+        let new_facet = Address::repeat_byte(2);
+        let kept_facet = Address::repeat_byte(3);
+        let current = vec![
+            Facet { address: old_facet, selectors: vec![function("a").selector(), function("b").selector()] },
+            Facet { address: kept_facet, selectors: vec![function("c").selector()] },
+        ];
+        let target = vec![
+            FacetArtifact { name: "New".to_string(), address: new_facet, functions: vec![function("a")] },
+            FacetArtifact { name: "Kept".to_string(), address: kept_facet, functions: vec![function("c"), function("d")] },
+        ];
+
+        let cut = plan_diamond_cut(Address::repeat_byte(9), &current, &target, false).unwrap();
+        assert_eq!(
+            cut.cuts,
+            vec![
+                FacetCut { facet_address: kept_facet, action: FacetCutAction::Add, selectors: vec![function("d").selector()] },
+                FacetCut { facet_address: new_facet, action: FacetCutAction::Replace, selectors: vec![function("a").selector()] },
+                FacetCut { facet_address: Address::zero(), action: FacetCutAction::Remove, selectors: vec![function("b").selector()] },
+            ]
+        );
+        assert_eq!(cut.calldata(Address::zero(), vec![])[..4], [0x1f, 0x93, 0x1c, 0x1c]);
+    }
+
+    #[test]
+    fn test_plan_diamond_cut_selector_clash() {
+        let target = vec![
+            FacetArtifact { name: "A".to_string(), address: Address::repeat_byte(1), functions: vec![function("a")] },
+            FacetArtifact { name: "B".to_string(), address: Address::repeat_byte(2), functions: vec![function("a")] },
+        ];
+        let result = plan_diamond_cut(Address::repeat_byte(9), &[], &target, false);
+        assert!(matches!(result, Err(UpdateError::SelectorClash)));
+    }
+
+    #[test]
+    fn test_plan_diamond_cut_up_to_date() {
+        let facet = Address::repeat_byte(1);
+        let current = vec![Facet { address: facet, selectors: vec![function("a").selector()] }];
+        let target = vec![FacetArtifact { name: "A".to_string(), address: facet, functions: vec![function("a")] }];
+        assert!(plan_diamond_cut(Address::repeat_byte(9), &current, &target, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plan_diamond_cut_keeps_diamond_cut() {
+        let cut_facet = Address::repeat_byte(1);
+        let cut_function = AbiFunction {
+            name: "diamondCut".to_string(),
+            inputs: vec![
+                "(address,uint8,bytes4[])[]".to_string(),
+                "address".to_string(),
+                "bytes".to_string(),
+            ],
+            outputs: vec![],
+            payable: false,
+            constant: false,
+        };
+        let current = vec![Facet { address: cut_facet, selectors: vec![cut_function.selector(), function("a").selector()] }];
+        let target = vec![FacetArtifact { name: "A".to_string(), address: cut_facet, functions: vec![function("a")] }];
+
+        let result = plan_diamond_cut(Address::repeat_byte(9), &current, &target, false);
+        assert!(matches!(result, Err(UpdateError::CutRemoval)));
+
+        let cut = plan_diamond_cut(Address::repeat_byte(9), &current, &target, true).unwrap();
+        assert_eq!(cut.cuts[0].selectors, vec![[0x1f, 0x93, 0x1c, 0x1c]]);
+    }
 }
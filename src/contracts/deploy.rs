@@ -1,7 +1,13 @@
 use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::error::Error;
+use crate::contracts::gas::{build_typed_transaction, GasOptimizationError, TypedTransactionOptions};
+use crate::contracts::nonce::{NonceError, NonceManager};
+use crate::contracts::offline::{prepare_transaction, OfflineError, OfflineTransaction};
 use web3::contract::{Contract, Options};
-use web3::types::{Address, U256};
+use web3::types::{Address, Bytes, TransactionRequest, H256, U256};
+use web3::{Transport, Web3};
 use std::str::FromStr;
+use std::fmt;
 
 /// Errors that can occur during contract deployment.
 #[derive(Debug)]
@@ -9,6 +15,36 @@ pub enum DeployError {
     InvalidContractCode,
     InvalidAddress,
     DeploymentFailed,
+    /// The node rejected the deployment transaction.
+    Rejected(Box<Error>),
+    /// Preparing the deployment for offline signing failed.
+    Offline(OfflineError),
+    /// Filling in the fees of the deployment failed.
+    Gas(GasOptimizationError),
+}
+
+impl fmt::Display for DeployError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeployError::InvalidContractCode => write!(f, "invalid contract code"),
+            DeployError::InvalidAddress => write!(f, "invalid address"),
+            DeployError::DeploymentFailed => write!(f, "deployment failed"),
+            DeployError::Rejected(error) => write!(f, "deployment rejected: {}", error),
+            DeployError::Offline(error) => write!(f, "offline preparation failed: {}", error),
+            DeployError::Gas(error) => write!(f, "fee calculation failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for DeployError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DeployError::Rejected(error) => Some(error.as_ref()),
+            DeployError::Offline(error) => Some(error),
+            DeployError::Gas(error) => Some(error),
+            _ => None,
+        }
+    }
 }
 
 /// Deploys a smart contract to the blockchain with input validation and enhanced error handling.
@@ -50,10 +86,93 @@ pub async fn deploy_contract(
     }
 }
 
+/// Sends a contract deployment from a node-managed account, taking the nonce from a shared manager.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `contract_code` - The bytecode of the contract.
+/// * `gas_limit` - The maximum gas allowed for deployment.
+/// * `sender_address` - The address deploying the contract.
+/// * `options` - The transaction type and gas strategy; the node picks the fees when `None`.
+///
+/// # Returns
+/// Result<H256, DeployError> - Returns the deployment transaction hash, otherwise returns an error.
+pub async fn send_deployment<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    contract_code: &[u8],
+    gas_limit: U256,
+    sender_address: &str,
+    options: Option<&TypedTransactionOptions>,
+) -> Result<H256, DeployError> {
+    let mut transaction = deployment_request(contract_code, gas_limit, sender_address)?;
+    if let Some(options) = options {
+        transaction = build_typed_transaction(web3, transaction, options).await.map_err(DeployError::Gas)?;
+    }
+
+    log_info(&format!("Deploying contract from address: {}", sender_address));
+
+    nonces.send_transaction(web3, transaction).await.map_err(|e| {
+        log_error("Contract deployment failed.");
+        match e {
+            NonceError::Rejected(error) => DeployError::Rejected(error),
+            _ => DeployError::DeploymentFailed,
+        }
+    })
+}
+
+/// Prepares a contract deployment for offline signing instead of sending it.
+///
+/// # Arguments
+/// * `web3` - The connection to the node, used only to read chain state.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `contract_code` - The bytecode of the contract.
+/// * `gas_limit` - The maximum gas allowed for deployment.
+/// * `sender_address` - The address deploying the contract.
+/// * `options` - The transaction type and fee options.
+///
+/// # Returns
+/// Result<OfflineTransaction, DeployError> - Returns the unsigned transaction, otherwise returns an error.
+pub async fn prepare_deployment<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    contract_code: &[u8],
+    gas_limit: U256,
+    sender_address: &str,
+    options: &TypedTransactionOptions,
+) -> Result<OfflineTransaction, DeployError> {
+    let transaction = deployment_request(contract_code, gas_limit, sender_address)?;
+    let description = format!("Deploy {} bytes of contract code", contract_code.len());
+    prepare_transaction(web3, nonces, transaction, options, &description)
+        .await
+        .map_err(DeployError::Offline)
+}
+
+fn deployment_request(contract_code: &[u8], gas_limit: U256, sender_address: &str) -> Result<TransactionRequest, DeployError> {
+    if contract_code.is_empty() {
+        return Err(DeployError::InvalidContractCode);
+    }
+    let sender = Address::from_str(sender_address).map_err(|_| DeployError::InvalidAddress)?;
+
+    Ok(TransactionRequest {
+        from: sender,
+        gas: Some(gas_limit),
+        data: Some(Bytes(contract_code.to_vec())),
+        ..Default::default()
+    })
+}
+
 // Unit test example
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::contracts::gas::TransactionType;
+    use crate::contracts::gas_strategy::FixedGasStrategy;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use serde_json::json;
+    use std::sync::Arc;
     use web3::types::U256;
 
     #[tokio::test]
@@ -73,4 +192,48 @@ mod tests {
         let result = deploy_contract(&[0x60, 0x80, 0x60, 0x40], U256::from(1), "0x1234567890abcdef1234567890abcdef12345678").await;
         assert!(matches!(result, Ok(())));
     }
+
+    #[tokio::test]
+    async fn test_send_deployment_against_mock() {
+        let sender = "0x1234567890abcdef1234567890abcdef12345678";
+        let hash = "0x7a4b1e4cbb1f9c2f43e9e5c6d7d9a3cde0bcc7e2b2f1a3a6f0d6c0c5e4b3a291";
+        let mock = MockProvider::new();
+        mock.on_request("eth_getTransactionCount", Some(json!([sender, "pending"])), json!("0x7"));
+        mock.on_request("eth_sendTransaction", Some(json!([{"from": sender, "nonce": "0x7", "data": "0x60806040"}])), json!(hash));
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let result = send_deployment(&web3, &NonceManager::new(), &[0x60, 0x80, 0x60, 0x40], U256::from(500_000), sender, None).await;
+        assert_eq!(format!("{:?}", result.unwrap()), hash);
+
+        let mock = MockProvider::new();
+        mock.on_request("eth_getTransactionCount", None, json!("0x7"));
+        mock.on_error("eth_sendTransaction", None, -32000, "insufficient funds for gas * price + value");
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let result = send_deployment(&web3, &NonceManager::new(), &[0x60, 0x80], U256::from(500_000), sender, None).await;
+        let error = Error::from(result.unwrap_err());
+        assert!(matches!(error.root(), Error::InsufficientFunds(_)));
+    }
+
+    #[tokio::test]
+    async fn test_send_deployment_with_gas_strategy() {
+        let sender = "0x1234567890abcdef1234567890abcdef12345678";
+        let hash = "0x7a4b1e4cbb1f9c2f43e9e5c6d7d9a3cde0bcc7e2b2f1a3a6f0d6c0c5e4b3a291";
+        let mock = MockProvider::new();
+        mock.on_request("eth_gasPrice", None, json!("0x3b9aca00"));
+        mock.on_request("eth_getTransactionCount", None, json!("0x7"));
+        mock.on_request("eth_sendTransaction", Some(json!([{"from": sender, "gasPrice": "0x77359400"}])), json!(hash));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        // The strategy pays 2 gwei although the node suggests 1 gwei.
+        let options = TypedTransactionOptions {
+            transaction_type: Some(TransactionType::Legacy),
+            gas_strategy: Some(Arc::new(FixedGasStrategy {
+                max_fee_per_gas: U256::from(2_000_000_000u64),
+                max_priority_fee_per_gas: U256::from(2_000_000_000u64),
+            })),
+            ..Default::default()
+        };
+        let result = send_deployment(&web3, &NonceManager::new(), &[0x60, 0x80], U256::from(500_000), sender, Some(&options)).await;
+        assert_eq!(format!("{:?}", result.unwrap()), hash);
+    }
 }
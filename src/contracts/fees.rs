@@ -0,0 +1,286 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::contracts::gas::{GasOptimizationError, DEFAULT_PRIORITY_FEE};
+use crate::error::Error;
+use serde::Deserialize;
+use serde_json::json;
+use web3::types::{U256, U64};
+use web3::{Transport, Web3};
+
+/// The largest change of the base fee from one block to the next, as a divisor (12.5%).
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// How soon a transaction should be included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeSpeed {
+    Slow,
+    Normal,
+    Fast,
+}
+
+/// Suggested EIP-1559 fee caps for one speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeSuggestion {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+/// The fees suggested by [`FeeOracle::estimate`].
+///
+/// # Fields
+/// - `base_fee`: The base fee of the next block.
+/// - `projected_base_fee`: The base fee expected after the projection window, following the
+///   recent trend of block fullness.
+/// - `max_base_fee`: The base fee after the projection window if every block is full; the
+///   suggested `maxFeePerGas` covers it.
+/// - `slow`, `normal`, `fast`: The suggested fee caps for each speed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeEstimates {
+    pub base_fee: U256,
+    pub projected_base_fee: U256,
+    pub max_base_fee: U256,
+    pub slow: FeeSuggestion,
+    pub normal: FeeSuggestion,
+    pub fast: FeeSuggestion,
+}
+
+impl FeeEstimates {
+    /// Builds estimates for a chain without EIP-1559, where every speed pays the node's gas price.
+    pub fn from_gas_price(gas_price: U256) -> Self {
+        let suggestion = FeeSuggestion { max_fee_per_gas: gas_price, max_priority_fee_per_gas: gas_price };
+        Self {
+            base_fee: gas_price,
+            projected_base_fee: gas_price,
+            max_base_fee: gas_price,
+            slow: suggestion,
+            normal: suggestion,
+            fast: suggestion,
+        }
+    }
+
+    /// Returns the suggestion for a speed.
+    pub fn get(&self, speed: FeeSpeed) -> FeeSuggestion {
+        match speed {
+            FeeSpeed::Slow => self.slow,
+            FeeSpeed::Normal => self.normal,
+            FeeSpeed::Fast => self.fast,
+        }
+    }
+}
+
+/// The result of `eth_feeHistory`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeHistory {
+    /// One entry per block of the window, plus the next block.
+    base_fee_per_gas: Vec<U256>,
+    gas_used_ratio: Vec<f64>,
+    /// The priority fees paid at each requested percentile, per block.
+    #[serde(default)]
+    reward: Vec<Vec<U256>>,
+}
+
+/// Suggests EIP-1559 fees from the priority fees paid in recent blocks.
+///
+/// # Fields
+/// - `block_count`: The number of recent blocks read through `eth_feeHistory`.
+/// - `percentiles`: The reward percentiles used for slow, normal and fast inclusion.
+/// - `projection_blocks`: The number of blocks the base fee is projected over. The suggested
+///   `maxFeePerGas` stays includable for that many consecutive full blocks.
+#[derive(Debug, Clone)]
+pub struct FeeOracle {
+    pub block_count: u64,
+    pub percentiles: [f64; 3],
+    pub projection_blocks: u64,
+}
+
+impl Default for FeeOracle {
+    fn default() -> Self {
+        Self {
+            block_count: 20,
+            percentiles: [10.0, 50.0, 90.0],
+            projection_blocks: 6,
+        }
+    }
+}
+
+impl FeeOracle {
+    /// Reads the fee history of the last `block_count` blocks and suggests fees.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to the node.
+    ///
+    /// # Returns
+    /// Result<FeeEstimates, GasOptimizationError> - Returns the suggested fees, otherwise returns
+    /// an error if the node does not support `eth_feeHistory`.
+    pub async fn estimate<T: Transport>(&self, web3: &Web3<T>) -> Result<FeeEstimates, GasOptimizationError> {
+        if self.block_count == 0 {
+            return Err(GasOptimizationError::GasCalculationFailed);
+        }
+        let params = vec![
+            json!(U64::from(self.block_count)),
+            json!("latest"),
+            json!(self.percentiles),
+        ];
+        let value = web3.transport().execute("eth_feeHistory", params).await.map_err(|e| {
+            log_error(&format!("Failed to read fee history: {}", e));
+            GasOptimizationError::Rejected(Box::new(Error::from(e)))
+        })?;
+        let history: FeeHistory = serde_json::from_value(value).map_err(|e| {
+            log_error(&format!("Invalid fee history: {}", e));
+            GasOptimizationError::NetworkQueryFailed
+        })?;
+
+        let estimates = self.estimates_from_history(&history)?;
+        log_info(&format!(
+            "Base fee {} (projected {} after {} blocks); priority fees {} / {} / {}.",
+            estimates.base_fee,
+            estimates.projected_base_fee,
+            self.projection_blocks,
+            estimates.slow.max_priority_fee_per_gas,
+            estimates.normal.max_priority_fee_per_gas,
+            estimates.fast.max_priority_fee_per_gas
+        ));
+        Ok(estimates)
+    }
+
+    fn estimates_from_history(&self, history: &FeeHistory) -> Result<FeeEstimates, GasOptimizationError> {
+        let base_fee = *history.base_fee_per_gas.last().ok_or(GasOptimizationError::NetworkQueryFailed)?;
+
+        let average_ratio = if history.gas_used_ratio.is_empty() {
+            0.5
+        } else {
+            history.gas_used_ratio.iter().sum::<f64>() / history.gas_used_ratio.len() as f64
+        };
+        let projected_base_fee = project_base_fee(base_fee, average_ratio, self.projection_blocks);
+        let max_base_fee = project_base_fee(base_fee, 1.0, self.projection_blocks);
+
+        let mut priority_fees = [U256::zero(); 3];
+        for (column, priority_fee) in priority_fees.iter_mut().enumerate() {
+            *priority_fee = median_reward(&history.reward, column).unwrap_or_else(|| {
+                log_warn("No priority fees in the fee history; using the default priority fee.");
+                U256::from(DEFAULT_PRIORITY_FEE)
+            });
+        }
+        // Faster speeds never pay less than slower ones.
+        priority_fees[1] = priority_fees[1].max(priority_fees[0]);
+        priority_fees[2] = priority_fees[2].max(priority_fees[1]);
+
+        let suggestion = |priority_fee: U256| FeeSuggestion {
+            max_fee_per_gas: max_base_fee + priority_fee,
+            max_priority_fee_per_gas: priority_fee,
+        };
+        Ok(FeeEstimates {
+            base_fee,
+            projected_base_fee,
+            max_base_fee,
+            slow: suggestion(priority_fees[0]),
+            normal: suggestion(priority_fees[1]),
+            fast: suggestion(priority_fees[2]),
+        })
+    }
+}
+
+/// Projects the base fee over a number of blocks that are each `gas_used_ratio` full.
+///
+/// Following EIP-1559, the base fee moves by up to 12.5% per block: it grows when blocks are
+/// more than half full and shrinks when they are less.
+///
+/// # Arguments
+/// * `base_fee` - The base fee of the next block.
+/// * `gas_used_ratio` - The fullness of each block, from `0.0` to `1.0`.
+/// * `blocks` - The number of blocks to project over.
+///
+/// # Returns
+/// U256 - The projected base fee.
+pub fn project_base_fee(base_fee: U256, gas_used_ratio: f64, blocks: u64) -> U256 {
+    // The change per block in parts per million, between -12.5% and +12.5%.
+    let ratio = gas_used_ratio.clamp(0.0, 1.0);
+    let change_ppm = ((ratio - 0.5) * 2.0 / BASE_FEE_MAX_CHANGE_DENOMINATOR as f64 * 1_000_000.0).round() as i64;
+
+    let mut projected = base_fee;
+    for _ in 0..blocks {
+        let delta = projected.saturating_mul(U256::from(change_ppm.unsigned_abs())) / U256::from(1_000_000);
+        projected = if change_ppm >= 0 {
+            projected.saturating_add(delta)
+        } else {
+            projected.saturating_sub(delta)
+        };
+    }
+    projected
+}
+
+/// Returns the median of the non-zero rewards in a percentile column; empty blocks report zero.
+fn median_reward(rewards: &[Vec<U256>], column: usize) -> Option<U256> {
+    let mut values: Vec<U256> = rewards
+        .iter()
+        .filter_map(|block| block.get(column).copied())
+        .filter(|reward| !reward.is_zero())
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    Some(values[values.len() / 2])
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    #[test]
+    fn test_project_base_fee() {
+        let base_fee = U256::from(100 * GWEI);
+        assert_eq!(project_base_fee(base_fee, 0.5, 10), base_fee);
+        assert_eq!(project_base_fee(base_fee, 1.0, 1), U256::from(112_500_000_000u64));
+        assert_eq!(project_base_fee(base_fee, 0.0, 1), U256::from(87_500_000_000u64));
+        assert!(project_base_fee(base_fee, 1.0, 6) > U256::from(200 * GWEI));
+    }
+
+    #[tokio::test]
+    async fn test_estimate_from_fee_history() {
+        let mock = MockProvider::new();
+        mock.on_request(
+            "eth_feeHistory",
+            Some(json!(["0x4", "latest", [10.0, 50.0, 90.0]])),
+            json!({
+                "oldestBlock": "0x100",
+                "baseFeePerGas": ["0x2540be400", "0x2540be400", "0x2540be400", "0x2540be400", "0x2540be400"],
+                "gasUsedRatio": [0.5, 0.5, 0.5, 0.0],
+                "reward": [
+                    ["0x3b9aca00", "0x77359400", "0xb2d05e00"],
+                    ["0x3b9aca00", "0x77359400", "0x12a05f200"],
+                    ["0x5f5e100", "0x3b9aca00", "0xb2d05e00"],
+                    ["0x0", "0x0", "0x0"]
+                ]
+            }),
+        );
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let oracle = FeeOracle { block_count: 4, projection_blocks: 1, ..Default::default() };
+        let estimates = oracle.estimate(&web3).await.unwrap();
+        assert_eq!(estimates.base_fee, U256::from(10 * GWEI));
+        // Three half-full blocks and an empty one: the base fee trends down.
+        assert!(estimates.projected_base_fee < estimates.base_fee);
+        assert_eq!(estimates.max_base_fee, U256::from(11_250_000_000u64));
+
+        assert_eq!(estimates.get(FeeSpeed::Slow).max_priority_fee_per_gas, U256::from(GWEI));
+        assert_eq!(estimates.normal.max_priority_fee_per_gas, U256::from(2 * GWEI));
+        assert_eq!(estimates.fast.max_priority_fee_per_gas, U256::from(3 * GWEI));
+        assert_eq!(estimates.fast.max_fee_per_gas, U256::from(14_250_000_000u64));
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_fee_history() {
+        let mock = MockProvider::new();
+        mock.on_error("eth_feeHistory", None, -32601, "the method eth_feeHistory does not exist");
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let result = FeeOracle::default().estimate(&web3).await;
+        assert!(matches!(result, Err(GasOptimizationError::Rejected(_))));
+    }
+}
@@ -1,15 +1,272 @@
-use crate::framework::logging::{log_info, log_warn};
-use web3::types::U256;
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::error::Error;
+use crate::contracts::bytecode::analyze_bytecode;
+use crate::contracts::fees::{FeeEstimates, FeeOracle};
+use crate::contracts::gas_strategy::{adjust_gas_limit, GasStrategy};
+use crate::contracts::network::Network;
+use serde::Deserialize;
+use web3::types::{AccessList, BlockId, BlockNumber, CallRequest, TransactionRequest, U256, U64};
+use web3::{Transport, Web3};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Errors that can occur during gas optimization.
 #[derive(Debug)]
 pub enum GasOptimizationError {
     InvalidGasLimit,
     GasCalculationFailed,
+    NetworkQueryFailed,
+    /// The gas limit does not cover the intrinsic gas of the transaction.
+    BelowIntrinsicGas { gas_limit: u64, intrinsic_gas: u64 },
+    /// The node could not estimate the transaction, e.g. because it reverts.
+    Rejected(Box<Error>),
+}
+
+impl fmt::Display for GasOptimizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasOptimizationError::InvalidGasLimit => write!(f, "invalid gas limit"),
+            GasOptimizationError::GasCalculationFailed => write!(f, "gas calculation failed"),
+            GasOptimizationError::NetworkQueryFailed => write!(f, "failed to query the network"),
+            GasOptimizationError::BelowIntrinsicGas { gas_limit, intrinsic_gas } => {
+                write!(f, "gas limit {} is below the intrinsic gas of {}", gas_limit, intrinsic_gas)
+            }
+            GasOptimizationError::Rejected(error) => write!(f, "gas estimation rejected: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for GasOptimizationError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GasOptimizationError::Rejected(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// The transaction envelope used to pay for gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionType {
+    /// A pre-EIP-2718 transaction with a single gas price.
+    Legacy,
+    /// An EIP-2930 transaction with a gas price and an access list.
+    AccessList,
+    /// An EIP-1559 transaction with `maxFeePerGas` and `maxPriorityFeePerGas`.
+    Eip1559,
+}
+
+impl TransactionType {
+    /// Returns the EIP-2718 type byte, or `None` for legacy transactions.
+    pub fn type_byte(&self) -> Option<U64> {
+        match self {
+            TransactionType::Legacy => None,
+            TransactionType::AccessList => Some(U64::from(1)),
+            TransactionType::Eip1559 => Some(U64::from(2)),
+        }
+    }
+}
+
+/// Whether to attach an access list generated by `eth_createAccessList`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessListMode {
+    /// Never attach an access list.
+    Never,
+    /// Attach the generated access list only if it lowers the gas used.
+    IfCheaper,
+}
+
+/// Options for [`build_typed_transaction`].
+///
+/// # Fields
+/// - `transaction_type`: The envelope to use; taken from `network`, or detected from the chain, when `None`.
+/// - `access_list`: Whether to generate an access list.
+/// - `priority_fee`: The tip per gas for EIP-1559 transactions; the node's suggestion when `None`.
+/// - `network`: The chain the transaction is for; signing is refused if the node serves another one.
+/// - `gas_strategy`: Decides the fees from the [`FeeOracle`] estimates; overrides `priority_fee` when set.
+/// - `fee_oracle`: The oracle whose estimates `gas_strategy` decides from on EIP-1559 chains.
+#[derive(Debug, Clone)]
+pub struct TypedTransactionOptions {
+    pub transaction_type: Option<TransactionType>,
+    pub access_list: AccessListMode,
+    pub priority_fee: Option<U256>,
+    pub network: Option<Network>,
+    pub gas_strategy: Option<Arc<dyn GasStrategy>>,
+    pub fee_oracle: FeeOracle,
+}
+
+impl Default for TypedTransactionOptions {
+    fn default() -> Self {
+        Self {
+            transaction_type: None,
+            access_list: AccessListMode::Never,
+            priority_fee: None,
+            network: None,
+            gas_strategy: None,
+            fee_oracle: FeeOracle::default(),
+        }
+    }
+}
+
+/// The priority fee used when the node does not support `eth_maxPriorityFeePerGas` (1.5 gwei).
+pub(crate) const DEFAULT_PRIORITY_FEE: u64 = 1_500_000_000;
+
+/// The result of `eth_createAccessList`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AccessListResult {
+    access_list: AccessList,
+}
+
+/// The gas of a plain Ether transfer, the least any transaction uses.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// The fields of the latest block used to project costs.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestBlock {
+    gas_limit: U256,
+    base_fee_per_gas: Option<U256>,
+}
+
+/// Safety margins applied to the node's gas estimate.
+///
+/// # Fields
+/// - `multiplier_percent`: The buffered limit as a percentage of the estimate, e.g. `120` for 20% headroom.
+/// - `floor`: The lowest gas limit returned.
+/// - `ceiling`: The highest gas limit returned; the block gas limit when `None`.
+#[derive(Debug, Clone)]
+pub struct GasEstimateOptions {
+    pub multiplier_percent: u64,
+    pub floor: U256,
+    pub ceiling: Option<U256>,
+}
+
+impl Default for GasEstimateOptions {
+    fn default() -> Self {
+        Self {
+            multiplier_percent: 120,
+            floor: U256::from(TRANSFER_GAS),
+            ceiling: None,
+        }
+    }
+}
+
+/// A gas estimate for a concrete transaction.
+///
+/// # Fields
+/// - `estimated_gas`: The raw result of `eth_estimateGas`.
+/// - `gas_limit`: The estimate with the multiplier, floor and ceiling applied.
+/// - `gas_price`: The price per gas the transaction would pay at current fees: its own gas
+///   price if set, otherwise the base fee plus the priority fee capped at its `maxFeePerGas`,
+///   or the node's gas price.
+/// - `expected_cost`: `estimated_gas * gas_price`, in wei.
+/// - `max_cost`: `gas_limit * gas_price`, in wei.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GasEstimate {
+    pub estimated_gas: U256,
+    pub gas_limit: U256,
+    pub gas_price: U256,
+    pub expected_cost: U256,
+    pub max_cost: U256,
+}
+
+/// Estimates the gas of a transaction through `eth_estimateGas` and applies safety margins.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `transaction` - The transaction to estimate; `from`, `to`, `data` and `value` should be set.
+/// * `options` - The multiplier, floor and ceiling applied to the estimate.
+///
+/// # Returns
+/// Result<GasEstimate, GasOptimizationError> - Returns the estimate, otherwise returns an error
+/// if the node rejects the transaction or it needs more gas than the ceiling.
+pub async fn estimate_transaction_gas<T: Transport>(
+    web3: &Web3<T>,
+    transaction: &TransactionRequest,
+    options: &GasEstimateOptions,
+) -> Result<GasEstimate, GasOptimizationError> {
+    let estimated_gas = web3.eth().estimate_gas(call_request(transaction), None).await.map_err(|e| {
+        log_error(&format!("Gas estimation failed: {}", e));
+        GasOptimizationError::Rejected(Box::new(Error::from(e)))
+    })?;
+
+    let block = latest_block(web3).await?;
+    let ceiling = options.ceiling.unwrap_or(block.gas_limit);
+    let gas_limit = buffered_gas_limit(estimated_gas, Some(ceiling), options)?;
+
+    let gas_price = match (transaction.gas_price, block.base_fee_per_gas) {
+        (Some(gas_price), _) => gas_price,
+        (None, Some(base_fee)) => {
+            let priority_fee = match transaction.max_priority_fee_per_gas {
+                Some(priority_fee) => priority_fee,
+                None => suggested_priority_fee(web3).await,
+            };
+            let gas_price = base_fee.saturating_add(priority_fee);
+            transaction.max_fee_per_gas.map_or(gas_price, |max_fee| gas_price.min(max_fee))
+        }
+        (None, None) => web3.eth().gas_price().await.map_err(|_| GasOptimizationError::NetworkQueryFailed)?,
+    };
+
+    let estimate = GasEstimate {
+        estimated_gas,
+        gas_limit,
+        gas_price,
+        expected_cost: estimated_gas.saturating_mul(gas_price),
+        max_cost: gas_limit.saturating_mul(gas_price),
+    };
+    log_info(&format!(
+        "Estimated gas: {} (limit {}), projected cost {} wei.",
+        estimate.estimated_gas, estimate.gas_limit, estimate.expected_cost
+    ));
+    Ok(estimate)
+}
+
+/// Reads the gas limit and base fee of the latest block.
+async fn latest_block<T: Transport>(web3: &Web3<T>) -> Result<LatestBlock, GasOptimizationError> {
+    let value = web3
+        .transport()
+        .execute("eth_getBlockByNumber", vec![serde_json::json!("latest"), serde_json::json!(false)])
+        .await
+        .map_err(|_| GasOptimizationError::NetworkQueryFailed)?;
+    serde_json::from_value(value).map_err(|_| GasOptimizationError::NetworkQueryFailed)
+}
+
+/// Returns the ceiling of the options, or the gas limit of the latest block when none is set.
+pub(crate) async fn gas_ceiling<T: Transport>(
+    web3: &Web3<T>,
+    options: &GasEstimateOptions,
+) -> Result<U256, GasOptimizationError> {
+    match options.ceiling {
+        Some(ceiling) => Ok(ceiling),
+        None => Ok(latest_block(web3).await?.gas_limit),
+    }
+}
+
+/// Applies the multiplier and floor of the options to a raw estimate and caps the result at
+/// the ceiling, refusing estimates that exceed the ceiling themselves.
+pub(crate) fn buffered_gas_limit(
+    estimated_gas: U256,
+    ceiling: Option<U256>,
+    options: &GasEstimateOptions,
+) -> Result<U256, GasOptimizationError> {
+    if let Some(ceiling) = ceiling.filter(|ceiling| estimated_gas > *ceiling) {
+        log_error(&format!("Estimated gas {} exceeds the ceiling of {}.", estimated_gas, ceiling));
+        return Err(GasOptimizationError::InvalidGasLimit);
+    }
+    let buffered = estimated_gas
+        .checked_mul(U256::from(options.multiplier_percent))
+        .ok_or(GasOptimizationError::GasCalculationFailed)?
+        / U256::from(100);
+    let gas_limit = buffered.max(options.floor);
+    Ok(ceiling.map_or(gas_limit, |ceiling| gas_limit.min(ceiling)))
 }
 
 /// Estimates the gas needed for a transaction with input validation.
 /// 
+/// Kept for callers that only have a gas limit; [`estimate_gas_limit`] asks the node instead.
+///
 /// # Arguments
 /// * `gas_limit` - The maximum gas allowed for deployment.
 ///
@@ -33,37 +290,391 @@ pub fn estimate_gas(gas_limit: U256) -> Result<U256, GasOptimizationError> {
     Ok(estimated_gas)
 }
 
+/// Estimates the gas limit of a transaction through `eth_estimateGas` with the default
+/// safety margins.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `transaction` - The transaction to estimate.
+///
+/// # Returns
+/// Result<U256, GasOptimizationError> - Returns the buffered gas limit, otherwise returns an error.
+pub async fn estimate_gas_limit<T: Transport>(
+    web3: &Web3<T>,
+    transaction: &TransactionRequest,
+) -> Result<U256, GasOptimizationError> {
+    let estimate = estimate_transaction_gas(web3, transaction, &GasEstimateOptions::default()).await?;
+    Ok(estimate.gas_limit)
+}
+
+/// Estimates the gas limit of a contract call from its bytecode, without a node.
+///
+/// Uses the static upper bound of the called function from [`analyze_bytecode`] plus the
+/// intrinsic gas of the call (calldata, access list and the EIP-7623 floor), then applies the
+/// multiplier, floor and ceiling. Dynamic costs such as cold storage access and memory
+/// expansion are not covered and must fit in the multiplier.
+///
+/// # Arguments
+/// * `runtime_code` - The deployed bytecode of the contract.
+/// * `transaction` - The call; its `data` starts with the selector of the called function.
+/// * `hardfork` - The fork whose intrinsic gas rules apply.
+/// * `options` - The multiplier, floor and ceiling applied to the estimate.
+///
+/// # Returns
+/// Result<U256, GasOptimizationError> - Returns the buffered gas limit, otherwise returns an error
+/// if the function is unknown, has no static upper bound (e.g. it loops) or exceeds the ceiling.
+pub fn estimate_gas_offline(
+    runtime_code: &[u8],
+    transaction: &TransactionRequest,
+    hardfork: Hardfork,
+    options: &GasEstimateOptions,
+) -> Result<U256, GasOptimizationError> {
+    let selector: [u8; 4] = transaction
+        .data
+        .as_ref()
+        .and_then(|data| data.0.get(..4))
+        .and_then(|selector| selector.try_into().ok())
+        .ok_or_else(|| {
+            log_error("The call data has no function selector.");
+            GasOptimizationError::GasCalculationFailed
+        })?;
+    let analysis = analyze_bytecode(runtime_code).map_err(|e| {
+        log_error(&format!("Bytecode analysis failed: {}", e));
+        GasOptimizationError::GasCalculationFailed
+    })?;
+    let function = analysis.function(selector).map_err(|e| {
+        log_error(&format!("Bytecode analysis failed: {}", e));
+        GasOptimizationError::GasCalculationFailed
+    })?;
+    let max_gas = function.max_gas.ok_or_else(|| {
+        log_warn(&format!("Function {} has no static gas bound.", function.selector_hex()));
+        GasOptimizationError::GasCalculationFailed
+    })?;
+
+    let intrinsic_gas = intrinsic_gas(transaction, hardfork);
+    let estimated_gas = U256::from((intrinsic_gas.standard() + max_gas).max(intrinsic_gas.floor));
+    let gas_limit = buffered_gas_limit(estimated_gas, options.ceiling, options)?;
+    log_info(&format!("Static gas estimate for {}: {} (limit {}).", function.selector_hex(), estimated_gas, gas_limit));
+    Ok(gas_limit)
+}
+
 /// Dynamically adjusts gas usage based on network conditions.
 /// 
 /// # Arguments
 /// * `current_gas_price` - The current gas price in the network.
 /// * `gas_limit` - The maximum gas allowed for the transaction.
+/// * `fees` - The fees suggested by a [`FeeOracle`](crate::contracts::fees::FeeOracle); prices above the normal `maxFeePerGas` count as high.
 ///
 /// # Returns
 /// U256 - Adjusted gas amount based on conditions.
-pub fn optimize_gas_dynamically(current_gas_price: U256, gas_limit: U256) -> U256 {
-    // Basic logic to adjust gas dynamically (can be enhanced based on real-time conditions)
-    let adjustment_factor = if current_gas_price > U256::from(100) {
-        U256::from(90) // Reduce gas usage if gas price is high
+pub fn optimize_gas_dynamically(current_gas_price: U256, gas_limit: U256, fees: &FeeEstimates) -> U256 {
+    let optimized_gas = adjust_gas_limit(gas_limit, current_gas_price, fees.normal.max_fee_per_gas);
+    log_info(&format!("Optimized gas: {:?}", optimized_gas)); // Corrected log_info usage
+
+    optimized_gas
+}
+
+/// The Ethereum hard forks that changed the intrinsic gas of transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Hardfork {
+    Frontier,
+    /// Contract creations pay 32,000 more.
+    Homestead,
+    /// EIP-2028: non-zero calldata bytes cost 16 instead of 68.
+    Istanbul,
+    /// EIP-2930: access lists cost 2,400 per address and 1,900 per storage key.
+    Berlin,
+    /// EIP-3860: init code costs 2 per 32-byte word.
+    Shanghai,
+    /// EIP-7623: calldata-heavy transactions pay at least 10 gas per calldata token.
+    #[default]
+    Prague,
+}
+
+/// The intrinsic gas of a transaction: what it pays before executing any code.
+///
+/// # Fields
+/// - `base`: 21,000, plus 32,000 for contract creations from Homestead on.
+/// - `calldata`: 4 per zero byte and 16 (68 before Istanbul) per non-zero byte.
+/// - `initcode`: 2 per 32-byte word of init code for contract creations, from Shanghai on.
+/// - `access_list`: 2,400 per address and 1,900 per storage key, from Berlin on.
+/// - `floor`: The EIP-7623 minimum, 21,000 plus 10 per calldata token (a zero byte is one
+///   token, a non-zero byte four), from Prague on; zero before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntrinsicGas {
+    pub base: u64,
+    pub calldata: u64,
+    pub initcode: u64,
+    pub access_list: u64,
+    pub floor: u64,
+}
+
+impl IntrinsicGas {
+    /// Returns the intrinsic gas charged before execution.
+    pub fn standard(&self) -> u64 {
+        self.base + self.calldata + self.initcode + self.access_list
+    }
+
+    /// Returns the lowest gas limit the transaction is valid with.
+    pub fn required(&self) -> u64 {
+        self.standard().max(self.floor)
+    }
+}
+
+/// Computes the intrinsic gas of a transaction under the rules of a hard fork.
+///
+/// # Arguments
+/// * `transaction` - The transaction; `to: None` makes it a contract creation with `data` as init code.
+/// * `hardfork` - The fork whose rules apply.
+///
+/// # Returns
+/// IntrinsicGas - The components of the intrinsic gas.
+pub fn intrinsic_gas(transaction: &TransactionRequest, hardfork: Hardfork) -> IntrinsicGas {
+    let data = transaction.data.as_ref().map_or(&[][..], |data| &data.0[..]);
+    let is_create = transaction.to.is_none();
+    let zero_bytes = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zero_bytes = data.len() as u64 - zero_bytes;
+
+    let base = if is_create && hardfork >= Hardfork::Homestead { TRANSFER_GAS + 32_000 } else { TRANSFER_GAS };
+    let non_zero_cost = if hardfork >= Hardfork::Istanbul { 16 } else { 68 };
+    let initcode = if is_create && hardfork >= Hardfork::Shanghai { 2 * (data.len() as u64).div_ceil(32) } else { 0 };
+    let access_list = match &transaction.access_list {
+        Some(list) if hardfork >= Hardfork::Berlin => list
+            .iter()
+            .map(|item| 2_400 + 1_900 * item.storage_keys.len() as u64)
+            .sum(),
+        _ => 0,
+    };
+    let tokens = zero_bytes + 4 * non_zero_bytes;
+    let floor = if hardfork >= Hardfork::Prague { TRANSFER_GAS + 10 * tokens } else { 0 };
+
+    IntrinsicGas {
+        base,
+        calldata: zero_bytes * 4 + non_zero_bytes * non_zero_cost,
+        initcode,
+        access_list,
+        floor,
+    }
+}
+
+/// Checks that a gas limit covers the intrinsic gas of the transaction, including the
+/// EIP-7623 calldata floor.
+///
+/// # Arguments
+/// * `gas_limit` - The gas limit to check.
+/// * `transaction` - The transaction the limit is for.
+/// * `hardfork` - The fork whose rules apply.
+///
+/// # Returns
+/// Result<(), GasOptimizationError> - Returns `Ok` if the limit is high enough, otherwise returns
+/// `GasOptimizationError::BelowIntrinsicGas`.
+pub fn check_gas_limit(gas_limit: u64, transaction: &TransactionRequest, hardfork: Hardfork) -> Result<(), GasOptimizationError> {
+    let intrinsic_gas = intrinsic_gas(transaction, hardfork).required();
+    if gas_limit < intrinsic_gas {
+        log_error(&format!("Gas limit {} is below the intrinsic gas of {}.", gas_limit, intrinsic_gas));
+        return Err(GasOptimizationError::BelowIntrinsicGas { gas_limit, intrinsic_gas });
+    }
+    Ok(())
+}
+
+/// Returns the EIP-1559 fee caps for a base fee and priority fee.
+///
+/// `maxFeePerGas` is twice the current base fee plus the tip, which keeps the transaction
+/// includable through six consecutive full blocks of base fee growth.
+///
+/// # Returns
+/// (U256, U256) - `maxFeePerGas` and `maxPriorityFeePerGas`.
+pub fn eip1559_fees(base_fee: U256, priority_fee: U256) -> (U256, U256) {
+    (base_fee * U256::from(2) + priority_fee, priority_fee)
+}
+
+/// Detects the transaction type to use from the latest block: EIP-1559 when blocks carry a base fee.
+pub async fn detect_transaction_type<T: Transport>(web3: &Web3<T>) -> Result<TransactionType, GasOptimizationError> {
+    let block = web3
+        .eth()
+        .block(BlockId::Number(BlockNumber::Latest))
+        .await
+        .map_err(|_| GasOptimizationError::NetworkQueryFailed)?
+        .ok_or(GasOptimizationError::NetworkQueryFailed)?;
+
+    Ok(if block.base_fee_per_gas.is_some() {
+        TransactionType::Eip1559
     } else {
-        U256::from(110) // Increase gas usage if gas price is low
+        TransactionType::Legacy
+    })
+}
+
+/// Fills in the type, fee fields and optionally the access list of a transaction.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `transaction` - The transaction to complete; `from`, `to`, `data` and `value` should be set.
+/// * `options` - The transaction type and access list options.
+///
+/// # Returns
+/// Result<TransactionRequest, GasOptimizationError> - Returns the typed transaction, otherwise returns an error.
+pub async fn build_typed_transaction<T: Transport>(
+    web3: &Web3<T>,
+    mut transaction: TransactionRequest,
+    options: &TypedTransactionOptions,
+) -> Result<TransactionRequest, GasOptimizationError> {
+    let mut transaction_type = match (options.transaction_type, &options.network) {
+        (Some(transaction_type), _) => transaction_type,
+        (None, Some(network)) => network.default_transaction_type(),
+        (None, None) => detect_transaction_type(web3).await?,
     };
 
-    let optimized_gas = gas_limit * adjustment_factor / U256::from(100);
-    log_info(&format!("Optimized gas: {:?}", optimized_gas)); // Corrected log_info usage
+    if options.access_list == AccessListMode::IfCheaper {
+        if let Some(access_list) = cheaper_access_list(web3, &transaction).await? {
+            transaction.access_list = Some(access_list);
+            // Access lists need a typed envelope.
+            if transaction_type == TransactionType::Legacy {
+                transaction_type = TransactionType::AccessList;
+            }
+        }
+    }
 
-    optimized_gas
+    match transaction_type {
+        TransactionType::Legacy | TransactionType::AccessList => {
+            let mut gas_price = web3.eth().gas_price().await.map_err(|_| GasOptimizationError::NetworkQueryFailed)?;
+            if let Some(strategy) = &options.gas_strategy {
+                gas_price = strategy.fees(&FeeEstimates::from_gas_price(gas_price), Duration::ZERO).max_fee_per_gas;
+            }
+            transaction.gas_price = Some(gas_price);
+            transaction.max_fee_per_gas = None;
+            transaction.max_priority_fee_per_gas = None;
+        }
+        TransactionType::Eip1559 => {
+            let (max_fee, max_priority_fee) = match &options.gas_strategy {
+                Some(strategy) => {
+                    let fees = strategy.fees(&options.fee_oracle.estimate(web3).await?, Duration::ZERO);
+                    (fees.max_fee_per_gas, fees.max_priority_fee_per_gas)
+                }
+                None => {
+                    let base_fee = web3
+                        .eth()
+                        .block(BlockId::Number(BlockNumber::Latest))
+                        .await
+                        .map_err(|_| GasOptimizationError::NetworkQueryFailed)?
+                        .and_then(|block| block.base_fee_per_gas)
+                        .ok_or(GasOptimizationError::NetworkQueryFailed)?;
+                    let priority_fee = match options.priority_fee {
+                        Some(priority_fee) => priority_fee,
+                        None => suggested_priority_fee(web3).await,
+                    };
+                    eip1559_fees(base_fee, priority_fee)
+                }
+            };
+            transaction.gas_price = None;
+            transaction.max_fee_per_gas = Some(max_fee);
+            transaction.max_priority_fee_per_gas = Some(max_priority_fee);
+        }
+    }
+
+    transaction.transaction_type = transaction_type.type_byte();
+    log_info(&format!("Built {:?} transaction.", transaction_type));
+    Ok(transaction)
 }
 
-pub fn check_gas_limit(gas_limit: u64) -> bool {
-    gas_limit > 0
+/// Asks the node for a priority fee, falling back to [`DEFAULT_PRIORITY_FEE`].
+async fn suggested_priority_fee<T: Transport>(web3: &Web3<T>) -> U256 {
+    match web3.transport().execute("eth_maxPriorityFeePerGas", vec![]).await {
+        Ok(value) => serde_json::from_value(value).unwrap_or_else(|_| U256::from(DEFAULT_PRIORITY_FEE)),
+        Err(_) => {
+            log_warn("eth_maxPriorityFeePerGas is not supported; using the default priority fee.");
+            U256::from(DEFAULT_PRIORITY_FEE)
+        }
+    }
+}
+
+/// Generates an access list through `eth_createAccessList` and returns it only if
+/// `eth_estimateGas` with the list attached is lower than without it.
+async fn cheaper_access_list<T: Transport>(
+    web3: &Web3<T>,
+    transaction: &TransactionRequest,
+) -> Result<Option<AccessList>, GasOptimizationError> {
+    let request = call_request(transaction);
+    let params = vec![
+        serde_json::to_value(&request).map_err(|_| GasOptimizationError::GasCalculationFailed)?,
+        serde_json::to_value(BlockNumber::Latest).map_err(|_| GasOptimizationError::GasCalculationFailed)?,
+    ];
+
+    let generated: AccessListResult = match web3.transport().execute("eth_createAccessList", params).await {
+        Ok(value) => serde_json::from_value(value).map_err(|_| GasOptimizationError::GasCalculationFailed)?,
+        Err(_) => {
+            log_warn("eth_createAccessList is not supported; sending without an access list.");
+            return Ok(None);
+        }
+    };
+    if generated.access_list.is_empty() {
+        return Ok(None);
+    }
+
+    let mut with_list = request.clone();
+    with_list.access_list = Some(generated.access_list.clone());
+    if with_list.transaction_type.is_none_or(|transaction_type| transaction_type.is_zero()) {
+        with_list.transaction_type = TransactionType::AccessList.type_byte();
+    }
+    let without = web3
+        .eth()
+        .estimate_gas(request, None)
+        .await
+        .map_err(|_| GasOptimizationError::NetworkQueryFailed)?;
+    let with = web3
+        .eth()
+        .estimate_gas(with_list, None)
+        .await
+        .map_err(|_| GasOptimizationError::NetworkQueryFailed)?;
+
+    if with >= without {
+        return Ok(None);
+    }
+    log_info(&format!("Access list saves {} gas ({} instead of {}).", without - with, with, without));
+    Ok(Some(generated.access_list))
+}
+
+/// Converts a transaction into the request used by `eth_call`, `eth_estimateGas` and `eth_createAccessList`.
+pub fn call_request(transaction: &TransactionRequest) -> CallRequest {
+    CallRequest {
+        from: Some(transaction.from),
+        to: transaction.to,
+        gas: transaction.gas,
+        gas_price: transaction.gas_price,
+        value: transaction.value,
+        data: transaction.data.clone(),
+        transaction_type: transaction.transaction_type,
+        access_list: transaction.access_list.clone(),
+        max_fee_per_gas: transaction.max_fee_per_gas,
+        max_priority_fee_per_gas: transaction.max_priority_fee_per_gas,
+    }
 }
 
 // Unit test example
 #[cfg(test)]
 mod tests {
     use super::*;
-    use web3::types::U256;
+    use crate::contracts::fees::{FeeSpeed, FeeSuggestion};
+    use crate::contracts::gas_strategy::{FixedGasStrategy, PercentileGasStrategy};
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use serde_json::json;
+    use web3::types::{AccessListItem, Address, Bytes, H256, U256};
+
+    fn mock_web3(estimated_gas: u64) -> Web3<ProviderTransport<MockProvider>> {
+        let mock = MockProvider::new();
+        mock.on_request("eth_estimateGas", None, json!(U256::from(estimated_gas)));
+        mock.on_request(
+            "eth_getBlockByNumber",
+            Some(json!(["latest"])),
+            json!({"gasLimit": "0x1c9c380", "baseFeePerGas": "0x2540be400"}),
+        );
+        mock.on_request("eth_maxPriorityFeePerGas", None, json!("0x3b9aca00"));
+        Web3::new(ProviderTransport::new(mock))
+    }
+
+    fn transfer() -> TransactionRequest {
+        TransactionRequest { from: Address::repeat_byte(1), to: Some(Address::repeat_byte(2)), ..Default::default() }
+    }
 
     #[test]
     fn test_invalid_gas_limit() {
@@ -77,15 +688,227 @@ mod tests {
         assert!(matches!(result, Ok(_)));
     }
 
+    #[tokio::test]
+    async fn test_gas_estimate_with_margins() {
+        let web3 = mock_web3(50_000);
+        let estimate = estimate_transaction_gas(&web3, &transfer(), &GasEstimateOptions::default()).await.unwrap();
+        assert_eq!(estimate.estimated_gas, U256::from(50_000));
+        assert_eq!(estimate.gas_limit, U256::from(60_000));
+        // 10 gwei base fee + 1 gwei priority fee.
+        assert_eq!(estimate.gas_price, U256::from(11_000_000_000u64));
+        assert_eq!(estimate.expected_cost, U256::from(550_000_000_000_000u64));
+        assert_eq!(estimate.max_cost, U256::from(660_000_000_000_000u64));
+
+        let options = GasEstimateOptions { floor: U256::from(100_000), ..Default::default() };
+        let estimate = estimate_transaction_gas(&web3, &transfer(), &options).await.unwrap();
+        assert_eq!(estimate.gas_limit, U256::from(100_000));
+
+        let options = GasEstimateOptions { ceiling: Some(U256::from(55_000)), ..Default::default() };
+        let estimate = estimate_transaction_gas(&web3, &transfer(), &options).await.unwrap();
+        assert_eq!(estimate.gas_limit, U256::from(55_000));
+
+        assert_eq!(estimate_gas_limit(&web3, &transfer()).await.unwrap(), U256::from(60_000));
+
+        // A fee cap below the base fee plus the priority fee limits what the transaction pays.
+        let capped = TransactionRequest { max_fee_per_gas: Some(U256::from(10_500_000_000u64)), ..transfer() };
+        let estimate = estimate_transaction_gas(&web3, &capped, &GasEstimateOptions::default()).await.unwrap();
+        assert_eq!(estimate.gas_price, U256::from(10_500_000_000u64));
+        assert_eq!(estimate.expected_cost, U256::from(525_000_000_000_000u64));
+    }
+
+    #[tokio::test]
+    async fn test_gas_estimate_above_ceiling() {
+        let web3 = mock_web3(40_000_000);
+        let result = estimate_gas_limit(&web3, &transfer()).await;
+        assert!(matches!(result, Err(GasOptimizationError::InvalidGasLimit)));
+    }
+
+    #[tokio::test]
+    async fn test_gas_estimate_reverted() {
+        let mock = MockProvider::new();
+        mock.on_error("eth_estimateGas", None, 3, "execution reverted: paused");
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let error = Error::from(estimate_gas_limit(&web3, &transfer()).await.unwrap_err());
+        assert!(matches!(error.root(), Error::ExecutionReverted { reason: Some(reason), .. } if reason == "paused"));
+    }
+
+    fn fee_estimates(max_fee_per_gas: u64) -> FeeEstimates {
+        let suggestion = FeeSuggestion { max_fee_per_gas: U256::from(max_fee_per_gas), max_priority_fee_per_gas: U256::one() };
+        FeeEstimates {
+            base_fee: U256::from(max_fee_per_gas),
+            projected_base_fee: U256::from(max_fee_per_gas),
+            max_base_fee: U256::from(max_fee_per_gas),
+            slow: suggestion,
+            normal: suggestion,
+            fast: suggestion,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typed_transaction_with_gas_strategy() {
+        let mock = MockProvider::new();
+        mock.on_request("eth_gasPrice", None, json!("0x3b9aca00"));
+        mock.on_request(
+            "eth_feeHistory",
+            None,
+            json!({"baseFeePerGas": ["0x3b9aca00"], "gasUsedRatio": [0.5], "reward": [["0x1", "0x2", "0x3"]]}),
+        );
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let fixed = FixedGasStrategy { max_fee_per_gas: U256::from(7), max_priority_fee_per_gas: U256::from(1) };
+        let options = TypedTransactionOptions {
+            transaction_type: Some(TransactionType::Legacy),
+            gas_strategy: Some(Arc::new(fixed)),
+            ..Default::default()
+        };
+        let transaction = build_typed_transaction(&web3, transfer(), &options).await.unwrap();
+        assert_eq!(transaction.gas_price, Some(U256::from(7)));
+
+        let options = TypedTransactionOptions {
+            transaction_type: Some(TransactionType::Eip1559),
+            gas_strategy: Some(Arc::new(PercentileGasStrategy { speed: FeeSpeed::Fast })),
+            ..Default::default()
+        };
+        let transaction = build_typed_transaction(&web3, transfer(), &options).await.unwrap();
+        assert_eq!(transaction.max_priority_fee_per_gas, Some(U256::from(3)));
+        assert_eq!(transaction.transaction_type, Some(U64::from(2)));
+    }
+
+    #[tokio::test]
+    async fn test_access_list_compared_by_estimate() {
+        let list = json!([{"address": Address::repeat_byte(3), "storageKeys": [H256::zero()]}]);
+        let mock = MockProvider::new();
+        // `gasUsed` from eth_createAccessList is not comparable with eth_estimateGas and is ignored.
+        mock.on_request("eth_createAccessList", None, json!({"accessList": list, "gasUsed": "0x1"}));
+        mock.on_request_once("eth_estimateGas", Some(json!([{"accessList": list}])), json!("0x6000"));
+        mock.on_request_once("eth_estimateGas", Some(json!([{"accessList": list}])), json!("0x5000"));
+        mock.on_request("eth_estimateGas", None, json!("0x5800"));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        assert!(cheaper_access_list(&web3, &transfer()).await.unwrap().is_none());
+        let access_list = cheaper_access_list(&web3, &transfer()).await.unwrap().unwrap();
+        assert_eq!(access_list[0].address, Address::repeat_byte(3));
+    }
+
     #[test]
     fn test_dynamic_gas_optimization_high_price() {
-        let optimized_gas = optimize_gas_dynamically(U256::from(150), U256::from(10000));
+        let optimized_gas = optimize_gas_dynamically(U256::from(150), U256::from(10000), &fee_estimates(100));
         assert!(optimized_gas < U256::from(10000));
     }
 
     #[test]
     fn test_dynamic_gas_optimization_low_price() {
-        let optimized_gas = optimize_gas_dynamically(U256::from(50), U256::from(10000));
+        let optimized_gas = optimize_gas_dynamically(U256::from(50), U256::from(10000), &fee_estimates(100));
         assert!(optimized_gas > U256::from(10000));
     }
+
+    #[test]
+    fn test_eip1559_fees() {
+        let (max_fee, priority_fee) = eip1559_fees(U256::from(30_000_000_000u64), U256::from(2_000_000_000u64));
+        assert_eq!(max_fee, U256::from(62_000_000_000u64));
+        assert_eq!(priority_fee, U256::from(2_000_000_000u64));
+    }
+
+    #[test]
+    fn test_transaction_type_bytes() {
+        assert_eq!(TransactionType::Legacy.type_byte(), None);
+        assert_eq!(TransactionType::AccessList.type_byte(), Some(U64::from(1)));
+        assert_eq!(TransactionType::Eip1559.type_byte(), Some(U64::from(2)));
+    }
+
+    #[test]
+    fn test_call_request_keeps_fee_fields() {
+        let transaction = TransactionRequest {
+            max_fee_per_gas: Some(U256::from(10)),
+            transaction_type: TransactionType::Eip1559.type_byte(),
+            ..Default::default()
+        };
+        let request = call_request(&transaction);
+        assert_eq!(request.max_fee_per_gas, Some(U256::from(10)));
+        assert_eq!(request.transaction_type, Some(U64::from(2)));
+    }
+
+    #[test]
+    fn test_estimate_gas_offline() {
+        // A dispatcher with one function that stops right away.
+        let code = [
+            0x5f, 0x35, 0x60, 0xe0, 0x1c, // selector
+            0x63, 0xaa, 0xbb, 0xcc, 0xdd, 0x81, 0x14, 0x60, 0x12, 0x57, // jump to 0x12 on a match
+            0x5f, 0x80, 0xfd, // revert
+            0x5b, 0x00, // 0x12: stop
+        ];
+        let call = |data: Vec<u8>| TransactionRequest {
+            to: Some(Address::repeat_byte(2)),
+            data: Some(Bytes(data)),
+            ..Default::default()
+        };
+        let options = GasEstimateOptions::default();
+        let gas_limit = estimate_gas_offline(&code, &call(vec![0xaa, 0xbb, 0xcc, 0xdd]), Hardfork::Prague, &options).unwrap();
+        // max(21,000 + 4 * 16 + 34, 21,000 + 16 * 10) * 120%
+        assert_eq!(gas_limit, U256::from(25_392));
+
+        // With 200 more non-zero bytes the EIP-7623 floor applies only from Prague on.
+        let mut data = vec![0xaa, 0xbb, 0xcc, 0xdd];
+        data.extend([0xff; 200]);
+        let gas_limit = estimate_gas_offline(&code, &call(data.clone()), Hardfork::Shanghai, &options).unwrap();
+        // (21,000 + 204 * 16 + 34) * 120%
+        assert_eq!(gas_limit, U256::from(29_157));
+        let gas_limit = estimate_gas_offline(&code, &call(data), Hardfork::Prague, &options).unwrap();
+        // (21,000 + 816 * 10) * 120%
+        assert_eq!(gas_limit, U256::from(34_992));
+
+        let result = estimate_gas_offline(&code, &call(vec![0; 4]), Hardfork::Prague, &options);
+        assert!(matches!(result, Err(GasOptimizationError::GasCalculationFailed)));
+        let result = estimate_gas_offline(&code, &call(vec![0xaa]), Hardfork::Prague, &options);
+        assert!(matches!(result, Err(GasOptimizationError::GasCalculationFailed)));
+
+        let options = GasEstimateOptions { ceiling: Some(U256::from(21_000)), ..Default::default() };
+        let result = estimate_gas_offline(&code, &call(vec![0xaa, 0xbb, 0xcc, 0xdd]), Hardfork::Prague, &options);
+        assert!(matches!(result, Err(GasOptimizationError::InvalidGasLimit)));
+    }
+
+    #[test]
+    fn test_intrinsic_gas() {
+        let call = TransactionRequest {
+            to: Some(Address::repeat_byte(2)),
+            data: Some(Bytes(vec![0xa9, 0x05, 0x9c, 0xbb, 0, 0, 0, 0])),
+            ..Default::default()
+        };
+        let gas = intrinsic_gas(&call, Hardfork::Berlin);
+        assert_eq!((gas.base, gas.calldata, gas.floor), (21_000, 80, 0));
+        assert_eq!(intrinsic_gas(&call, Hardfork::Frontier).calldata, 4 * 68 + 4 * 4);
+
+        // 4 non-zero and 4 zero bytes are 20 tokens: the floor is 21,200.
+        let gas = intrinsic_gas(&call, Hardfork::Prague);
+        assert_eq!((gas.standard(), gas.floor, gas.required()), (21_080, 21_200, 21_200));
+
+        let with_access_list = TransactionRequest {
+            access_list: Some(vec![AccessListItem { address: Address::repeat_byte(3), storage_keys: vec![H256::zero(); 2] }]),
+            ..call.clone()
+        };
+        assert_eq!(intrinsic_gas(&with_access_list, Hardfork::Prague).access_list, 6_200);
+        assert_eq!(intrinsic_gas(&with_access_list, Hardfork::Istanbul).access_list, 0);
+
+        let create = TransactionRequest { data: Some(Bytes(vec![1; 33])), ..Default::default() };
+        let gas = intrinsic_gas(&create, Hardfork::Shanghai);
+        assert_eq!((gas.base, gas.calldata, gas.initcode), (53_000, 528, 4));
+        assert_eq!(intrinsic_gas(&create, Hardfork::Frontier).base, 21_000);
+    }
+
+    #[test]
+    fn test_check_gas_limit() {
+        let transfer = transfer();
+        assert!(check_gas_limit(21_000, &transfer, Hardfork::default()).is_ok());
+        assert!(matches!(
+            check_gas_limit(0, &transfer, Hardfork::default()),
+            Err(GasOptimizationError::BelowIntrinsicGas { intrinsic_gas: 21_000, .. })
+        ));
+
+        // Calldata-heavy transactions are held to the EIP-7623 floor from Prague on.
+        let calldata = TransactionRequest { data: Some(Bytes(vec![0xff; 1_000])), ..transfer };
+        assert!(check_gas_limit(40_000, &calldata, Hardfork::Shanghai).is_ok());
+        assert!(check_gas_limit(40_000, &calldata, Hardfork::Prague).is_err());
+        assert!(check_gas_limit(61_000, &calldata, Hardfork::Prague).is_ok());
+    }
 }
@@ -0,0 +1,213 @@
+use crate::contracts::fees::{FeeEstimates, FeeSpeed, FeeSuggestion};
+use web3::types::U256;
+use std::fmt::Debug;
+use std::time::Duration;
+
+/// Decides what a transaction pays for gas.
+///
+/// A strategy turns the current fee estimates into fee caps. `elapsed` is the time since the
+/// transaction was first sent, so escalating strategies can pay more the longer it stays
+/// pending; it is zero for the first submission. Teams can implement their own strategy to
+/// pick a different tradeoff between cost and latency.
+pub trait GasStrategy: Debug + Send + Sync {
+    /// Returns the fee caps to pay.
+    ///
+    /// # Arguments
+    /// * `estimates` - The current fee estimates, e.g. from a [`FeeOracle`](crate::contracts::fees::FeeOracle).
+    /// * `elapsed` - The time since the transaction was first sent.
+    fn fees(&self, estimates: &FeeEstimates, elapsed: Duration) -> FeeSuggestion;
+}
+
+/// Always pays the same fees, whatever the network conditions.
+#[derive(Debug, Clone)]
+pub struct FixedGasStrategy {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+}
+
+impl GasStrategy for FixedGasStrategy {
+    fn fees(&self, _estimates: &FeeEstimates, _elapsed: Duration) -> FeeSuggestion {
+        FeeSuggestion {
+            max_fee_per_gas: self.max_fee_per_gas,
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas,
+        }
+    }
+}
+
+/// Pays the fees suggested for a speed, i.e. a percentile of recent priority fees.
+#[derive(Debug, Clone)]
+pub struct PercentileGasStrategy {
+    pub speed: FeeSpeed,
+}
+
+impl GasStrategy for PercentileGasStrategy {
+    fn fees(&self, estimates: &FeeEstimates, _elapsed: Duration) -> FeeSuggestion {
+        estimates.get(self.speed)
+    }
+}
+
+/// Starts from the fees suggested for a speed and adds a fixed amount per interval pending.
+///
+/// # Fields
+/// - `speed`: The suggestion the first submission pays.
+/// - `increment`: The amount added to both fee caps per interval, in wei.
+/// - `every`: The interval between increments.
+/// - `max_fee_per_gas`: The highest `maxFeePerGas` paid, if any.
+#[derive(Debug, Clone)]
+pub struct LinearEscalator {
+    pub speed: FeeSpeed,
+    pub increment: U256,
+    pub every: Duration,
+    pub max_fee_per_gas: Option<U256>,
+}
+
+impl GasStrategy for LinearEscalator {
+    fn fees(&self, estimates: &FeeEstimates, elapsed: Duration) -> FeeSuggestion {
+        let steps = U256::from(intervals(elapsed, self.every));
+        let bump = self.increment.saturating_mul(steps);
+        let start = estimates.get(self.speed);
+        capped(
+            start.max_fee_per_gas.saturating_add(bump),
+            start.max_priority_fee_per_gas.saturating_add(bump),
+            self.max_fee_per_gas,
+        )
+    }
+}
+
+/// Starts from the fees suggested for a speed and multiplies them per interval pending, up to a cap.
+///
+/// # Fields
+/// - `speed`: The suggestion the first submission pays.
+/// - `coefficient_percent`: The fees after each interval as a percentage of the fees before, e.g. `125`.
+/// - `every`: The interval between increases.
+/// - `max_fee_per_gas`: The highest `maxFeePerGas` paid.
+#[derive(Debug, Clone)]
+pub struct GeometricEscalator {
+    pub speed: FeeSpeed,
+    pub coefficient_percent: u64,
+    pub every: Duration,
+    pub max_fee_per_gas: U256,
+}
+
+impl GasStrategy for GeometricEscalator {
+    fn fees(&self, estimates: &FeeEstimates, elapsed: Duration) -> FeeSuggestion {
+        let start = estimates.get(self.speed);
+        let mut max_fee = start.max_fee_per_gas;
+        let mut priority_fee = start.max_priority_fee_per_gas;
+        for _ in 0..intervals(elapsed, self.every) {
+            if max_fee >= self.max_fee_per_gas {
+                break;
+            }
+            max_fee = max_fee.saturating_mul(U256::from(self.coefficient_percent)) / U256::from(100);
+            priority_fee = priority_fee.saturating_mul(U256::from(self.coefficient_percent)) / U256::from(100);
+        }
+        capped(max_fee, priority_fee, Some(self.max_fee_per_gas))
+    }
+}
+
+/// Returns the number of whole intervals in `elapsed`.
+fn intervals(elapsed: Duration, every: Duration) -> u64 {
+    if every.is_zero() {
+        return 0;
+    }
+    (elapsed.as_millis() / every.as_millis().max(1)) as u64
+}
+
+/// Caps `maxFeePerGas`, keeping the priority fee at most the max fee.
+fn capped(max_fee_per_gas: U256, max_priority_fee_per_gas: U256, cap: Option<U256>) -> FeeSuggestion {
+    let max_fee_per_gas = cap.map_or(max_fee_per_gas, |cap| max_fee_per_gas.min(cap));
+    FeeSuggestion {
+        max_fee_per_gas,
+        max_priority_fee_per_gas: max_priority_fee_per_gas.min(max_fee_per_gas),
+    }
+}
+
+/// Adjusts a gas limit to the price of gas: 10% lower when gas is expensive, 10% higher when it is cheap.
+///
+/// # Arguments
+/// * `gas_limit` - The gas limit to adjust.
+/// * `gas_price` - The price of gas.
+/// * `threshold` - The price above which gas counts as expensive.
+///
+/// # Returns
+/// U256 - The adjusted gas limit.
+pub fn adjust_gas_limit(gas_limit: U256, gas_price: U256, threshold: U256) -> U256 {
+    let adjustment_factor = if gas_price > threshold {
+        U256::from(90) // Reduce gas usage if gas price is high
+    } else {
+        U256::from(110) // Increase gas usage if gas price is low
+    };
+    gas_limit * adjustment_factor / U256::from(100)
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    fn estimates() -> FeeEstimates {
+        let suggestion = |priority: u64| FeeSuggestion {
+            max_fee_per_gas: U256::from((20 + priority) * GWEI),
+            max_priority_fee_per_gas: U256::from(priority * GWEI),
+        };
+        FeeEstimates {
+            base_fee: U256::from(10 * GWEI),
+            projected_base_fee: U256::from(10 * GWEI),
+            max_base_fee: U256::from(20 * GWEI),
+            slow: suggestion(1),
+            normal: suggestion(2),
+            fast: suggestion(3),
+        }
+    }
+
+    #[test]
+    fn test_fixed_and_percentile() {
+        let fixed = FixedGasStrategy { max_fee_per_gas: U256::from(50 * GWEI), max_priority_fee_per_gas: U256::from(GWEI) };
+        assert_eq!(fixed.fees(&estimates(), Duration::from_secs(600)).max_fee_per_gas, U256::from(50 * GWEI));
+
+        let fast = PercentileGasStrategy { speed: FeeSpeed::Fast };
+        assert_eq!(fast.fees(&estimates(), Duration::ZERO), estimates().fast);
+    }
+
+    #[test]
+    fn test_linear_escalator() {
+        let strategy = LinearEscalator {
+            speed: FeeSpeed::Slow,
+            increment: U256::from(GWEI),
+            every: Duration::from_secs(30),
+            max_fee_per_gas: Some(U256::from(24 * GWEI)),
+        };
+        assert_eq!(strategy.fees(&estimates(), Duration::ZERO), estimates().slow);
+
+        let fees = strategy.fees(&estimates(), Duration::from_secs(65));
+        assert_eq!(fees.max_fee_per_gas, U256::from(23 * GWEI));
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(3 * GWEI));
+
+        let fees = strategy.fees(&estimates(), Duration::from_secs(600));
+        assert_eq!(fees.max_fee_per_gas, U256::from(24 * GWEI));
+    }
+
+    #[test]
+    fn test_geometric_escalator() {
+        let strategy = GeometricEscalator {
+            speed: FeeSpeed::Normal,
+            coefficient_percent: 150,
+            every: Duration::from_secs(60),
+            max_fee_per_gas: U256::from(40 * GWEI),
+        };
+        let fees = strategy.fees(&estimates(), Duration::from_secs(60));
+        assert_eq!(fees.max_fee_per_gas, U256::from(33 * GWEI));
+        assert_eq!(fees.max_priority_fee_per_gas, U256::from(3 * GWEI));
+
+        let fees = strategy.fees(&estimates(), Duration::from_secs(3600));
+        assert_eq!(fees.max_fee_per_gas, U256::from(40 * GWEI));
+    }
+
+    #[test]
+    fn test_adjust_gas_limit() {
+        assert_eq!(adjust_gas_limit(U256::from(10000), U256::from(150), U256::from(100)), U256::from(9000));
+        assert_eq!(adjust_gas_limit(U256::from(10000), U256::from(50), U256::from(100)), U256::from(11000));
+    }
+}
@@ -1,13 +1,168 @@
 use crate::framework::logging::{log_info, log_error};
+use crate::error::Error;
+use crate::contracts::abi::AbiFunction;
+use crate::contracts::block::BlockTag;
+use crate::contracts::gas::{build_typed_transaction, GasOptimizationError, TypedTransactionOptions};
+use crate::contracts::nonce::{NonceError, NonceManager};
+use crate::contracts::offline::{prepare_transaction, OfflineError, OfflineTransaction};
+use crate::contracts::storage::{StorageKey, StorageLayout};
+use crate::framework::provider::Provider;
+use crate::framework::rpc_batch::RpcBatch;
 use web3::contract::{Contract, Options};
-use web3::types::{Address, U256};
+use web3::contract::tokens::Detokenize;
+use web3::ethabi::Token;
+use serde::Serialize;
+use serde_json::{json, Value};
+use web3::types::{Address, Bytes, CallRequest, TransactionRequest, H256, U256, U64};
+use web3::{Transport, Web3};
+use std::collections::BTreeMap;
 use std::str::FromStr;
+use std::fmt;
 
 /// Errors that can occur during contract interactions.
 #[derive(Debug)]
 pub enum InteractionError {
     InvalidAddress,
     FunctionCallFailed,
+    InvalidParameters,
+    DecodingFailed,
+    /// The node rejected the call or transaction, e.g. because it reverted.
+    Rejected(Box<Error>),
+    /// Preparing the transaction for offline signing failed.
+    Offline(OfflineError),
+    /// Filling in the fees of the transaction failed.
+    Gas(GasOptimizationError),
+}
+
+impl fmt::Display for InteractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InteractionError::InvalidAddress => write!(f, "invalid address"),
+            InteractionError::FunctionCallFailed => write!(f, "function call failed"),
+            InteractionError::InvalidParameters => write!(f, "invalid parameters"),
+            InteractionError::DecodingFailed => write!(f, "failed to decode the outputs"),
+            InteractionError::Rejected(error) => write!(f, "rejected by the node: {}", error),
+            InteractionError::Offline(error) => write!(f, "offline preparation failed: {}", error),
+            InteractionError::Gas(error) => write!(f, "fee calculation failed: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for InteractionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InteractionError::Rejected(error) => Some(error.as_ref()),
+            InteractionError::Offline(error) => Some(error),
+            InteractionError::Gas(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Optional context for a read-only call.
+///
+/// # Fields
+/// - `from`: The address the call is made from, for functions that depend on `msg.sender`.
+/// - `value`: The amount of Ether sent with the call, for payable view simulations.
+/// - `block`: The block the call is executed against; the latest block when `None`.
+/// - `state_overrides`: Account state replaced for the duration of the call only.
+#[derive(Debug, Clone, Default)]
+pub struct CallOptions {
+    pub from: Option<Address>,
+    pub value: Option<U256>,
+    pub block: Option<BlockTag>,
+    pub state_overrides: Option<StateOverride>,
+}
+
+/// The state of one account replaced during a call. Fields left `None` keep their on-chain value.
+///
+/// `state` replaces the whole storage of the account, while `state_diff` only replaces the
+/// given slots; nodes reject an override that sets both.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountOverride {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<U64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<Bytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<BTreeMap<H256, H256>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// A set of account overrides sent as the third parameter of `eth_call`, which lets a call
+/// run as if an address had another balance, nonce, code or storage, without deploying anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct StateOverride {
+    accounts: BTreeMap<Address, AccountOverride>,
+}
+
+impl StateOverride {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the balance of an address.
+    pub fn balance(mut self, address: Address, balance: U256) -> Self {
+        self.account(address).balance = Some(balance);
+        self
+    }
+
+    /// Replaces the nonce of an address.
+    pub fn nonce(mut self, address: Address, nonce: u64) -> Self {
+        self.account(address).nonce = Some(U64::from(nonce));
+        self
+    }
+
+    /// Replaces the runtime code of an address, e.g. with a patched build of a contract.
+    pub fn code(mut self, address: Address, code: Vec<u8>) -> Self {
+        self.account(address).code = Some(Bytes(code));
+        self
+    }
+
+    /// Replaces one storage slot of an address, keeping the others. After
+    /// [`StateOverride::replace_storage`] the slot is added to the replaced storage instead,
+    /// since nodes reject `state` and `stateDiff` on the same account.
+    pub fn storage(mut self, address: Address, slot: U256, value: H256) -> Self {
+        let account = self.account(address);
+        match account.state.as_mut() {
+            Some(state) => state.insert(slot_key(slot), value),
+            None => account.state_diff.get_or_insert_with(BTreeMap::new).insert(slot_key(slot), value),
+        };
+        self
+    }
+
+    /// Replaces the whole storage of an address; slots not given read as zero.
+    pub fn replace_storage(mut self, address: Address, slots: &[(U256, H256)]) -> Self {
+        let state = slots.iter().map(|(slot, value)| (slot_key(*slot), *value)).collect();
+        let account = self.account(address);
+        account.state = Some(state);
+        account.state_diff = None;
+        self
+    }
+
+    /// Returns the override of an address, if any.
+    pub fn get(&self, address: &Address) -> Option<&AccountOverride> {
+        self.accounts.get(address)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    fn account(&mut self, address: Address) -> &mut AccountOverride {
+        self.accounts.entry(address).or_default()
+    }
+}
+
+fn slot_key(slot: U256) -> H256 {
+    let mut key = [0u8; 32];
+    slot.to_big_endian(&mut key);
+    H256(key)
 }
 
 /// Calls a function of a smart contract with security checks and error handling.
@@ -46,14 +201,277 @@ pub async fn call_contract_function(
     }
 }
 
-pub fn fetch_contract_data() {
-    // Contract data fetching logic would go here
+/// Calls a read-only function through `eth_call` and returns its decoded outputs.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `contract_address` - The address of the contract.
+/// * `function` - The ABI of the function to call.
+/// * `params` - Parameters to pass to the function.
+/// * `options` - The sender, value, block and state overrides of the call.
+///
+/// # Returns
+/// Result<Vec<Token>, InteractionError> - Returns the decoded outputs, otherwise returns an error.
+pub async fn call_view_function<T: Transport>(
+    web3: &Web3<T>,
+    contract_address: &str,
+    function: &AbiFunction,
+    params: &[Token],
+    options: CallOptions,
+) -> Result<Vec<Token>, InteractionError> {
+    let address = Address::from_str(contract_address).map_err(|_| InteractionError::InvalidAddress)?;
+
+    let calldata = function.encode_input(params).map_err(|e| {
+        log_error(&e);
+        InteractionError::InvalidParameters
+    })?;
+
+    log_info(&format!("Reading {} from contract: {}", function.signature(), contract_address));
+
+    let request = CallRequest {
+        from: options.from,
+        to: Some(address),
+        value: options.value,
+        data: Some(Bytes(calldata)),
+        ..Default::default()
+    };
+    let mut call_params = vec![json!(request), json!(options.block.unwrap_or_default())];
+    if let Some(overrides) = options.state_overrides.filter(|overrides| !overrides.is_empty()) {
+        call_params.push(json!(overrides));
+    }
+    let output: Bytes = execute(web3, "eth_call", call_params).await.map_err(|e| {
+        log_error(&format!("Call to {} failed: {}", function.signature(), e));
+        InteractionError::Rejected(Box::new(Error::from(e)))
+    })?;
+
+    function.decode_output(&output.0).map_err(|e| {
+        log_error(&e);
+        InteractionError::DecodingFailed
+    })
+}
+
+/// Calls a read-only function and converts its outputs into a Rust type, e.g. `U256` or `(Address, bool)`.
+///
+/// # Arguments
+/// See [`call_view_function`].
+///
+/// # Returns
+/// Result<R, InteractionError> - Returns the typed outputs, otherwise returns an error.
+pub async fn query_contract_function<T: Transport, R: Detokenize>(
+    web3: &Web3<T>,
+    contract_address: &str,
+    function: &AbiFunction,
+    params: &[Token],
+    options: CallOptions,
+) -> Result<R, InteractionError> {
+    let tokens = call_view_function(web3, contract_address, function, params, options).await?;
+    R::from_tokens(tokens).map_err(|_| InteractionError::DecodingFailed)
+}
+
+/// Sends a state-changing function call from a node-managed account, taking the nonce from a shared manager.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `contract_address` - The address of the contract.
+/// * `function` - The ABI of the function to call.
+/// * `params` - Parameters to pass to the function.
+/// * `sender_address` - The address sending the transaction.
+/// * `options` - The transaction type and gas strategy; the node picks the fees when `None`.
+///
+/// # Returns
+/// Result<H256, InteractionError> - Returns the transaction hash, otherwise returns an error.
+pub async fn send_contract_transaction<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    contract_address: &str,
+    function: &AbiFunction,
+    params: &[Token],
+    sender_address: &str,
+    options: Option<&TypedTransactionOptions>,
+) -> Result<H256, InteractionError> {
+    let mut transaction = contract_transaction_request(contract_address, function, params, sender_address)?;
+    if let Some(options) = options {
+        transaction = build_typed_transaction(web3, transaction, options).await.map_err(InteractionError::Gas)?;
+    }
+
+    log_info(&format!("Sending {} to contract: {}", function.signature(), contract_address));
+
+    nonces.send_transaction(web3, transaction).await.map_err(|e| {
+        log_error(&format!("Function call to {} failed.", function.signature()));
+        match e {
+            NonceError::Rejected(error) => InteractionError::Rejected(error),
+            _ => InteractionError::FunctionCallFailed,
+        }
+    })
+}
+
+/// Prepares a state-changing function call for offline signing instead of sending it.
+///
+/// # Arguments
+/// * `web3` - The connection to the node, used only to read chain state.
+/// * `nonces` - The nonce manager shared by all tasks sending from `sender_address`.
+/// * `contract_address` - The address of the contract.
+/// * `function` - The ABI of the function to call.
+/// * `params` - Parameters to pass to the function.
+/// * `sender_address` - The address sending the transaction.
+/// * `options` - The transaction type and fee options.
+///
+/// # Returns
+/// Result<OfflineTransaction, InteractionError> - Returns the unsigned transaction, otherwise returns an error.
+pub async fn prepare_contract_transaction<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    contract_address: &str,
+    function: &AbiFunction,
+    params: &[Token],
+    sender_address: &str,
+    options: &TypedTransactionOptions,
+) -> Result<OfflineTransaction, InteractionError> {
+    let transaction = contract_transaction_request(contract_address, function, params, sender_address)?;
+    let description = format!("Call {} on {}", function.signature(), contract_address);
+    prepare_transaction(web3, nonces, transaction, options, &description)
+        .await
+        .map_err(InteractionError::Offline)
+}
+
+fn contract_transaction_request(
+    contract_address: &str,
+    function: &AbiFunction,
+    params: &[Token],
+    sender_address: &str,
+) -> Result<TransactionRequest, InteractionError> {
+    let address = Address::from_str(contract_address).map_err(|_| InteractionError::InvalidAddress)?;
+    let sender = Address::from_str(sender_address).map_err(|_| InteractionError::InvalidAddress)?;
+
+    let calldata = function.encode_input(params).map_err(|e| {
+        log_error(&e);
+        InteractionError::InvalidParameters
+    })?;
+
+    Ok(TransactionRequest {
+        from: sender,
+        to: Some(address),
+        data: Some(Bytes(calldata)),
+        ..Default::default()
+    })
+}
+
+/// Reads a raw storage slot of a contract through `eth_getStorageAt`.
+///
+/// Slots of mappings, arrays and struct members can be computed with the helpers in
+/// [`crate::contracts::storage`].
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `contract_address` - The address of the contract.
+/// * `slot` - The storage slot to read.
+/// * `block` - The block to read at; the latest block when `None`.
+///
+/// # Returns
+/// Result<H256, InteractionError> - Returns the 32-byte storage word, otherwise returns an error.
+pub async fn fetch_contract_data<T: Transport>(
+    web3: &Web3<T>,
+    contract_address: &str,
+    slot: U256,
+    block: Option<BlockTag>,
+) -> Result<H256, InteractionError> {
+    let address = Address::from_str(contract_address).map_err(|_| InteractionError::InvalidAddress)?;
+
+    let params = vec![json!(address), json!(slot), json!(block.unwrap_or_default())];
+    execute(web3, "eth_getStorageAt", params).await.map_err(|e| {
+        log_error(&format!("Failed to read slot {} of {}: {}", slot, contract_address, e));
+        InteractionError::FunctionCallFailed
+    })
+}
+
+/// Reads several storage slots of a contract with a single JSON-RPC batch request.
+///
+/// # Arguments
+/// * `provider` - The provider the batch is sent through.
+/// * `contract_address` - The address of the contract.
+/// * `slots` - The storage slots to read.
+/// * `block` - The block to read at; the latest block when `None`.
+///
+/// # Returns
+/// Result<Vec<H256>, InteractionError> - Returns the storage words in the order of `slots`,
+/// otherwise returns an error if any read failed.
+pub async fn fetch_contract_data_batch<P: Provider + ?Sized>(
+    provider: &P,
+    contract_address: &str,
+    slots: &[U256],
+    block: Option<BlockTag>,
+) -> Result<Vec<H256>, InteractionError> {
+    let address = Address::from_str(contract_address).map_err(|_| InteractionError::InvalidAddress)?;
+
+    let mut batch = RpcBatch::new();
+    let indexes: Vec<usize> = slots
+        .iter()
+        .map(|slot| batch.get_storage_at(address, *slot, block))
+        .collect();
+
+    let response = batch.send(provider).await.map_err(|_| InteractionError::FunctionCallFailed)?;
+    indexes
+        .into_iter()
+        .map(|index| {
+            response.storage(index).map_err(|e| {
+                log_error(&format!("Failed to read slot {} of {}: {}", slots[index], contract_address, e.message));
+                InteractionError::FunctionCallFailed
+            })
+        })
+        .collect()
+}
+
+/// Reads and decodes a state variable using the contract's solc storage layout, which also
+/// works for private variables without a getter.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `contract_address` - The address of the contract.
+/// * `layout` - The storage layout of the contract.
+/// * `label` - The name of the state variable.
+/// * `path` - Mapping keys, array indexes and struct members leading to a value type.
+/// * `block` - The block to read at; the latest block when `None`.
+///
+/// # Returns
+/// Result<Token, InteractionError> - Returns the decoded value, otherwise returns an error.
+pub async fn fetch_storage_variable<T: Transport>(
+    web3: &Web3<T>,
+    contract_address: &str,
+    layout: &StorageLayout,
+    label: &str,
+    path: &[StorageKey],
+    block: Option<BlockTag>,
+) -> Result<Token, InteractionError> {
+    let (location, type_id) = layout.resolve(label, path).map_err(|e| {
+        log_error(&e);
+        InteractionError::InvalidParameters
+    })?;
+
+    let word = fetch_contract_data(web3, contract_address, location.slot, block).await?;
+
+    layout.decode(&type_id, &location, word).map_err(|e| {
+        log_error(&e);
+        InteractionError::DecodingFailed
+    })
+}
+
+/// Sends a raw request, for block tags and parameters the typed `web3` API does not support.
+async fn execute<T: Transport, R: serde::de::DeserializeOwned>(
+    web3: &Web3<T>,
+    method: &str,
+    params: Vec<Value>,
+) -> Result<R, web3::Error> {
+    let value = web3.transport().execute(method, params).await?;
+    serde_json::from_value(value).map_err(|e| web3::Error::Decoder(e.to_string()))
 }
 
 // Unit test example
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
     use web3::types::U256;
 
     #[tokio::test]
@@ -73,4 +491,111 @@ mod tests {
         let result = call_contract_function("0x1234567890abcdef1234567890abcdef12345678", "failFunction", vec![U256::from(1)]).await;
         assert!(matches!(result, Err(InteractionError::FunctionCallFailed)));
     }
+
+    fn balance_of() -> AbiFunction {
+        AbiFunction {
+            name: "balanceOf".to_string(),
+            inputs: vec!["address".to_string()],
+            outputs: vec!["uint256".to_string()],
+            payable: false,
+            constant: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_view_call_invalid_address() {
+        let web3 = Web3::new(web3::transports::Http::new("http://localhost:8545").unwrap());
+        let result = call_view_function(&web3, "invalid", &balance_of(), &[], CallOptions::default()).await;
+        assert!(matches!(result, Err(InteractionError::InvalidAddress)));
+    }
+
+    #[tokio::test]
+    async fn test_view_call_invalid_parameters() {
+        let web3 = Web3::new(web3::transports::Http::new("http://localhost:8545").unwrap());
+        let result = call_view_function(
+            &web3,
+            "0x1234567890abcdef1234567890abcdef12345678",
+            &balance_of(),
+            &[Token::Bool(true)],
+            CallOptions::default(),
+        )
+        .await;
+        assert!(matches!(result, Err(InteractionError::InvalidParameters)));
+    }
+
+    #[tokio::test]
+    async fn test_view_call_against_mock() {
+        let token = Address::from_str("0x1234567890abcdef1234567890abcdef12345678").unwrap();
+        let holder = Token::Address(Address::repeat_byte(0x11));
+        let mock = MockProvider::new();
+        mock.on_call(
+            token,
+            &balance_of().encode_input(&[holder.clone()]).unwrap(),
+            &web3::ethabi::encode(&[Token::Uint(U256::from(1_000_000))]),
+        );
+        mock.on_error("eth_call", None, 3, "execution reverted");
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let result = call_view_function(&web3, "0x1234567890abcdef1234567890abcdef12345678", &balance_of(), &[holder], CallOptions::default()).await;
+        assert_eq!(result.unwrap(), vec![Token::Uint(U256::from(1_000_000))]);
+
+        let other = Token::Address(Address::repeat_byte(0x22));
+        let result = call_view_function(&web3, "0x1234567890abcdef1234567890abcdef12345678", &balance_of(), &[other], CallOptions::default()).await;
+        let error = Error::from(result.unwrap_err());
+        assert!(matches!(error.root(), Error::ExecutionReverted { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_view_call_at_block_with_state_overrides() {
+        let token = Address::from_str("0x1234567890abcdef1234567890abcdef12345678").unwrap();
+        let holder = Address::repeat_byte(0x11);
+        let overrides = StateOverride::new()
+            .balance(holder, U256::from(5))
+            .storage(token, U256::from(3), H256::from_low_u64_be(7))
+            .code(Address::repeat_byte(0x22), vec![0x60, 0x00]);
+        let mock = MockProvider::new();
+        mock.on_request(
+            "eth_call",
+            Some(json!([
+                {"to": token},
+                "finalized",
+                {
+                    format!("{:?}", holder): {"balance": "0x5"},
+                    format!("{:?}", token): {"stateDiff": {format!("{:?}", H256::from_low_u64_be(3)): H256::from_low_u64_be(7)}}
+                }
+            ])),
+            json!(Bytes(web3::ethabi::encode(&[Token::Uint(U256::from(7))]))),
+        );
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let options = CallOptions { block: Some(BlockTag::Finalized), state_overrides: Some(overrides.clone()), ..Default::default() };
+        let result = call_view_function(&web3, "0x1234567890abcdef1234567890abcdef12345678", &balance_of(), &[Token::Address(holder)], options).await;
+        assert_eq!(result.unwrap(), vec![Token::Uint(U256::from(7))]);
+
+        let options = CallOptions { block: Some(BlockTag::Safe), state_overrides: Some(overrides), ..Default::default() };
+        let result = call_view_function(&web3, "0x1234567890abcdef1234567890abcdef12345678", &balance_of(), &[Token::Address(holder)], options).await;
+        assert!(matches!(result, Err(InteractionError::Rejected(_))));
+    }
+
+    #[test]
+    fn test_storage_after_replace_storage() {
+        let token = Address::repeat_byte(0x12);
+        let overrides = StateOverride::new()
+            .replace_storage(token, &[(U256::from(1), H256::from_low_u64_be(2))])
+            .storage(token, U256::from(3), H256::from_low_u64_be(4));
+        let account = overrides.get(&token).unwrap();
+        assert!(account.state_diff.is_none());
+        assert_eq!(account.state.as_ref().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_contract_data_at_block_hash() {
+        let hash = H256::repeat_byte(0xab);
+        let mock = MockProvider::new();
+        mock.on_request("eth_getStorageAt", Some(json!(["0x1234567890abcdef1234567890abcdef12345678", "0x0", {"blockHash": hash}])), json!(H256::from_low_u64_be(42)));
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let word = fetch_contract_data(&web3, "0x1234567890abcdef1234567890abcdef12345678", U256::zero(), Some(hash.into())).await;
+        assert_eq!(word.unwrap(), H256::from_low_u64_be(42));
+    }
 }
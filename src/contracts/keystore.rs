@@ -0,0 +1,221 @@
+use crate::framework::logging::log_error;
+use aes::Aes128;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use serde::Deserialize;
+use sha2::Sha256;
+use web3::signing::keccak256;
+use std::fmt;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+/// The length of the derived key: 16 bytes of AES key followed by 16 bytes of MAC key.
+const DERIVED_KEY_LENGTH: usize = 32;
+/// The most memory scrypt may use (`128 * n * r` bytes), 1 GiB. Geth's default of
+/// `n = 2^18, r = 8` needs 256 MiB.
+const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+/// The highest scrypt parallelization parameter accepted.
+const MAX_SCRYPT_P: u32 = 16;
+/// The highest number of PBKDF2 rounds accepted; Geth uses 262,144.
+const MAX_PBKDF2_ROUNDS: u32 = 10_000_000;
+
+/// Errors that can occur while decrypting a keystore.
+#[derive(Debug)]
+pub enum KeystoreError {
+    InvalidKeystore,
+    UnsupportedKdf,
+    UnsupportedCipher,
+    WrongPassword,
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::InvalidKeystore => write!(f, "invalid keystore"),
+            KeystoreError::UnsupportedKdf => write!(f, "unsupported key derivation function"),
+            KeystoreError::UnsupportedCipher => write!(f, "unsupported cipher"),
+            KeystoreError::WrongPassword => write!(f, "wrong password"),
+        }
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+#[derive(Debug, Deserialize)]
+struct Keystore {
+    version: u8,
+    #[serde(alias = "Crypto")]
+    crypto: KeystoreCrypto,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeystoreCrypto {
+    cipher: String,
+    cipherparams: CipherParams,
+    ciphertext: String,
+    kdf: String,
+    kdfparams: KdfParams,
+    mac: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KdfParams {
+    dklen: usize,
+    salt: String,
+    // scrypt
+    n: Option<u64>,
+    r: Option<u32>,
+    p: Option<u32>,
+    // pbkdf2
+    c: Option<u32>,
+    prf: Option<String>,
+}
+
+/// Decrypts an Ethereum keystore v3 JSON file and returns the private key.
+///
+/// Both the `scrypt` and `pbkdf2` (`hmac-sha256`) key derivation functions are supported,
+/// with the `aes-128-ctr` cipher. The KDF parameters come from the file, so they are checked
+/// against fixed limits before any key is derived; keystores that would need more than 1 GiB
+/// of memory or an unusual derived key length are rejected as invalid.
+///
+/// # Arguments
+/// * `keystore_json` - The content of the keystore file.
+/// * `password` - The password the keystore was encrypted with.
+///
+/// # Returns
+/// Result<Vec<u8>, KeystoreError> - Returns the private key, otherwise returns an error.
+pub fn decrypt_keystore(keystore_json: &str, password: &str) -> Result<Vec<u8>, KeystoreError> {
+    let keystore: Keystore = serde_json::from_str(keystore_json).map_err(|e| {
+        log_error(&format!("Failed to parse keystore: {}", e));
+        KeystoreError::InvalidKeystore
+    })?;
+    if keystore.version != 3 {
+        return Err(KeystoreError::InvalidKeystore);
+    }
+    let crypto = keystore.crypto;
+    if crypto.cipher != "aes-128-ctr" {
+        return Err(KeystoreError::UnsupportedCipher);
+    }
+
+    let params = &crypto.kdfparams;
+    if params.dklen != DERIVED_KEY_LENGTH {
+        log_error(&format!("Unsupported derived key length {}.", params.dklen));
+        return Err(KeystoreError::InvalidKeystore);
+    }
+    let salt = from_hex(&params.salt)?;
+    let mut derived_key = [0u8; DERIVED_KEY_LENGTH];
+    match crypto.kdf.as_str() {
+        "scrypt" => {
+            let n = params.n.ok_or(KeystoreError::InvalidKeystore)?;
+            let r = params.r.ok_or(KeystoreError::InvalidKeystore)?;
+            let p = params.p.ok_or(KeystoreError::InvalidKeystore)?;
+            let memory = 128u64.saturating_mul(n).saturating_mul(u64::from(r));
+            if !n.is_power_of_two() || memory > MAX_SCRYPT_MEMORY || p > MAX_SCRYPT_P {
+                log_error(&format!("Scrypt parameters n = {}, r = {}, p = {} are out of bounds.", n, r, p));
+                return Err(KeystoreError::InvalidKeystore);
+            }
+            let scrypt_params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, DERIVED_KEY_LENGTH)
+                .map_err(|_| KeystoreError::InvalidKeystore)?;
+            scrypt::scrypt(password.as_bytes(), &salt, &scrypt_params, &mut derived_key)
+                .map_err(|_| KeystoreError::InvalidKeystore)?;
+        }
+        "pbkdf2" => {
+            if params.prf.as_deref() != Some("hmac-sha256") {
+                return Err(KeystoreError::UnsupportedKdf);
+            }
+            let rounds = params.c.ok_or(KeystoreError::InvalidKeystore)?;
+            if rounds > MAX_PBKDF2_ROUNDS {
+                log_error(&format!("{} PBKDF2 rounds are out of bounds.", rounds));
+                return Err(KeystoreError::InvalidKeystore);
+            }
+            pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), &salt, rounds, &mut derived_key);
+        }
+        _ => return Err(KeystoreError::UnsupportedKdf),
+    }
+
+    // The MAC is keccak256 of the second half of the derived key followed by the ciphertext.
+    let mut ciphertext = from_hex(&crypto.ciphertext)?;
+    let mut mac_input = derived_key[16..32].to_vec();
+    mac_input.extend_from_slice(&ciphertext);
+    if keccak256(&mac_input).to_vec() != from_hex(&crypto.mac)? {
+        return Err(KeystoreError::WrongPassword);
+    }
+
+    let iv = from_hex(&crypto.cipherparams.iv)?;
+    if iv.len() != 16 {
+        return Err(KeystoreError::InvalidKeystore);
+    }
+    let mut cipher = Aes128Ctr::new(derived_key[..16].into(), iv.as_slice().into());
+    cipher.apply_keystream(&mut ciphertext);
+    Ok(ciphertext)
+}
+
+fn from_hex(value: &str) -> Result<Vec<u8>, KeystoreError> {
+    let value = value.trim_start_matches("0x");
+    if !value.len().is_multiple_of(2) {
+        return Err(KeystoreError::InvalidKeystore);
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).map_err(|_| KeystoreError::InvalidKeystore))
+        .collect()
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::abi::to_hex;
+
+    // Test vector from the Web3 Secret Storage Definition.
+    const PBKDF2_KEYSTORE: &str = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"6087dab2f9fdbbfaddc31a909735c1e6"},"ciphertext":"5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46","kdf":"pbkdf2","kdfparams":{"c":262144,"dklen":32,"prf":"hmac-sha256","salt":"ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"},"mac":"517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"},"id":"3198bc9c-6672-5ab3-d995-4942343ae5b6","version":3}"#;
+    // Test vector `31_byte_key` from go-ethereum's keystore test data.
+    const SCRYPT_KEYSTORE: &str = r#"{"crypto":{"cipher":"aes-128-ctr","cipherparams":{"iv":"e0c41130a323adc1446fc82f724bca2f"},"ciphertext":"9517cd5bdbe69076f9bf5057248c6c050141e970efa36ce53692d5d59a3984","kdf":"scrypt","kdfparams":{"dklen":32,"n":2,"r":8,"p":1,"salt":"711f816911c92d649fb4c84b047915679933555030b3552c1212609b38208c63"},"mac":"d5e116151c6aa71470e67a7d42c9620c75c4d23229847dcc127794f0732b0db5"},"version":3}"#;
+    const PRIVATE_KEY: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    #[test]
+    fn test_pbkdf2_keystore() {
+        let key = decrypt_keystore(PBKDF2_KEYSTORE, "testpassword").unwrap();
+        assert_eq!(to_hex(&key), PRIVATE_KEY);
+    }
+
+    #[test]
+    fn test_scrypt_keystore() {
+        let key = decrypt_keystore(SCRYPT_KEYSTORE, "foo").unwrap();
+        assert_eq!(to_hex(&key), "fa7b3db73dc7dfdf8c5fbdb796d741e4488628c41fc4febd9160a866ba0f35");
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let result = decrypt_keystore(PBKDF2_KEYSTORE, "wrongpassword");
+        assert!(matches!(result, Err(KeystoreError::WrongPassword)));
+    }
+
+    #[test]
+    fn test_invalid_keystore() {
+        let result = decrypt_keystore("{}", "testpassword");
+        assert!(matches!(result, Err(KeystoreError::InvalidKeystore)));
+    }
+
+    #[test]
+    fn test_unbounded_kdf_params_rejected() {
+        let huge_dklen = PBKDF2_KEYSTORE.replace("\"dklen\":32", "\"dklen\":18446744073709551615");
+        assert!(matches!(decrypt_keystore(&huge_dklen, "testpassword"), Err(KeystoreError::InvalidKeystore)));
+
+        let short_dklen = SCRYPT_KEYSTORE.replace("\"dklen\":32", "\"dklen\":16");
+        assert!(matches!(decrypt_keystore(&short_dklen, "foo"), Err(KeystoreError::InvalidKeystore)));
+
+        let huge_n = SCRYPT_KEYSTORE.replace("\"n\":2", "\"n\":4294967296");
+        assert!(matches!(decrypt_keystore(&huge_n, "foo"), Err(KeystoreError::InvalidKeystore)));
+
+        let huge_p = SCRYPT_KEYSTORE.replace("\"p\":1", "\"p\":4294967295");
+        assert!(matches!(decrypt_keystore(&huge_p, "foo"), Err(KeystoreError::InvalidKeystore)));
+
+        let huge_c = PBKDF2_KEYSTORE.replace("\"c\":262144", "\"c\":4294967295");
+        assert!(matches!(decrypt_keystore(&huge_c, "testpassword"), Err(KeystoreError::InvalidKeystore)));
+    }
+}
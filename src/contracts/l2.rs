@@ -0,0 +1,504 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::contracts::abi::{encode_call, to_hex};
+use crate::contracts::gas::{
+    buffered_gas_limit, estimate_transaction_gas, gas_ceiling, GasEstimateOptions, GasOptimizationError, TransactionType,
+};
+use crate::contracts::network::{Network, Rollup};
+use crate::contracts::signer::UnsignedTransaction;
+use crate::error::Error;
+use serde_json::{json, Value};
+use web3::ethabi::{decode, ParamType, Token};
+use web3::types::{Address, Bytes, TransactionRequest, U256};
+use web3::{Transport, Web3};
+
+/// The `GasPriceOracle` predeploy of OP-stack chains.
+pub const GAS_PRICE_ORACLE_ADDRESS: &str = "0x420000000000000000000000000000000000000F";
+
+/// The `NodeInterface` virtual contract of Arbitrum chains.
+pub const NODE_INTERFACE_ADDRESS: &str = "0x00000000000000000000000000000000000000C8";
+
+/// The bytes a signature adds to an unsigned transaction, which OP-stack pricing counts as non-zero.
+const SIGNATURE_BYTES: u64 = 68;
+
+/// The Fjord linear regression of the compressed size: `intercept + coefficient * fastlz_size`, scaled by 1e6.
+const FJORD_INTERCEPT: i64 = -42_585_600;
+const FJORD_FASTLZ_COEFFICIENT: i64 = 836_500;
+const FJORD_MIN_TRANSACTION_SIZE: i64 = 100;
+
+/// The OP-stack hard fork that decides the L1 data fee formula.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OpStackUpgrade {
+    /// Calldata priced at the L1 base fee with a fixed overhead and scalar.
+    Bedrock,
+    /// Calldata priced at a mix of the L1 base fee and the blob base fee.
+    Ecotone,
+    /// Like Ecotone, but priced by the FastLZ-compressed size of the transaction.
+    Fjord,
+}
+
+/// The L1 fee parameters read from the `GasPriceOracle`.
+///
+/// # Fields
+/// - `upgrade`: The active fee formula.
+/// - `l1_base_fee`: The L1 base fee known to the L2.
+/// - `blob_base_fee`: The L1 blob base fee (Ecotone and later).
+/// - `base_fee_scalar`, `blob_base_fee_scalar`: The weights of both fees, scaled by 1e6 (Ecotone and later).
+/// - `overhead`, `scalar`: The fixed gas overhead and the fee scalar, scaled by 1e6 (Bedrock).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpStackFeeParams {
+    pub upgrade: OpStackUpgrade,
+    pub l1_base_fee: U256,
+    pub blob_base_fee: U256,
+    pub base_fee_scalar: U256,
+    pub blob_base_fee_scalar: U256,
+    pub overhead: U256,
+    pub scalar: U256,
+}
+
+impl OpStackFeeParams {
+    /// Reads the fee parameters of the active upgrade from the `GasPriceOracle`.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to an OP-stack node.
+    ///
+    /// # Returns
+    /// Result<OpStackFeeParams, GasOptimizationError> - Returns the parameters, otherwise returns an error.
+    pub async fn fetch<T: Transport>(web3: &Web3<T>) -> Result<Self, GasOptimizationError> {
+        let oracle: Address = GAS_PRICE_ORACLE_ADDRESS.parse().expect("valid GasPriceOracle address");
+        // The upgrade flags do not exist before their fork, so a failed call means "not active".
+        let is_fjord = call_uint(web3, oracle, "isFjord()").await.map(|flag| !flag.is_zero()).unwrap_or(false);
+        let is_ecotone =
+            is_fjord || call_uint(web3, oracle, "isEcotone()").await.map(|flag| !flag.is_zero()).unwrap_or(false);
+
+        let l1_base_fee = call_uint(web3, oracle, "l1BaseFee()").await?;
+        let params = if is_ecotone {
+            Self {
+                upgrade: if is_fjord { OpStackUpgrade::Fjord } else { OpStackUpgrade::Ecotone },
+                l1_base_fee,
+                blob_base_fee: call_uint(web3, oracle, "blobBaseFee()").await?,
+                base_fee_scalar: call_uint(web3, oracle, "baseFeeScalar()").await?,
+                blob_base_fee_scalar: call_uint(web3, oracle, "blobBaseFeeScalar()").await?,
+                overhead: U256::zero(),
+                scalar: U256::zero(),
+            }
+        } else {
+            Self {
+                upgrade: OpStackUpgrade::Bedrock,
+                l1_base_fee,
+                blob_base_fee: U256::zero(),
+                base_fee_scalar: U256::zero(),
+                blob_base_fee_scalar: U256::zero(),
+                overhead: call_uint(web3, oracle, "overhead()").await?,
+                scalar: call_uint(web3, oracle, "scalar()").await?,
+            }
+        };
+        log_info(&format!("OP-stack L1 fee parameters ({:?}): L1 base fee {}.", params.upgrade, l1_base_fee));
+        Ok(params)
+    }
+
+    /// Computes the L1 data fee of a transaction the way the `GasPriceOracle` does.
+    ///
+    /// # Arguments
+    /// * `unsigned_transaction` - The RLP-encoded unsigned transaction; the signature is accounted for.
+    ///
+    /// # Returns
+    /// (U256, U256) - The L1 data fee in wei and the L1 gas it pays for.
+    pub fn l1_fee(&self, unsigned_transaction: &[u8]) -> (U256, U256) {
+        let million = U256::from(1_000_000);
+        match self.upgrade {
+            OpStackUpgrade::Bedrock => {
+                let l1_gas = calldata_gas(unsigned_transaction) + self.overhead;
+                (l1_gas * self.l1_base_fee * self.scalar / million, l1_gas)
+            }
+            OpStackUpgrade::Ecotone => {
+                let l1_gas = calldata_gas(unsigned_transaction);
+                (l1_gas * self.weighted_fee() / (U256::from(16) * million), l1_gas)
+            }
+            OpStackUpgrade::Fjord => {
+                let fastlz_size = flz_compress_len(unsigned_transaction) as i64 + SIGNATURE_BYTES as i64;
+                let estimated_size = (FJORD_INTERCEPT + FJORD_FASTLZ_COEFFICIENT * fastlz_size)
+                    .max(FJORD_MIN_TRANSACTION_SIZE * 1_000_000) as u64;
+                let fee = U256::from(estimated_size) * self.weighted_fee() / (million * million);
+                (fee, U256::from(estimated_size) * U256::from(16) / million)
+            }
+        }
+    }
+
+    /// `16 * baseFeeScalar * l1BaseFee + blobBaseFeeScalar * blobBaseFee`, the per-byte price scaled by 16e6.
+    fn weighted_fee(&self) -> U256 {
+        U256::from(16) * self.base_fee_scalar * self.l1_base_fee + self.blob_base_fee_scalar * self.blob_base_fee
+    }
+}
+
+/// A fee estimate for an L2 transaction, split into L2 execution and L1 data costs.
+///
+/// # Fields
+/// - `rollup`: The rollup stack the estimate is for; `None` on an L1.
+/// - `gas_limit`: The gas limit to set on the transaction, with the safety margins applied. On
+///   Arbitrum it includes the L2 gas charged for L1 data.
+/// - `l2_gas`: The gas used by execution on the L2.
+/// - `l2_gas_price`: The price per L2 gas.
+/// - `l2_execution_fee`: `l2_gas * l2_gas_price`, in wei.
+/// - `l1_data_gas`: The gas paying for the L1 data: L1 gas on OP-stack chains, L2 gas on Arbitrum.
+/// - `l1_data_fee`: The cost of posting the transaction to L1, in wei.
+/// - `total_fee`: `l2_execution_fee + l1_data_fee`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2GasEstimate {
+    pub rollup: Option<Rollup>,
+    pub gas_limit: U256,
+    pub l2_gas: U256,
+    pub l2_gas_price: U256,
+    pub l2_execution_fee: U256,
+    pub l1_data_gas: U256,
+    pub l1_data_fee: U256,
+    pub total_fee: U256,
+}
+
+/// Estimates the fee of a transaction including the L1 data cost of the network's rollup stack.
+///
+/// On OP-stack chains the execution gas comes from `eth_estimateGas` and the L1 data fee from the
+/// `GasPriceOracle` parameters. On Arbitrum both come from `NodeInterface.gasEstimateComponents`.
+/// On chains without a rollup the L1 components are zero.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `network` - The network profile, whose `features.rollup` selects the pricing.
+/// * `transaction` - The transaction to estimate.
+/// * `options` - The multiplier, floor and ceiling applied to the gas limit.
+///
+/// # Returns
+/// Result<L2GasEstimate, GasOptimizationError> - Returns the estimate, otherwise returns an error.
+pub async fn estimate_l2_gas<T: Transport>(
+    web3: &Web3<T>,
+    network: &Network,
+    transaction: &TransactionRequest,
+    options: &GasEstimateOptions,
+) -> Result<L2GasEstimate, GasOptimizationError> {
+    let estimate = match network.features.rollup {
+        Some(Rollup::Arbitrum) => estimate_arbitrum(web3, transaction, options).await?,
+        rollup => {
+            let l2 = estimate_transaction_gas(web3, transaction, options).await?;
+            let (l1_data_fee, l1_data_gas) = match rollup {
+                Some(Rollup::OpStack) => {
+                    let params = OpStackFeeParams::fetch(web3).await?;
+                    params.l1_fee(&unsigned_encoding(network, transaction, l2.gas_limit, l2.gas_price))
+                }
+                _ => (U256::zero(), U256::zero()),
+            };
+            L2GasEstimate {
+                rollup,
+                gas_limit: l2.gas_limit,
+                l2_gas: l2.estimated_gas,
+                l2_gas_price: l2.gas_price,
+                l2_execution_fee: l2.expected_cost,
+                l1_data_gas,
+                l1_data_fee,
+                total_fee: l2.expected_cost.saturating_add(l1_data_fee),
+            }
+        }
+    };
+    log_info(&format!(
+        "Estimated fee on {}: {} wei ({} L2 execution, {} L1 data).",
+        network.name, estimate.total_fee, estimate.l2_execution_fee, estimate.l1_data_fee
+    ));
+    Ok(estimate)
+}
+
+async fn estimate_arbitrum<T: Transport>(
+    web3: &Web3<T>,
+    transaction: &TransactionRequest,
+    options: &GasEstimateOptions,
+) -> Result<L2GasEstimate, GasOptimizationError> {
+    let calldata = encode_call(
+        "gasEstimateComponents(address,bool,bytes)",
+        &[
+            Token::Address(transaction.to.unwrap_or_default()),
+            Token::Bool(transaction.to.is_none()),
+            Token::Bytes(transaction.data.clone().unwrap_or_default().0),
+        ],
+    );
+    let mut call = json!({"from": transaction.from, "to": NODE_INTERFACE_ADDRESS, "data": Bytes(calldata)});
+    if let Some(value) = transaction.value {
+        call["value"] = json!(value);
+    }
+    let output = execute_call(web3, call).await?;
+    let tokens = decode(&[ParamType::Uint(64), ParamType::Uint(64), ParamType::Uint(256), ParamType::Uint(256)], &output)
+        .map_err(|e| {
+            log_error(&format!("Invalid gasEstimateComponents output: {}", e));
+            GasOptimizationError::GasCalculationFailed
+        })?;
+    let component = |index: usize| tokens[index].clone().into_uint().unwrap_or_default();
+    let (gas_estimate, l1_data_gas, base_fee) = (component(0), component(1), component(2));
+
+    let ceiling = gas_ceiling(web3, options).await?;
+    let gas_limit = buffered_gas_limit(gas_estimate, Some(ceiling), options)?;
+    let l2_gas = gas_estimate.saturating_sub(l1_data_gas);
+    let l2_execution_fee = l2_gas.saturating_mul(base_fee);
+    let l1_data_fee = l1_data_gas.saturating_mul(base_fee);
+    Ok(L2GasEstimate {
+        rollup: Some(Rollup::Arbitrum),
+        gas_limit,
+        l2_gas,
+        l2_gas_price: base_fee,
+        l2_execution_fee,
+        l1_data_gas,
+        l1_data_fee,
+        total_fee: l2_execution_fee.saturating_add(l1_data_fee),
+    })
+}
+
+/// Encodes the transaction as it would be signed, filling unset fields with the estimate.
+fn unsigned_encoding(network: &Network, transaction: &TransactionRequest, gas: U256, gas_price: U256) -> Vec<u8> {
+    UnsignedTransaction {
+        transaction_type: network.default_transaction_type(),
+        chain_id: network.chain_id,
+        nonce: transaction.nonce.unwrap_or_default(),
+        to: transaction.to,
+        value: transaction.value.unwrap_or_default(),
+        data: transaction.data.clone().unwrap_or_default().0,
+        gas: transaction.gas.unwrap_or(gas),
+        gas_price: transaction.gas_price.unwrap_or(gas_price),
+        max_fee_per_gas: transaction.max_fee_per_gas.unwrap_or(gas_price),
+        max_priority_fee_per_gas: transaction.max_priority_fee_per_gas.unwrap_or_default(),
+        access_list: if network.default_transaction_type() == TransactionType::Legacy {
+            Vec::new()
+        } else {
+            transaction.access_list.clone().unwrap_or_default()
+        },
+    }
+    .encode_unsigned()
+}
+
+/// The calldata gas of the oracle's Bedrock and Ecotone formulas: 4 per zero byte and 16 per
+/// non-zero byte, with the signature counted as non-zero.
+fn calldata_gas(data: &[u8]) -> U256 {
+    let zeroes = data.iter().filter(|byte| **byte == 0).count() as u64;
+    let non_zeroes = data.len() as u64 - zeroes + SIGNATURE_BYTES;
+    U256::from(zeroes * 4 + non_zeroes * 16)
+}
+
+/// Returns the size of `input` compressed with FastLZ level 1, as used by the Fjord fee formula.
+///
+/// This is a port of `FlzCompressLen` from op-geth and `LibZip.flzCompress` from Solady; only the
+/// length of the output is computed.
+pub fn flz_compress_len(input: &[u8]) -> u32 {
+    let u24 = |i: u32| {
+        let i = i as usize;
+        u32::from(input[i]) | u32::from(input[i + 1]) << 8 | u32::from(input[i + 2]) << 16
+    };
+    let hash = |value: u32| (2_654_435_769u32.wrapping_mul(value) >> 19) & 0x1fff;
+    let literals = |run: u32, size: &mut u32| {
+        // Runs of up to 32 bytes, each with a one-byte header.
+        let rest = run % 0x20;
+        *size += 0x21 * (run / 0x20) + if rest > 0 { rest + 1 } else { 0 };
+    };
+    let matched = |length: u32, size: &mut u32| {
+        let length = length - 1;
+        *size += 3 * (length / 262) + if length % 262 >= 6 { 3 } else { 2 };
+    };
+
+    let mut size = 0;
+    let mut table = vec![0u32; 8192];
+    let mut anchor = 0;
+    let ip_limit = (input.len() as u32).saturating_sub(13);
+    let mut ip = anchor + 2;
+    while ip < ip_limit {
+        let mut reference;
+        loop {
+            let sequence = u24(ip);
+            let slot = hash(sequence) as usize;
+            reference = table[slot];
+            table[slot] = ip;
+            let distance = ip.wrapping_sub(reference);
+            if ip >= ip_limit {
+                break;
+            }
+            ip += 1;
+            if distance <= 0x1fff && sequence == u24(reference) {
+                break;
+            }
+        }
+        if ip >= ip_limit {
+            break;
+        }
+        ip -= 1;
+        if ip > anchor {
+            literals(ip - anchor, &mut size);
+        }
+        let (p, q) = (reference + 3, ip + 3);
+        let mut end = ip_limit + 9 - q;
+        let mut length = 0;
+        while length < end {
+            if input[(p + length) as usize] != input[(q + length) as usize] {
+                end = 0;
+            }
+            length += 1;
+        }
+        matched(length, &mut size);
+        ip += length;
+        for _ in 0..2 {
+            table[hash(u24(ip)) as usize] = ip;
+            ip += 1;
+        }
+        anchor = ip;
+    }
+    literals(input.len() as u32 - anchor, &mut size);
+    size
+}
+
+/// Calls a parameterless view function and decodes its `uint256` result.
+async fn call_uint<T: Transport>(web3: &Web3<T>, to: Address, signature: &str) -> Result<U256, GasOptimizationError> {
+    let output = execute_call(web3, json!({"to": to, "data": Bytes(encode_call(signature, &[]))})).await?;
+    if output.len() < 32 {
+        log_warn(&format!("{} returned {} bytes: 0x{}", signature, output.len(), to_hex(&output)));
+        return Err(GasOptimizationError::GasCalculationFailed);
+    }
+    Ok(U256::from_big_endian(&output[..32]))
+}
+
+async fn execute_call<T: Transport>(web3: &Web3<T>, call: Value) -> Result<Vec<u8>, GasOptimizationError> {
+    let value = web3.transport().execute("eth_call", vec![call, json!("latest")]).await.map_err(|e| {
+        GasOptimizationError::Rejected(Box::new(Error::from(e)))
+    })?;
+    let output: Bytes = serde_json::from_value(value).map_err(|_| GasOptimizationError::NetworkQueryFailed)?;
+    Ok(output.0)
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use web3::ethabi::encode;
+
+    const GWEI: u64 = 1_000_000_000;
+
+    /// Incompressible bytes from an xorshift generator.
+    fn noise(len: usize) -> Vec<u8> {
+        let mut state = 0x9e37_79b9u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn ecotone(upgrade: OpStackUpgrade) -> OpStackFeeParams {
+        OpStackFeeParams {
+            upgrade,
+            l1_base_fee: U256::from(10 * GWEI),
+            blob_base_fee: U256::from(GWEI),
+            base_fee_scalar: U256::from(1_368),
+            blob_base_fee_scalar: U256::from(810_949),
+            overhead: U256::zero(),
+            scalar: U256::zero(),
+        }
+    }
+
+    #[test]
+    fn test_bedrock_and_ecotone_fees() {
+        let data = [1u8; 100];
+        let bedrock = OpStackFeeParams {
+            upgrade: OpStackUpgrade::Bedrock,
+            overhead: U256::from(188),
+            scalar: U256::from(684_000),
+            ..ecotone(OpStackUpgrade::Bedrock)
+        };
+        // (100 + 68) * 16 + 188 = 2,876 L1 gas at 10 gwei, scaled by 0.684.
+        assert_eq!(bedrock.l1_fee(&data), (U256::from(19_671_840_000_000u64), U256::from(2_876)));
+
+        // 2,688 * (16 * 1,368 * 10 gwei + 810,949 * 1 gwei) / 16e6
+        let (fee, l1_gas) = ecotone(OpStackUpgrade::Ecotone).l1_fee(&data);
+        assert_eq!(l1_gas, U256::from(2_688));
+        assert_eq!(fee, U256::from(173_011_272_000u64));
+    }
+
+    #[test]
+    fn test_fjord_fee() {
+        let params = ecotone(OpStackUpgrade::Fjord);
+        // Small transactions pay for the 100-byte minimum.
+        let (fee, l1_gas) = params.l1_fee(&[1u8; 10]);
+        assert_eq!(l1_gas, U256::from(1_600));
+        assert_eq!(fee, U256::from(100) * params.weighted_fee() / U256::from(1_000_000));
+
+        // Compressible data pays less than incompressible data of the same length.
+        let random = noise(2_000);
+        assert!(params.l1_fee(&[7u8; 2_000]).0 < params.l1_fee(&random).0);
+    }
+
+    #[test]
+    fn test_flz_compress_len() {
+        assert_eq!(flz_compress_len(&[]), 0);
+        // Short inputs are a single literal run: one header byte plus the data.
+        assert_eq!(flz_compress_len(&[1, 2, 3, 4, 5]), 6);
+        assert!(flz_compress_len(&[0u8; 1_000]) < 40);
+        assert!(flz_compress_len(&noise(1_000)) > 1_000);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_on_op_stack() {
+        let oracle: Address = GAS_PRICE_ORACLE_ADDRESS.parse().unwrap();
+        let word = |value: u64| encode(&[Token::Uint(U256::from(value))]);
+        let mock = MockProvider::new();
+        mock.on_call(oracle, &encode_call("isFjord()", &[]), &word(0));
+        mock.on_call(oracle, &encode_call("isEcotone()", &[]), &word(1));
+        mock.on_call(oracle, &encode_call("l1BaseFee()", &[]), &word(10 * GWEI));
+        mock.on_call(oracle, &encode_call("blobBaseFee()", &[]), &word(GWEI));
+        mock.on_call(oracle, &encode_call("baseFeeScalar()", &[]), &word(1_368));
+        mock.on_call(oracle, &encode_call("blobBaseFeeScalar()", &[]), &word(810_949));
+        mock.on_request("eth_estimateGas", None, json!(U256::from(50_000)));
+        mock.on_request("eth_getBlockByNumber", None, json!({"gasLimit": "0x1c9c380", "baseFeePerGas": "0x3b9aca00"}));
+        mock.on_request("eth_maxPriorityFeePerGas", None, json!("0x0"));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let transaction = TransactionRequest {
+            from: Address::repeat_byte(1),
+            to: Some(Address::repeat_byte(2)),
+            data: Some(Bytes(vec![1u8; 100])),
+            ..Default::default()
+        };
+        let estimate = estimate_l2_gas(&web3, &Network::optimism(), &transaction, &GasEstimateOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(estimate.rollup, Some(Rollup::OpStack));
+        assert_eq!(estimate.l2_gas, U256::from(50_000));
+        assert_eq!(estimate.l2_execution_fee, U256::from(50_000 * GWEI));
+        let expected = ecotone(OpStackUpgrade::Ecotone)
+            .l1_fee(&unsigned_encoding(&Network::optimism(), &transaction, U256::from(60_000), U256::from(GWEI)));
+        assert_eq!(estimate.l1_data_fee, expected.0);
+        assert_eq!(estimate.total_fee, estimate.l2_execution_fee + estimate.l1_data_fee);
+    }
+
+    #[tokio::test]
+    async fn test_estimate_on_arbitrum() {
+        let output = encode(&[
+            Token::Uint(U256::from(600_000)),
+            Token::Uint(U256::from(450_000)),
+            Token::Uint(U256::from(10_000_000)),
+            Token::Uint(U256::from(30 * GWEI)),
+        ]);
+        let mock = MockProvider::new();
+        mock.on_request("eth_call", None, json!(Bytes(output)));
+        mock.on_request("eth_getBlockByNumber", None, json!({"gasLimit": "0x1c9c380", "baseFeePerGas": "0x5f5e100"}));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let transaction = TransactionRequest { to: Some(Address::repeat_byte(2)), ..Default::default() };
+        let estimate = estimate_l2_gas(&web3, &Network::arbitrum_one(), &transaction, &GasEstimateOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(estimate.gas_limit, U256::from(720_000));
+        assert_eq!(estimate.l2_gas, U256::from(150_000));
+        assert_eq!(estimate.l1_data_gas, U256::from(450_000));
+        assert_eq!(estimate.l1_data_fee, U256::from(4_500_000_000_000u64));
+        assert_eq!(estimate.total_fee, U256::from(6_000_000_000_000u64));
+
+        // An estimate above the ceiling is refused rather than clamped.
+        let options = GasEstimateOptions { ceiling: Some(U256::from(500_000)), ..Default::default() };
+        let result = estimate_l2_gas(&web3, &Network::arbitrum_one(), &transaction, &options).await;
+        assert!(matches!(result, Err(GasOptimizationError::InvalidGasLimit)));
+    }
+}
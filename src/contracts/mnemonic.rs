@@ -0,0 +1,130 @@
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use sha2::Sha512;
+use std::fmt;
+
+/// The BIP-44 path of the first Ethereum account, as used by most wallets.
+pub const DEFAULT_DERIVATION_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Offset marking a BIP-32 child index as hardened.
+const HARDENED: u32 = 0x8000_0000;
+
+/// Errors that can occur while deriving a key from a mnemonic.
+#[derive(Debug)]
+pub enum MnemonicError {
+    InvalidMnemonic,
+    InvalidDerivationPath,
+    DerivationFailed,
+}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MnemonicError::InvalidMnemonic => write!(f, "invalid mnemonic"),
+            MnemonicError::InvalidDerivationPath => write!(f, "invalid derivation path"),
+            MnemonicError::DerivationFailed => write!(f, "key derivation failed"),
+        }
+    }
+}
+
+impl std::error::Error for MnemonicError {}
+
+/// Derives a private key from a BIP-39 mnemonic along a BIP-32 derivation path.
+///
+/// # Arguments
+/// * `phrase` - The mnemonic words, separated by spaces.
+/// * `passphrase` - The optional BIP-39 passphrase; empty for none.
+/// * `path` - The derivation path, e.g. [`DEFAULT_DERIVATION_PATH`].
+///
+/// # Returns
+/// Result<SecretKey, MnemonicError> - Returns the derived private key, otherwise returns an error.
+pub fn derive_private_key(phrase: &str, passphrase: &str, path: &str) -> Result<SecretKey, MnemonicError> {
+    let mnemonic = Mnemonic::parse(phrase).map_err(|_| MnemonicError::InvalidMnemonic)?;
+    let seed = mnemonic.to_seed(passphrase);
+    let indexes = parse_derivation_path(path)?;
+
+    let (mut key, mut chain_code) = hmac_sha512(b"Bitcoin seed", &seed)?;
+    let secp = Secp256k1::new();
+    for index in indexes {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&key[..]);
+        } else {
+            data.extend_from_slice(&PublicKey::from_secret_key(&secp, &key).serialize());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, child_chain_code) = hmac_sha512(&chain_code, &data)?;
+        key.add_assign(&tweak[..]).map_err(|_| MnemonicError::DerivationFailed)?;
+        chain_code = child_chain_code;
+    }
+
+    Ok(key)
+}
+
+/// Parses a derivation path such as `m/44'/60'/0'/0/0` into BIP-32 child indexes.
+pub fn parse_derivation_path(path: &str) -> Result<Vec<u32>, MnemonicError> {
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        return Err(MnemonicError::InvalidDerivationPath);
+    }
+
+    segments
+        .map(|segment| {
+            let (number, hardened) = match segment.strip_suffix('\'').or_else(|| segment.strip_suffix('h')) {
+                Some(number) => (number, true),
+                None => (segment, false),
+            };
+            let index: u32 = number.parse().map_err(|_| MnemonicError::InvalidDerivationPath)?;
+            if index >= HARDENED {
+                return Err(MnemonicError::InvalidDerivationPath);
+            }
+            Ok(if hardened { index + HARDENED } else { index })
+        })
+        .collect()
+}
+
+/// Splits HMAC-SHA512 into a private key (left half) and a chain code (right half).
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<(SecretKey, [u8; 32]), MnemonicError> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(|_| MnemonicError::DerivationFailed)?;
+    mac.update(data);
+    let output = mac.finalize().into_bytes();
+
+    let secret = SecretKey::from_slice(&output[..32]).map_err(|_| MnemonicError::DerivationFailed)?;
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&output[32..]);
+    Ok((secret, chain_code))
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::abi::to_hex;
+
+    #[test]
+    fn test_parse_derivation_path() {
+        assert_eq!(
+            parse_derivation_path(DEFAULT_DERIVATION_PATH).unwrap(),
+            vec![44 + HARDENED, 60 + HARDENED, HARDENED, 0, 0]
+        );
+        assert!(matches!(parse_derivation_path("44'/60'"), Err(MnemonicError::InvalidDerivationPath)));
+        assert!(matches!(parse_derivation_path("m/x"), Err(MnemonicError::InvalidDerivationPath)));
+    }
+
+    #[test]
+    fn test_derive_known_account() {
+        // The default development mnemonic of Hardhat and Anvil.
+        let phrase = "test test test test test test test test test test test junk";
+        let key = derive_private_key(phrase, "", DEFAULT_DERIVATION_PATH).unwrap();
+        assert_eq!(to_hex(&key[..]), "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80");
+    }
+
+    #[test]
+    fn test_invalid_mnemonic() {
+        let result = derive_private_key("not a valid mnemonic", "", DEFAULT_DERIVATION_PATH);
+        assert!(matches!(result, Err(MnemonicError::InvalidMnemonic)));
+    }
+}
@@ -9,4 +9,20 @@ pub mod contract_update;
 pub mod gas;
 pub mod abi;
 pub mod watch;
-pub mod monitor;
\ No newline at end of file
+pub mod monitor;
+pub mod proposal;
+pub mod nonce;
+pub mod pending;
+pub mod storage;
+pub mod multicall;
+pub mod signer;
+pub mod keystore;
+pub mod mnemonic;
+pub mod offline;
+pub mod block;
+pub mod network;
+pub mod fees;
+pub mod gas_strategy;
+pub mod bytecode;
+pub mod profiler;
+pub mod l2;
\ No newline at end of file
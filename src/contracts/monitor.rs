@@ -1,6 +1,10 @@
 use crate::framework::logging::{log_info, log_warn, log_error};
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, U64};
+use web3::{Transport, Web3};
+use std::str::FromStr;
 use std::time::{Instant, Duration};
 use std::thread;
+use std::fmt;
 
 /// Errors that can occur during contract monitoring.
 #[derive(Debug)]
@@ -10,6 +14,18 @@ pub enum MonitorError {
     TransientError, // For retry mechanism
 }
 
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorError::InvalidContractAddress => write!(f, "invalid contract address"),
+            MonitorError::MonitoringFailed => write!(f, "monitoring failed"),
+            MonitorError::TransientError => write!(f, "transient monitoring error"),
+        }
+    }
+}
+
+impl std::error::Error for MonitorError {}
+
 /// Monitors contract activity, logs the status, and periodically checks for events.
 /// Implements retry mechanism in case of transient errors.
 ///
@@ -73,10 +89,52 @@ pub fn monitor_contract_activity(
     Ok(())
 }
 
+/// Performs one monitoring poll: fetches the contract's logs from `from_block` up to the latest block.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `contract_address` - The address of the contract to be monitored.
+/// * `from_block` - The first block not yet checked.
+///
+/// # Returns
+/// Result<(Vec<Log>, U64), MonitorError> - Returns the new logs and the block to start the next
+/// poll from, or `TransientError` if the node could not be queried.
+pub async fn poll_contract_activity<T: Transport>(
+    web3: &Web3<T>,
+    contract_address: &str,
+    from_block: U64,
+) -> Result<(Vec<Log>, U64), MonitorError> {
+    let address = Address::from_str(contract_address).map_err(|_| MonitorError::InvalidContractAddress)?;
+
+    let latest = web3.eth().block_number().await.map_err(|e| {
+        log_warn(&format!("Failed to read the latest block: {}", e));
+        MonitorError::TransientError
+    })?;
+    if latest < from_block {
+        return Ok((Vec::new(), from_block));
+    }
+
+    let filter = FilterBuilder::default()
+        .address(vec![address])
+        .from_block(BlockNumber::Number(from_block))
+        .to_block(BlockNumber::Number(latest))
+        .build();
+    let logs = web3.eth().logs(filter).await.map_err(|e| {
+        log_warn(&format!("Failed to fetch logs of {}: {}", contract_address, e));
+        MonitorError::TransientError
+    })?;
+
+    log_info(&format!("Found {} log(s) for {} in blocks {}..={}", logs.len(), contract_address, from_block, latest));
+    Ok((logs, latest + 1))
+}
+
 // Unit test example
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use serde_json::json;
     use std::time::Duration;
 
     #[test]
@@ -90,4 +148,24 @@ mod tests {
         let result = monitor_contract_activity("0x123", "TestEvent", Duration::from_secs(5), 3);
         assert!(matches!(result, Ok(())));
     }
+
+    #[tokio::test]
+    async fn test_poll_contract_activity_against_mock() {
+        let contract = "0x1234567890abcdef1234567890abcdef12345678";
+        let mock = MockProvider::new();
+        mock.on_request("eth_blockNumber", None, json!("0x20"));
+        mock.on_request("eth_getLogs", Some(json!([{"fromBlock": "0x1e", "toBlock": "0x20"}])), json!([]));
+        mock.on_error("eth_getLogs", None, -32005, "query returned more than 10000 results");
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let (logs, next_block) = poll_contract_activity(&web3, contract, U64::from(0x1e)).await.unwrap();
+        assert!(logs.is_empty());
+        assert_eq!(next_block, U64::from(0x21));
+
+        let (_, next_block) = poll_contract_activity(&web3, contract, U64::from(0x21)).await.unwrap();
+        assert_eq!(next_block, U64::from(0x21));
+
+        let result = poll_contract_activity(&web3, contract, U64::from(0x1)).await;
+        assert!(matches!(result, Err(MonitorError::TransientError)));
+    }
 }
@@ -0,0 +1,248 @@
+use crate::framework::logging::{log_info, log_error};
+use crate::contracts::abi::{encode_call, AbiFunction};
+use crate::contracts::block::BlockTag;
+use crate::contracts::interaction::InteractionError;
+use serde_json::json;
+use web3::ethabi::{decode, ParamType, Token};
+use web3::types::{Address, Bytes, CallRequest};
+use web3::{Transport, Web3};
+use std::str::FromStr;
+
+/// The Multicall3 deployment address, identical on every chain it is deployed to.
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+
+/// A read call queued in a [`Multicall`].
+#[derive(Debug, Clone)]
+struct QueuedCall {
+    target: Address,
+    function: AbiFunction,
+    calldata: Vec<u8>,
+    allow_failure: bool,
+}
+
+/// The outcome of a single call in a batch.
+///
+/// # Fields
+/// - `success`: Whether the call succeeded.
+/// - `return_data`: The raw return data, or the revert data when the call failed.
+/// - `decoded`: The decoded outputs, if the call succeeded and its return data decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MulticallResult {
+    pub success: bool,
+    pub return_data: Vec<u8>,
+    pub decoded: Option<Vec<Token>>,
+}
+
+/// Collects read calls, possibly against different contracts, into a single Multicall3 `aggregate3` call.
+#[derive(Debug, Clone)]
+pub struct Multicall {
+    address: Address,
+    calls: Vec<QueuedCall>,
+}
+
+impl Default for Multicall {
+    fn default() -> Self {
+        Self {
+            address: Address::from_str(MULTICALL3_ADDRESS).expect("valid Multicall3 address"),
+            calls: Vec::new(),
+        }
+    }
+}
+
+impl Multicall {
+    /// Creates an empty batch using the canonical Multicall3 deployment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty batch using a Multicall3 deployed at a custom address.
+    pub fn with_address(address: Address) -> Self {
+        Self { address, calls: Vec::new() }
+    }
+
+    /// Queues a call and returns its index in the batch results.
+    ///
+    /// # Arguments
+    /// * `contract_address` - The address of the contract to call.
+    /// * `function` - The ABI of the function to call.
+    /// * `params` - Parameters to pass to the function.
+    /// * `allow_failure` - Whether the batch may succeed when this call reverts.
+    pub fn add_call(
+        &mut self,
+        contract_address: &str,
+        function: &AbiFunction,
+        params: &[Token],
+        allow_failure: bool,
+    ) -> Result<usize, InteractionError> {
+        let target = Address::from_str(contract_address).map_err(|_| InteractionError::InvalidAddress)?;
+        let calldata = function.encode_input(params).map_err(|e| {
+            log_error(&e);
+            InteractionError::InvalidParameters
+        })?;
+
+        self.calls.push(QueuedCall { target, function: function.clone(), calldata, allow_failure });
+        Ok(self.calls.len() - 1)
+    }
+
+    /// Returns the number of queued calls.
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// Returns `true` if no calls are queued.
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Returns the calldata for `aggregate3((address,bool,bytes)[])`.
+    pub fn calldata(&self) -> Vec<u8> {
+        let calls = self
+            .calls
+            .iter()
+            .map(|call| {
+                Token::Tuple(vec![
+                    Token::Address(call.target),
+                    Token::Bool(call.allow_failure),
+                    Token::Bytes(call.calldata.clone()),
+                ])
+            })
+            .collect();
+        encode_call("aggregate3((address,bool,bytes)[])", &[Token::Array(calls)])
+    }
+
+    /// Decodes the `(bool,bytes)[]` returned by `aggregate3`, decoding each successful call
+    /// with the outputs of its function.
+    pub fn decode(&self, output: &[u8]) -> Result<Vec<MulticallResult>, InteractionError> {
+        let result_type = ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Bool, ParamType::Bytes])));
+        let entries = match decode(&[result_type], output).map_err(|_| InteractionError::DecodingFailed)?.pop() {
+            Some(Token::Array(entries)) if entries.len() == self.calls.len() => entries,
+            _ => return Err(InteractionError::DecodingFailed),
+        };
+
+        entries
+            .into_iter()
+            .zip(&self.calls)
+            .map(|(entry, call)| match entry {
+                Token::Tuple(fields) => match (fields.first(), fields.get(1)) {
+                    (Some(Token::Bool(success)), Some(Token::Bytes(return_data))) => Ok(MulticallResult {
+                        success: *success,
+                        decoded: if *success { call.function.decode_output(return_data).ok() } else { None },
+                        return_data: return_data.clone(),
+                    }),
+                    _ => Err(InteractionError::DecodingFailed),
+                },
+                _ => Err(InteractionError::DecodingFailed),
+            })
+            .collect()
+    }
+
+    /// Executes the batch through `eth_call` and returns one result per queued call, in order.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to the node.
+    /// * `block` - The block the calls are executed against; the latest block when `None`.
+    ///
+    /// # Returns
+    /// Result<Vec<MulticallResult>, InteractionError> - Returns the per-call results, or an error
+    /// if the whole batch failed, e.g. because a call that does not allow failure reverted.
+    pub async fn call<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        block: Option<BlockTag>,
+    ) -> Result<Vec<MulticallResult>, InteractionError> {
+        if self.calls.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        log_info(&format!("Executing {} call(s) through Multicall3.", self.calls.len()));
+
+        let request = CallRequest {
+            to: Some(self.address),
+            data: Some(Bytes(self.calldata())),
+            ..Default::default()
+        };
+        let params = vec![json!(request), json!(block.unwrap_or_default())];
+        let output = web3
+            .transport()
+            .execute("eth_call", params)
+            .await
+            .and_then(|value| serde_json::from_value::<Bytes>(value).map_err(|e| web3::Error::Decoder(e.to_string())))
+            .map_err(|e| {
+                log_error(&format!("Multicall failed: {}", e));
+                InteractionError::FunctionCallFailed
+            })?;
+
+        self.decode(&output.0)
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use web3::ethabi::encode;
+    use web3::types::U256;
+
+    fn total_supply() -> AbiFunction {
+        AbiFunction {
+            name: "totalSupply".to_string(),
+            inputs: vec![],
+            outputs: vec!["uint256".to_string()],
+            payable: false,
+            constant: true,
+        }
+    }
+
+    #[test]
+    fn test_aggregate3_calldata() {
+        let mut multicall = Multicall::new();
+        multicall.add_call("0x1234567890abcdef1234567890abcdef12345678", &total_supply(), &[], true).unwrap();
+        assert_eq!(multicall.len(), 1);
+        assert_eq!(multicall.calldata()[..4], [0x82, 0xad, 0x56, 0xcb]);
+    }
+
+    #[test]
+    fn test_invalid_call() {
+        let mut multicall = Multicall::new();
+        let result = multicall.add_call("invalid", &total_supply(), &[], true);
+        assert!(matches!(result, Err(InteractionError::InvalidAddress)));
+        assert!(multicall.is_empty());
+    }
+
+    #[test]
+    fn test_decode_results() {
+        let mut multicall = Multicall::new();
+        multicall.add_call("0x1234567890abcdef1234567890abcdef12345678", &total_supply(), &[], true).unwrap();
+        multicall.add_call("0xabcdefabcdefabcdefabcdefabcdefabcdefabcd", &total_supply(), &[], true).unwrap();
+
+        let output = encode(&[Token::Array(vec![
+            Token::Tuple(vec![Token::Bool(true), Token::Bytes(encode(&[Token::Uint(U256::from(1000))]))]),
+            Token::Tuple(vec![Token::Bool(false), Token::Bytes(vec![0x08, 0xc3, 0x79, 0xa0])]),
+        ])]);
+
+        let results = multicall.decode(&output).unwrap();
+        assert_eq!(results[0].decoded, Some(vec![Token::Uint(U256::from(1000))]));
+        assert!(!results[1].success);
+        assert_eq!(results[1].decoded, None);
+        assert_eq!(results[1].return_data, vec![0x08, 0xc3, 0x79, 0xa0]);
+    }
+
+    #[tokio::test]
+    async fn test_call_at_block_tag() {
+        let output = encode(&[Token::Array(vec![Token::Tuple(vec![
+            Token::Bool(true),
+            Token::Bytes(encode(&[Token::Uint(U256::from(1000))])),
+        ])])]);
+        let mock = MockProvider::new();
+        mock.on_request("eth_call", Some(json!([{}, "safe"])), json!(Bytes(output)));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let mut multicall = Multicall::new();
+        multicall.add_call("0x1234567890abcdef1234567890abcdef12345678", &total_supply(), &[], true).unwrap();
+        let results = multicall.call(&web3, Some(BlockTag::Safe)).await.unwrap();
+        assert_eq!(results[0].decoded, Some(vec![Token::Uint(U256::from(1000))]));
+        assert!(multicall.call(&web3, Some(BlockTag::Finalized)).await.is_err());
+    }
+}
@@ -0,0 +1,350 @@
+use crate::framework::logging::{log_info, log_error};
+use crate::contracts::gas::{Hardfork, TransactionType};
+use crate::contracts::signer::UnsignedTransaction;
+use web3::{Transport, Web3};
+use std::time::Duration;
+use std::fmt;
+
+/// Errors that can occur while checking a node against a network profile.
+#[derive(Debug)]
+pub enum NetworkError {
+    ChainIdQueryFailed,
+    ChainIdMismatch { expected: u64, actual: u64 },
+    UnsupportedTransactionType,
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkError::ChainIdQueryFailed => write!(f, "chain id query failed"),
+            NetworkError::ChainIdMismatch { expected, actual } => write!(f, "expected chain {} but the node serves chain {}", expected, actual),
+            NetworkError::UnsupportedTransactionType => write!(f, "unsupported transaction type"),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// The native currency gas is paid in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NativeCurrency {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+impl NativeCurrency {
+    pub fn new(name: &str, symbol: &str, decimals: u8) -> Self {
+        Self { name: name.to_string(), symbol: symbol.to_string(), decimals }
+    }
+
+    fn ether() -> Self {
+        Self::new("Ether", "ETH", 18)
+    }
+}
+
+/// Protocol features a chain supports.
+///
+/// # Fields
+/// - `eip1559`: Blocks carry a base fee and accept `maxFeePerGas` transactions.
+/// - `push0`: The `PUSH0` opcode (Shanghai) is available, so code compiled for it deploys.
+/// - `blob_transactions`: EIP-4844 blob transactions are accepted.
+/// - `rollup`: The rollup stack of an L2, whose fees include the cost of posting data to L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkFeatures {
+    pub eip1559: bool,
+    pub push0: bool,
+    pub blob_transactions: bool,
+    pub rollup: Option<Rollup>,
+}
+
+impl Default for NetworkFeatures {
+    fn default() -> Self {
+        Self { eip1559: true, push0: true, blob_transactions: false, rollup: None }
+    }
+}
+
+/// The rollup stack of an L2, which decides how its L1 data fee is charged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rollup {
+    /// OP Mainnet, Base and other OP-stack chains, priced by the `GasPriceOracle` predeploy.
+    OpStack,
+    /// Arbitrum chains, priced by the `NodeInterface` precompile.
+    Arbitrum,
+}
+
+/// A chain profile: what the crate expects of the node it talks to.
+///
+/// Signing paths that are given a profile check the node's `eth_chainId` against
+/// `chain_id` first and refuse to sign on a mismatch, so a misconfigured endpoint cannot
+/// produce a transaction that is valid on another chain.
+///
+/// # Fields
+/// - `chain_id`: The EIP-155 chain id.
+/// - `name`: A short name, e.g. `mainnet` or `arbitrum-one`.
+/// - `native_currency`: The currency gas is paid in.
+/// - `block_time`: The average time between blocks.
+/// - `features`: The protocol features of the chain.
+/// - `hardfork`: The fork whose intrinsic gas rules the chain follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub chain_id: u64,
+    pub name: String,
+    pub native_currency: NativeCurrency,
+    pub block_time: Duration,
+    pub features: NetworkFeatures,
+    pub hardfork: Hardfork,
+}
+
+impl Network {
+    /// Creates a custom profile paying gas in Ether with 12-second blocks, the default features
+    /// and the latest hard fork.
+    pub fn new(chain_id: u64, name: &str) -> Self {
+        Self {
+            chain_id,
+            name: name.to_string(),
+            native_currency: NativeCurrency::ether(),
+            block_time: Duration::from_secs(12),
+            features: NetworkFeatures::default(),
+            hardfork: Hardfork::default(),
+        }
+    }
+
+    pub fn native_currency(mut self, native_currency: NativeCurrency) -> Self {
+        self.native_currency = native_currency;
+        self
+    }
+
+    pub fn block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    pub fn features(mut self, features: NetworkFeatures) -> Self {
+        self.features = features;
+        self
+    }
+
+    pub fn hardfork(mut self, hardfork: Hardfork) -> Self {
+        self.hardfork = hardfork;
+        self
+    }
+
+    /// Ethereum mainnet.
+    pub fn mainnet() -> Self {
+        Self::new(1, "mainnet").features(NetworkFeatures { blob_transactions: true, ..Default::default() })
+    }
+
+    /// The Sepolia testnet.
+    pub fn sepolia() -> Self {
+        Self::new(11_155_111, "sepolia").features(NetworkFeatures { blob_transactions: true, ..Default::default() })
+    }
+
+    /// The Holesky testnet.
+    pub fn holesky() -> Self {
+        Self::new(17_000, "holesky").features(NetworkFeatures { blob_transactions: true, ..Default::default() })
+    }
+
+    /// OP Mainnet.
+    pub fn optimism() -> Self {
+        Self::new(10, "optimism")
+            .block_time(Duration::from_secs(2))
+            .features(NetworkFeatures { rollup: Some(Rollup::OpStack), ..Default::default() })
+    }
+
+    /// Base.
+    pub fn base() -> Self {
+        Self::new(8_453, "base")
+            .block_time(Duration::from_secs(2))
+            .features(NetworkFeatures { rollup: Some(Rollup::OpStack), ..Default::default() })
+    }
+
+    /// Arbitrum One.
+    pub fn arbitrum_one() -> Self {
+        Self::new(42_161, "arbitrum-one")
+            .block_time(Duration::from_millis(250))
+            .features(NetworkFeatures { rollup: Some(Rollup::Arbitrum), ..Default::default() })
+    }
+
+    /// Polygon PoS.
+    pub fn polygon() -> Self {
+        Self::new(137, "polygon")
+            .native_currency(NativeCurrency::new("POL", "POL", 18))
+            .block_time(Duration::from_secs(2))
+    }
+
+    /// BNB Smart Chain.
+    pub fn bsc() -> Self {
+        Self::new(56, "bsc")
+            .native_currency(NativeCurrency::new("BNB", "BNB", 18))
+            .block_time(Duration::from_secs(3))
+            .hardfork(Hardfork::Shanghai)
+    }
+
+    /// Avalanche C-Chain.
+    pub fn avalanche() -> Self {
+        Self::new(43_114, "avalanche")
+            .native_currency(NativeCurrency::new("Avalanche", "AVAX", 18))
+            .block_time(Duration::from_secs(2))
+            .hardfork(Hardfork::Shanghai)
+    }
+
+    /// Gnosis Chain.
+    pub fn gnosis() -> Self {
+        Self::new(100, "gnosis")
+            .native_currency(NativeCurrency::new("xDAI", "xDAI", 18))
+            .block_time(Duration::from_secs(5))
+            .features(NetworkFeatures { blob_transactions: true, ..Default::default() })
+    }
+
+    /// A local Anvil or Hardhat node.
+    pub fn anvil() -> Self {
+        Self::new(31_337, "anvil")
+            .block_time(Duration::from_secs(1))
+            .features(NetworkFeatures { blob_transactions: true, ..Default::default() })
+    }
+
+    /// Returns every built-in profile.
+    pub fn builtin() -> Vec<Network> {
+        vec![
+            Self::mainnet(),
+            Self::sepolia(),
+            Self::holesky(),
+            Self::optimism(),
+            Self::base(),
+            Self::arbitrum_one(),
+            Self::polygon(),
+            Self::bsc(),
+            Self::avalanche(),
+            Self::gnosis(),
+            Self::anvil(),
+        ]
+    }
+
+    /// Returns the built-in profile with a chain id, if any.
+    pub fn from_chain_id(chain_id: u64) -> Option<Network> {
+        Self::builtin().into_iter().find(|network| network.chain_id == chain_id)
+    }
+
+    /// Returns the built-in profile with a name, ignoring case, if any.
+    pub fn from_name(name: &str) -> Option<Network> {
+        Self::builtin().into_iter().find(|network| network.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the transaction type to use when none is requested.
+    pub fn default_transaction_type(&self) -> TransactionType {
+        if self.features.eip1559 {
+            TransactionType::Eip1559
+        } else {
+            TransactionType::Legacy
+        }
+    }
+
+    /// Checks that the node serves this chain through `eth_chainId`.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to the node.
+    ///
+    /// # Returns
+    /// Result<u64, NetworkError> - Returns the chain id, otherwise returns an error if the node
+    /// serves another chain or cannot be queried.
+    pub async fn verify_chain<T: Transport>(&self, web3: &Web3<T>) -> Result<u64, NetworkError> {
+        let actual = web3.eth().chain_id().await.map_err(|e| {
+            log_error(&format!("Failed to read chain id: {}", e));
+            NetworkError::ChainIdQueryFailed
+        })?;
+        let actual = actual.low_u64();
+        if actual != self.chain_id {
+            log_error(&format!(
+                "Node serves chain {} but the {} profile expects chain {}; refusing to sign.",
+                actual, self.name, self.chain_id
+            ));
+            return Err(NetworkError::ChainIdMismatch { expected: self.chain_id, actual });
+        }
+        log_info(&format!("Node serves {} (chain {}).", self.name, actual));
+        Ok(actual)
+    }
+
+    /// Checks that a transaction can be signed for this chain: its EIP-155 chain id matches
+    /// and its type is supported.
+    ///
+    /// # Returns
+    /// Result<(), NetworkError> - Returns Ok if the transaction fits the chain, otherwise returns an error.
+    pub fn check_transaction(&self, transaction: &UnsignedTransaction) -> Result<(), NetworkError> {
+        if transaction.chain_id != self.chain_id {
+            log_error(&format!(
+                "Transaction is for chain {} but the {} profile expects chain {}.",
+                transaction.chain_id, self.name, self.chain_id
+            ));
+            return Err(NetworkError::ChainIdMismatch { expected: self.chain_id, actual: transaction.chain_id });
+        }
+        if transaction.transaction_type == TransactionType::Eip1559 && !self.features.eip1559 {
+            log_error(&format!("{} does not support EIP-1559 transactions.", self.name));
+            return Err(NetworkError::UnsupportedTransactionType);
+        }
+        Ok(())
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use serde_json::json;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_builtin_profiles() {
+        let chain_ids: HashSet<u64> = Network::builtin().iter().map(|network| network.chain_id).collect();
+        assert_eq!(chain_ids.len(), Network::builtin().len());
+
+        assert_eq!(Network::from_chain_id(137).unwrap().native_currency.symbol, "POL");
+        assert_eq!(Network::from_name("Arbitrum-One").unwrap().chain_id, 42_161);
+        assert!(Network::mainnet().features.blob_transactions);
+        assert_eq!(Network::mainnet().hardfork, Hardfork::Prague);
+        assert_eq!(Network::avalanche().hardfork, Hardfork::Shanghai);
+        assert!(Network::from_chain_id(999_999).is_none());
+    }
+
+    #[test]
+    fn test_custom_profile() {
+        let network = Network::new(424_242, "devnet")
+            .native_currency(NativeCurrency::new("Test Ether", "tETH", 18))
+            .features(NetworkFeatures { eip1559: false, push0: false, blob_transactions: false, rollup: None });
+        assert_eq!(network.default_transaction_type(), TransactionType::Legacy);
+
+        let transaction = UnsignedTransaction {
+            transaction_type: TransactionType::Eip1559,
+            chain_id: 424_242,
+            nonce: Default::default(),
+            to: None,
+            value: Default::default(),
+            data: vec![],
+            gas: Default::default(),
+            gas_price: Default::default(),
+            max_fee_per_gas: Default::default(),
+            max_priority_fee_per_gas: Default::default(),
+            access_list: vec![],
+        };
+        assert!(matches!(network.check_transaction(&transaction), Err(NetworkError::UnsupportedTransactionType)));
+        assert!(matches!(
+            Network::mainnet().check_transaction(&transaction),
+            Err(NetworkError::ChainIdMismatch { expected: 1, actual: 424_242 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_chain() {
+        let mock = MockProvider::new();
+        mock.on_request("eth_chainId", None, json!("0xaa36a7"));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        assert_eq!(Network::sepolia().verify_chain(&web3).await.unwrap(), 11_155_111);
+        assert!(matches!(
+            Network::mainnet().verify_chain(&web3).await,
+            Err(NetworkError::ChainIdMismatch { expected: 1, actual: 11_155_111 })
+        ));
+    }
+}
@@ -0,0 +1,419 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::error::Error;
+use crate::contracts::network::{Network, NetworkError};
+use crate::contracts::pending::PendingTransaction;
+use web3::types::{Address, BlockNumber, TransactionId, TransactionRequest, H256, U256};
+use web3::{Transport, Web3};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::fmt;
+
+/// Errors that can occur while managing nonces.
+#[derive(Debug)]
+pub enum NonceError {
+    NonceQueryFailed,
+    WrongNetwork,
+    /// The node rejected the transaction.
+    Rejected(Box<Error>),
+}
+
+impl fmt::Display for NonceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceError::NonceQueryFailed => write!(f, "failed to read the nonce"),
+            NonceError::WrongNetwork => write!(f, "the node serves another chain"),
+            NonceError::Rejected(error) => write!(f, "transaction rejected: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for NonceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            NonceError::Rejected(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Nonce bookkeeping for a single sending account.
+#[derive(Debug, Default)]
+struct AccountNonces {
+    /// The next nonce to hand out.
+    next: U256,
+    /// Nonces handed out whose transactions have not been sent or released yet.
+    reserved: BTreeSet<U256>,
+    /// Hashes of transactions sent through the manager, by nonce.
+    sent: BTreeMap<U256, H256>,
+}
+
+/// Hands out nonces for sending accounts so that concurrent tasks never reuse one.
+///
+/// A single manager should be shared (e.g. behind an `Arc`) by every task that sends
+/// from the same account through `deploy`, `interaction` or `contract_update`.
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    accounts: Mutex<HashMap<Address, AccountNonces>>,
+    network: Option<Network>,
+}
+
+impl NonceManager {
+    /// Creates an empty manager. Nonces are loaded from the node on first use.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a manager that checks the node's chain id against a network profile before
+    /// every send, and refuses to let the node sign on a mismatch.
+    pub fn with_network(network: Network) -> Self {
+        Self { network: Some(network), ..Self::default() }
+    }
+
+    /// Overrides the next nonce handed out for an account.
+    pub fn set_nonce(&self, address: Address, nonce: U256) {
+        let mut accounts = self.accounts.lock().unwrap();
+        accounts.entry(address).or_default().next = nonce;
+    }
+
+    /// Reserves the next nonce for an account, loading it from `eth_getTransactionCount(pending)`
+    /// the first time the account is seen.
+    ///
+    /// The nonce stays reserved until it is passed to [`NonceManager::record_sent`] or
+    /// [`NonceManager::release`].
+    ///
+    /// # Returns
+    /// Result<U256, NonceError> - Returns the reserved nonce, otherwise returns an error.
+    pub async fn next_nonce<T: Transport>(&self, web3: &Web3<T>, address: Address) -> Result<U256, NonceError> {
+        if !self.accounts.lock().unwrap().contains_key(&address) {
+            let pending = pending_nonce(web3, address).await?;
+            // Another task may have loaded the account meanwhile; keep its counter if so.
+            self.accounts
+                .lock()
+                .unwrap()
+                .entry(address)
+                .or_insert_with(|| AccountNonces { next: pending, ..AccountNonces::default() });
+        }
+
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.get_mut(&address).expect("account loaded above");
+        let nonce = account.next;
+        account.next = nonce + U256::one();
+        account.reserved.insert(nonce);
+        Ok(nonce)
+    }
+
+    /// Records the hash of a transaction sent with a nonce from this manager.
+    pub fn record_sent(&self, address: Address, nonce: U256, hash: H256) {
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(address).or_default();
+        account.reserved.remove(&nonce);
+        account.sent.insert(nonce, hash);
+    }
+
+    /// Gives back a reserved nonce whose transaction will not be sent.
+    ///
+    /// The nonce is not handed out again directly; [`NonceManager::resync`] reclaims it once no
+    /// other reservation is outstanding, and [`NonceManager::find_gaps`] reports it until then.
+    pub fn release(&self, address: Address, nonce: U256) {
+        let mut accounts = self.accounts.lock().unwrap();
+        if let Some(account) = accounts.get_mut(&address) {
+            account.reserved.remove(&nonce);
+        }
+    }
+
+    /// Reloads the next nonce of an account from `eth_getTransactionCount(pending)`.
+    ///
+    /// Called after a send fails, since the node's view is authoritative once a
+    /// transaction has been rejected. The counter is left alone while other nonces are
+    /// reserved, and never drops below a nonce already sent through the manager.
+    pub async fn resync<T: Transport>(&self, web3: &Web3<T>, address: Address) -> Result<U256, NonceError> {
+        let pending = pending_nonce(web3, address).await?;
+        let mut accounts = self.accounts.lock().unwrap();
+        let account = accounts.entry(address).or_default();
+        if !account.reserved.is_empty() {
+            log_info(&format!(
+                "Kept nonce for {:?} at {} while {} nonce(s) are reserved",
+                address,
+                account.next,
+                account.reserved.len()
+            ));
+            return Ok(account.next);
+        }
+        account.next = match account.sent.keys().next_back() {
+            Some(last) => pending.max(*last + U256::one()),
+            None => pending,
+        };
+        log_info(&format!("Resynced nonce for {:?} to {}", address, account.next));
+        Ok(account.next)
+    }
+
+    /// Reserves a specific nonce, returning `false` if another task already holds it.
+    fn claim(&self, address: Address, nonce: U256) -> bool {
+        let mut accounts = self.accounts.lock().unwrap();
+        accounts.entry(address).or_default().reserved.insert(nonce)
+    }
+
+    /// Finds nonces that were handed out but whose transactions the node no longer knows,
+    /// e.g. because they were dropped from the mempool or never broadcast.
+    ///
+    /// Nonces that are still reserved belong to a send in progress and are not gaps.
+    ///
+    /// # Returns
+    /// Result<Vec<U256>, NonceError> - Returns the missing nonces in ascending order.
+    pub async fn find_gaps<T: Transport>(&self, web3: &Web3<T>, address: Address) -> Result<Vec<U256>, NonceError> {
+        let pending = pending_nonce(web3, address).await?;
+        let candidates = {
+            let accounts = self.accounts.lock().unwrap();
+            match accounts.get(&address) {
+                Some(account) => unconfirmed_nonces(pending, account.next, &account.sent)
+                    .into_iter()
+                    .filter(|(nonce, _)| !account.reserved.contains(nonce))
+                    .collect::<Vec<_>>(),
+                None => return Ok(Vec::new()),
+            }
+        };
+
+        let mut gaps = Vec::new();
+        for (nonce, hash) in candidates {
+            let known = match hash {
+                Some(hash) => web3
+                    .eth()
+                    .transaction(TransactionId::Hash(hash))
+                    .await
+                    .map_err(|_| NonceError::NonceQueryFailed)?
+                    .is_some(),
+                None => false,
+            };
+            if !known {
+                gaps.push(nonce);
+            }
+        }
+
+        if !gaps.is_empty() {
+            log_warn(&format!("Detected {} nonce gap(s) for {:?}: {:?}", gaps.len(), address, gaps));
+        }
+        Ok(gaps)
+    }
+
+    /// Fills nonce gaps with zero-value self-transfers so that queued transactions can be mined.
+    ///
+    /// The fillers are signed by the node, so the node's chain id is checked first when the
+    /// manager has a network profile.
+    ///
+    /// # Returns
+    /// Result<Vec<H256>, NonceError> - Returns the hashes of the filler transactions.
+    pub async fn fill_gaps<T: Transport>(&self, web3: &Web3<T>, address: Address) -> Result<Vec<H256>, NonceError> {
+        self.verify_network(web3).await?;
+        let mut hashes = Vec::new();
+        for nonce in self.find_gaps(web3, address).await? {
+            if !self.claim(address, nonce) {
+                continue;
+            }
+            let filler = TransactionRequest {
+                from: address,
+                to: Some(address),
+                value: Some(U256::zero()),
+                nonce: Some(nonce),
+                ..Default::default()
+            };
+            let hash = web3.eth().send_transaction(filler).await.map_err(|e| {
+                self.release(address, nonce);
+                log_error(&format!("Failed to fill nonce {} for {:?}: {}", nonce, address, e));
+                NonceError::Rejected(Box::new(Error::from(e)))
+            })?;
+            self.record_sent(address, nonce, hash);
+            hashes.push(hash);
+        }
+        Ok(hashes)
+    }
+
+    /// Sends a transaction through `eth_sendTransaction` with the next nonce of its sender.
+    ///
+    /// On failure the nonce is released and the sender's counter is resynced from the node
+    /// before the error is returned.
+    ///
+    /// # Returns
+    /// Result<H256, NonceError> - Returns the transaction hash, otherwise returns an error.
+    pub async fn send_transaction<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        transaction: TransactionRequest,
+    ) -> Result<H256, NonceError> {
+        let (_, hash) = self.send_with_nonce(web3, transaction).await?;
+        Ok(hash)
+    }
+
+    /// Sends a transaction like [`NonceManager::send_transaction`] and returns a handle that
+    /// tracks it until it is confirmed, and can speed it up or cancel it.
+    ///
+    /// # Returns
+    /// Result<PendingTransaction, NonceError> - Returns the pending transaction, otherwise returns an error.
+    pub async fn send_pending_transaction<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        transaction: TransactionRequest,
+    ) -> Result<PendingTransaction, NonceError> {
+        let (transaction, hash) = self.send_with_nonce(web3, transaction).await?;
+        let pending = PendingTransaction::new(hash, transaction);
+        Ok(match &self.network {
+            Some(network) => pending.network(network.clone()),
+            None => pending,
+        })
+    }
+
+    /// Checks the node's chain id against the manager's network profile, if it has one.
+    async fn verify_network<T: Transport>(&self, web3: &Web3<T>) -> Result<(), NonceError> {
+        if let Some(network) = &self.network {
+            network.verify_chain(web3).await.map_err(|e| match e {
+                NetworkError::ChainIdQueryFailed => NonceError::NonceQueryFailed,
+                _ => NonceError::WrongNetwork,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Assigns the next nonce, sends the transaction and returns the request as sent.
+    async fn send_with_nonce<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        mut transaction: TransactionRequest,
+    ) -> Result<(TransactionRequest, H256), NonceError> {
+        self.verify_network(web3).await?;
+        let nonce = self.next_nonce(web3, transaction.from).await?;
+        transaction.nonce = Some(nonce);
+
+        match web3.eth().send_transaction(transaction.clone()).await {
+            Ok(hash) => {
+                self.record_sent(transaction.from, nonce, hash);
+                log_info(&format!("Sent transaction {:?} with nonce {}", hash, nonce));
+                Ok((transaction, hash))
+            }
+            Err(e) => {
+                log_error(&format!("Transaction with nonce {} failed: {}", nonce, e));
+                self.release(transaction.from, nonce);
+                let _ = self.resync(web3, transaction.from).await;
+                Err(NonceError::Rejected(Box::new(Error::from(e))))
+            }
+        }
+    }
+}
+
+/// Reads the pending transaction count of an account.
+async fn pending_nonce<T: Transport>(web3: &Web3<T>, address: Address) -> Result<U256, NonceError> {
+    web3.eth()
+        .transaction_count(address, Some(BlockNumber::Pending))
+        .await
+        .map_err(|e| {
+            log_error(&format!("Failed to read nonce for {:?}: {}", address, e));
+            NonceError::NonceQueryFailed
+        })
+}
+
+/// Returns the nonces in `pending..next` with the hash sent for each, if any.
+///
+/// The node's pending count stops at the first missing nonce, so everything from there up to
+/// the next nonce handed out is either queued behind a gap or missing itself.
+fn unconfirmed_nonces(pending: U256, next: U256, sent: &BTreeMap<U256, H256>) -> Vec<(U256, Option<H256>)> {
+    let mut nonces = Vec::new();
+    let mut nonce = pending;
+    while nonce < next {
+        nonces.push((nonce, sent.get(&nonce).copied()));
+        nonce += U256::one();
+    }
+    nonces
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_concurrent_nonces_are_unique() {
+        let web3 = Web3::new(web3::transports::Http::new("http://localhost:8545").unwrap());
+        let manager = Arc::new(NonceManager::new());
+        let address = Address::repeat_byte(1);
+        manager.set_nonce(address, U256::from(5));
+
+        let mut handles = Vec::new();
+        for _ in 0..20 {
+            let manager = manager.clone();
+            let web3 = web3.clone();
+            handles.push(tokio::spawn(async move { manager.next_nonce(&web3, address).await.unwrap() }));
+        }
+
+        let mut nonces = HashSet::new();
+        for handle in handles {
+            nonces.insert(handle.await.unwrap());
+        }
+        assert_eq!(nonces.len(), 20);
+        assert!(nonces.contains(&U256::from(5)));
+        assert!(nonces.contains(&U256::from(24)));
+    }
+
+    #[tokio::test]
+    async fn test_send_refused_on_wrong_chain() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x89"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let manager = NonceManager::with_network(Network::mainnet());
+        let transaction = TransactionRequest { from: Address::repeat_byte(1), ..Default::default() };
+        let result = manager.send_transaction(&web3, transaction).await;
+        assert!(matches!(result, Err(NonceError::WrongNetwork)));
+        assert!(manager.accounts.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_fill_gaps_refused_on_wrong_chain() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x89"));
+        mock.on_request("eth_getTransactionCount", None, serde_json::json!("0x5"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let manager = NonceManager::with_network(Network::mainnet());
+        let address = Address::repeat_byte(1);
+        manager.set_nonce(address, U256::from(7));
+        let result = manager.fill_gaps(&web3, address).await;
+        assert!(matches!(result, Err(NonceError::WrongNetwork)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_send_keeps_reserved_nonces() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_getTransactionCount", None, serde_json::json!("0x5"));
+        mock.on_error("eth_sendTransaction", None, -32000, "insufficient funds");
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+        let manager = NonceManager::new();
+        let address = Address::repeat_byte(1);
+
+        // Another task holds nonce 5 while this send fails with nonce 6.
+        let held = manager.next_nonce(&web3, address).await.unwrap();
+        let transaction = TransactionRequest { from: address, ..Default::default() };
+        assert!(manager.send_transaction(&web3, transaction).await.is_err());
+        assert_eq!(held, U256::from(5));
+
+        let next = manager.next_nonce(&web3, address).await.unwrap();
+        assert_eq!(next, U256::from(7));
+        assert_eq!(manager.find_gaps(&web3, address).await.unwrap(), vec![U256::from(6)]);
+
+        manager.release(address, held);
+        manager.release(address, next);
+        assert_eq!(manager.resync(&web3, address).await.unwrap(), U256::from(5));
+    }
+
+    #[test]
+    fn test_unconfirmed_nonces() {
+        let mut sent = BTreeMap::new();
+        sent.insert(U256::from(3), H256::repeat_byte(3));
+
+        let nonces = unconfirmed_nonces(U256::from(3), U256::from(5), &sent);
+        assert_eq!(
+            nonces,
+            vec![(U256::from(3), Some(H256::repeat_byte(3))), (U256::from(4), None)]
+        );
+        assert!(unconfirmed_nonces(U256::from(5), U256::from(5), &sent).is_empty());
+    }
+}
@@ -0,0 +1,535 @@
+use crate::framework::logging::{log_info, log_error};
+use crate::contracts::abi::to_hex;
+use crate::contracts::gas::{
+    build_typed_transaction, check_gas_limit, estimate_gas_limit, GasOptimizationError, Hardfork, TransactionType,
+    TypedTransactionOptions,
+};
+use crate::contracts::network::{Network, NetworkError};
+use crate::contracts::nonce::{NonceError, NonceManager};
+use crate::contracts::signer::{SignedTransaction, Signer, UnsignedTransaction};
+use serde::{Deserialize, Serialize};
+use web3::types::{AccessList, Address, Bytes, TransactionRequest, H256, U256};
+use web3::{Transport, Web3};
+use std::fmt;
+
+/// Errors that can occur while preparing, signing or broadcasting an offline transaction.
+#[derive(Debug)]
+pub enum OfflineError {
+    PreparationFailed,
+    InvalidTransaction,
+    SignerMismatch,
+    WrongNetwork,
+    SigningFailed,
+    BroadcastFailed,
+    /// Filling in the transaction type, fees or gas limit failed, e.g. because the estimate reverted.
+    Gas(GasOptimizationError),
+    /// Reserving a nonce failed.
+    Nonce(NonceError),
+}
+
+impl fmt::Display for OfflineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OfflineError::PreparationFailed => write!(f, "transaction preparation failed"),
+            OfflineError::InvalidTransaction => write!(f, "invalid transaction"),
+            OfflineError::SignerMismatch => write!(f, "the signer is not the sender of the transaction"),
+            OfflineError::WrongNetwork => write!(f, "the node or transaction is for another network"),
+            OfflineError::SigningFailed => write!(f, "signing failed"),
+            OfflineError::BroadcastFailed => write!(f, "broadcast failed"),
+            OfflineError::Gas(error) => write!(f, "gas: {}", error),
+            OfflineError::Nonce(error) => write!(f, "nonce: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for OfflineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            OfflineError::Gas(error) => Some(error),
+            OfflineError::Nonce(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// A transaction prepared on a networked machine for signing on an air-gapped one.
+///
+/// Every field the signature covers is filled in (chain id, nonce, gas and fees), so signing
+/// needs no node access.
+///
+/// # Fields
+/// - `from`: The account expected to sign.
+/// - `description`: A human-readable summary for the person approving the signature.
+/// - `transaction`: The transaction to sign.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OfflineTransaction {
+    pub from: Address,
+    pub description: String,
+    pub transaction: UnsignedTransaction,
+}
+
+/// The JSON form of an [`OfflineTransaction`].
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OfflineTransactionJson {
+    description: String,
+    #[serde(rename = "type")]
+    transaction_type: String,
+    chain_id: u64,
+    from: Address,
+    nonce: U256,
+    to: Option<Address>,
+    value: U256,
+    data: Bytes,
+    gas: U256,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gas_price: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_fee_per_gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_priority_fee_per_gas: Option<U256>,
+    #[serde(default)]
+    access_list: AccessList,
+    unsigned_rlp: String,
+    signing_hash: H256,
+}
+
+impl OfflineTransaction {
+    /// Returns the JSON description: every field in readable form, plus the unsigned RLP
+    /// payload and the hash that will be signed.
+    pub fn to_json(&self) -> String {
+        let tx = &self.transaction;
+        let (gas_price, max_fee_per_gas, max_priority_fee_per_gas) = match tx.transaction_type {
+            TransactionType::Eip1559 => (None, Some(tx.max_fee_per_gas), Some(tx.max_priority_fee_per_gas)),
+            _ => (Some(tx.gas_price), None, None),
+        };
+        let json = OfflineTransactionJson {
+            description: self.description.clone(),
+            transaction_type: type_name(tx.transaction_type).to_string(),
+            chain_id: tx.chain_id,
+            from: self.from,
+            nonce: tx.nonce,
+            to: tx.to,
+            value: tx.value,
+            data: Bytes(tx.data.clone()),
+            gas: tx.gas,
+            gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            access_list: tx.access_list.clone(),
+            unsigned_rlp: format!("0x{}", to_hex(&tx.encode_unsigned())),
+            signing_hash: tx.signing_hash(),
+        };
+        serde_json::to_string_pretty(&json).expect("offline transaction serializes")
+    }
+
+    /// Parses the JSON produced by [`OfflineTransaction::to_json`].
+    ///
+    /// The transaction is rebuilt from the readable fields and rejected if its RLP payload does
+    /// not match `unsignedRlp`, so that what is signed is exactly what was reviewed.
+    ///
+    /// # Returns
+    /// Result<OfflineTransaction, OfflineError> - Returns the transaction, otherwise returns an error.
+    pub fn from_json(json: &str) -> Result<Self, OfflineError> {
+        let parsed: OfflineTransactionJson = serde_json::from_str(json).map_err(|e| {
+            log_error(&format!("Invalid offline transaction: {}", e));
+            OfflineError::InvalidTransaction
+        })?;
+
+        let transaction_type = match parsed.transaction_type.as_str() {
+            "legacy" => TransactionType::Legacy,
+            "accessList" => TransactionType::AccessList,
+            "eip1559" => TransactionType::Eip1559,
+            _ => return Err(OfflineError::InvalidTransaction),
+        };
+        let transaction = UnsignedTransaction {
+            transaction_type,
+            chain_id: parsed.chain_id,
+            nonce: parsed.nonce,
+            to: parsed.to,
+            value: parsed.value,
+            data: parsed.data.0,
+            gas: parsed.gas,
+            gas_price: parsed.gas_price.unwrap_or_default(),
+            max_fee_per_gas: parsed.max_fee_per_gas.unwrap_or_default(),
+            max_priority_fee_per_gas: parsed.max_priority_fee_per_gas.unwrap_or_default(),
+            access_list: parsed.access_list,
+        };
+
+        let unsigned_rlp = format!("0x{}", to_hex(&transaction.encode_unsigned()));
+        if unsigned_rlp != parsed.unsigned_rlp.to_lowercase() || transaction.signing_hash() != parsed.signing_hash {
+            log_error("Offline transaction fields do not match its unsigned RLP payload.");
+            return Err(OfflineError::InvalidTransaction);
+        }
+
+        Ok(Self { from: parsed.from, description: parsed.description, transaction })
+    }
+
+    /// Signs the transaction. Needs no node access.
+    ///
+    /// # Arguments
+    /// * `signer` - The signer holding the key of `from`.
+    ///
+    /// # Returns
+    /// Result<SignedTransaction, OfflineError> - Returns the raw signed transaction, otherwise
+    /// returns an error if the signer is not the expected account.
+    pub fn sign(&self, signer: &dyn Signer) -> Result<SignedTransaction, OfflineError> {
+        if signer.address() != self.from {
+            log_error(&format!("Signer {:?} is not the expected sender {:?}", signer.address(), self.from));
+            return Err(OfflineError::SignerMismatch);
+        }
+        signer.sign_transaction(&self.transaction).map_err(|e| {
+            log_error(&format!("Failed to sign offline transaction: {:?}", e));
+            OfflineError::SigningFailed
+        })
+    }
+
+    /// Signs the transaction like [`OfflineTransaction::sign`], first checking it against the
+    /// network profile the air-gapped machine expects. The node's chain id was already checked
+    /// when the transaction was prepared with the same profile.
+    ///
+    /// # Returns
+    /// Result<SignedTransaction, OfflineError> - Returns the raw signed transaction, otherwise
+    /// returns an error if the transaction is for another chain.
+    pub fn sign_for_network(&self, signer: &dyn Signer, network: &Network) -> Result<SignedTransaction, OfflineError> {
+        network.check_transaction(&self.transaction).map_err(|_| OfflineError::WrongNetwork)?;
+        self.sign(signer)
+    }
+
+    /// Records the broadcast of a transaction whose nonce was reserved by [`prepare_transaction`],
+    /// so that the nonce manager tracks it like a transaction it sent itself.
+    pub fn record_broadcast(&self, nonces: &NonceManager, hash: H256) {
+        nonces.record_sent(self.from, self.transaction.nonce, hash);
+    }
+
+    /// Returns the nonce reserved by [`prepare_transaction`] when the transaction will not be
+    /// broadcast, so that later sends are not queued behind it.
+    pub fn release_nonce(&self, nonces: &NonceManager) {
+        nonces.release(self.from, self.transaction.nonce);
+    }
+}
+
+/// Fills in the chain id, nonce, gas limit and fees of a request and returns it as an
+/// [`OfflineTransaction`] instead of sending it.
+///
+/// # Arguments
+/// * `web3` - The connection to the node, used only to read chain state.
+/// * `nonces` - The nonce manager of the sender. Unless `request` sets a nonce, one is reserved
+///   and must be handed back through [`OfflineTransaction::record_broadcast`] once the signed
+///   transaction is broadcast, or [`OfflineTransaction::release_nonce`] if it never is.
+/// * `request` - The transaction to prepare. A gas limit or nonce that is already set is kept.
+/// * `options` - The transaction type and fee options. With a network profile the node's chain
+///   id is checked against it and the transaction is refused on a mismatch, and the gas limit is
+///   checked under the profile's hard fork instead of the latest one.
+/// * `description` - A human-readable summary for the person approving the signature.
+///
+/// # Returns
+/// Result<OfflineTransaction, OfflineError> - Returns the prepared transaction, otherwise returns an error.
+pub async fn prepare_transaction<T: Transport>(
+    web3: &Web3<T>,
+    nonces: &NonceManager,
+    request: TransactionRequest,
+    options: &TypedTransactionOptions,
+    description: &str,
+) -> Result<OfflineTransaction, OfflineError> {
+    let chain_id = match &options.network {
+        Some(network) => network.verify_chain(web3).await.map_err(|e| match e {
+            NetworkError::ChainIdQueryFailed => OfflineError::PreparationFailed,
+            _ => OfflineError::WrongNetwork,
+        })?,
+        None => web3.eth().chain_id().await.map_err(|e| {
+            log_error(&format!("Failed to read chain id: {}", e));
+            OfflineError::PreparationFailed
+        })?.low_u64(),
+    };
+    let mut request = build_typed_transaction(web3, request, options).await.map_err(OfflineError::Gas)?;
+
+    if request.gas.is_none() {
+        let gas = estimate_gas_limit(web3, &request).await.map_err(OfflineError::Gas)?;
+        request.gas = Some(gas);
+    }
+    let gas = request.gas.unwrap_or_default();
+    let hardfork = options.network.as_ref().map_or(Hardfork::default(), |network| network.hardfork);
+    if gas > U256::from(u64::MAX) || check_gas_limit(gas.as_u64(), &request, hardfork).is_err() {
+        return Err(OfflineError::InvalidTransaction);
+    }
+    let reserved = request.nonce.is_none();
+    let nonce = match request.nonce {
+        Some(nonce) => nonce,
+        None => nonces
+            .next_nonce(web3, request.from)
+            .await
+            .map_err(OfflineError::Nonce)?,
+    };
+    request.nonce = Some(nonce);
+
+    let transaction = unsigned_transaction(&request, chain_id).and_then(|transaction| match &options.network {
+        Some(network) => network
+            .check_transaction(&transaction)
+            .map(|_| transaction)
+            .map_err(|_| OfflineError::WrongNetwork),
+        None => Ok(transaction),
+    });
+    if transaction.is_err() && reserved {
+        nonces.release(request.from, nonce);
+    }
+    let transaction = transaction?;
+    log_info(&format!("Prepared offline transaction from {:?} with nonce {}", request.from, nonce));
+    Ok(OfflineTransaction { from: request.from, description: description.to_string(), transaction })
+}
+
+/// Submits a raw signed transaction through `eth_sendRawTransaction`.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `raw_transaction` - The hex-encoded raw transaction produced by the offline signer.
+///
+/// # Returns
+/// Result<H256, OfflineError> - Returns the transaction hash, otherwise returns an error.
+pub async fn broadcast_raw_transaction<T: Transport>(web3: &Web3<T>, raw_transaction: &str) -> Result<H256, OfflineError> {
+    let raw: Bytes = serde_json::from_value(serde_json::Value::String(raw_transaction.trim().to_string()))
+        .map_err(|_| OfflineError::InvalidTransaction)?;
+
+    let hash = web3.eth().send_raw_transaction(raw).await.map_err(|e| {
+        log_error(&format!("Failed to broadcast raw transaction: {}", e));
+        OfflineError::BroadcastFailed
+    })?;
+    log_info(&format!("Broadcast raw transaction {:?}", hash));
+    Ok(hash)
+}
+
+/// Converts a fully populated request into an [`UnsignedTransaction`].
+fn unsigned_transaction(request: &TransactionRequest, chain_id: u64) -> Result<UnsignedTransaction, OfflineError> {
+    let transaction_type = match request.transaction_type.map(|t| t.as_u64()) {
+        None | Some(0) => TransactionType::Legacy,
+        Some(1) => TransactionType::AccessList,
+        Some(2) => TransactionType::Eip1559,
+        Some(_) => return Err(OfflineError::InvalidTransaction),
+    };
+    let missing = |field: &str| {
+        log_error(&format!("Offline transaction is missing `{}`.", field));
+        OfflineError::InvalidTransaction
+    };
+
+    let mut transaction = UnsignedTransaction {
+        transaction_type,
+        chain_id,
+        nonce: request.nonce.ok_or_else(|| missing("nonce"))?,
+        to: request.to,
+        value: request.value.unwrap_or_default(),
+        data: request.data.clone().unwrap_or_default().0,
+        gas: request.gas.ok_or_else(|| missing("gas"))?,
+        gas_price: U256::zero(),
+        max_fee_per_gas: U256::zero(),
+        max_priority_fee_per_gas: U256::zero(),
+        access_list: request.access_list.clone().unwrap_or_default(),
+    };
+    match transaction_type {
+        TransactionType::Eip1559 => {
+            transaction.max_fee_per_gas = request.max_fee_per_gas.ok_or_else(|| missing("maxFeePerGas"))?;
+            transaction.max_priority_fee_per_gas =
+                request.max_priority_fee_per_gas.ok_or_else(|| missing("maxPriorityFeePerGas"))?;
+        }
+        _ => transaction.gas_price = request.gas_price.ok_or_else(|| missing("gasPrice"))?,
+    }
+    Ok(transaction)
+}
+
+fn type_name(transaction_type: TransactionType) -> &'static str {
+    match transaction_type {
+        TransactionType::Legacy => "legacy",
+        TransactionType::AccessList => "accessList",
+        TransactionType::Eip1559 => "eip1559",
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::signer::PrivateKeySigner;
+    use web3::types::U64;
+
+    fn signer() -> PrivateKeySigner {
+        PrivateKeySigner::from_hex("0x4646464646464646464646464646464646464646464646464646464646464646").unwrap()
+    }
+
+    fn offline_transaction() -> OfflineTransaction {
+        let request = TransactionRequest {
+            from: signer().address(),
+            to: Some(Address::repeat_byte(0x35)),
+            value: Some(U256::from(1_000)),
+            data: Some(Bytes(vec![0x36, 0x59, 0xcf, 0xe6])),
+            gas: Some(U256::from(50_000)),
+            nonce: Some(U256::from(9)),
+            max_fee_per_gas: Some(U256::from(30_000_000_000u64)),
+            max_priority_fee_per_gas: Some(U256::from(1_000_000_000u64)),
+            transaction_type: Some(U64::from(2)),
+            ..Default::default()
+        };
+        OfflineTransaction {
+            from: request.from,
+            description: "Upgrade treasury proxy".to_string(),
+            transaction: unsigned_transaction(&request, 1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip_and_sign() {
+        let prepared = offline_transaction();
+        let json = prepared.to_json();
+        assert!(json.contains("\"type\": \"eip1559\""));
+        assert!(json.contains("\"unsignedRlp\": \"0x02"));
+
+        let imported = OfflineTransaction::from_json(&json).unwrap();
+        assert_eq!(imported, prepared);
+
+        let signed = imported.sign(&signer()).unwrap();
+        assert_eq!(signed.raw_transaction.0[0], 0x02);
+    }
+
+    #[test]
+    fn test_tampered_json_is_rejected() {
+        let json = offline_transaction().to_json().replace("\"value\": \"0x3e8\"", "\"value\": \"0x3e9\"");
+        assert!(matches!(OfflineTransaction::from_json(&json), Err(OfflineError::InvalidTransaction)));
+    }
+
+    #[test]
+    fn test_wrong_signer() {
+        let other = PrivateKeySigner::from_hex("0x0101010101010101010101010101010101010101010101010101010101010101").unwrap();
+        assert!(matches!(offline_transaction().sign(&other), Err(OfflineError::SignerMismatch)));
+    }
+
+    #[test]
+    fn test_sign_for_network() {
+        let prepared = offline_transaction();
+        assert!(prepared.sign_for_network(&signer(), &Network::mainnet()).is_ok());
+        assert!(matches!(prepared.sign_for_network(&signer(), &Network::sepolia()), Err(OfflineError::WrongNetwork)));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_refuses_wrong_chain() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0xaa36a7"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let request = TransactionRequest { from: signer().address(), ..Default::default() };
+        let options = TypedTransactionOptions { network: Some(Network::mainnet()), ..Default::default() };
+        let result = prepare_transaction(&web3, &NonceManager::new(), request, &options, "Transfer").await;
+        assert!(matches!(result, Err(OfflineError::WrongNetwork)));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_refuses_gas_below_intrinsic() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x1"));
+        mock.on_request("eth_gasPrice", None, serde_json::json!("0x3b9aca00"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        // 21,000 covers a plain transfer but not the calldata.
+        let request = TransactionRequest {
+            from: signer().address(),
+            to: Some(Address::repeat_byte(0x35)),
+            data: Some(Bytes(vec![0xff; 100])),
+            gas: Some(U256::from(21_000)),
+            ..Default::default()
+        };
+        let options = TypedTransactionOptions { transaction_type: Some(TransactionType::Legacy), ..Default::default() };
+        let result = prepare_transaction(&web3, &NonceManager::new(), request, &options, "Transfer").await;
+        assert!(matches!(result, Err(OfflineError::InvalidTransaction)));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_forwards_estimate_revert() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x1"));
+        mock.on_request("eth_gasPrice", None, serde_json::json!("0x3b9aca00"));
+        mock.on_request("eth_getBlockByNumber", None, serde_json::json!({"gasLimit": "0x1c9c380", "baseFeePerGas": "0x3b9aca00"}));
+        mock.on_error("eth_estimateGas", None, 3, "execution reverted: paused");
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let request = TransactionRequest {
+            from: signer().address(),
+            to: Some(Address::repeat_byte(0x35)),
+            ..Default::default()
+        };
+        let options = TypedTransactionOptions { transaction_type: Some(TransactionType::Legacy), ..Default::default() };
+        let error = prepare_transaction(&web3, &NonceManager::new(), request, &options, "Transfer").await.unwrap_err();
+        assert!(matches!(error, OfflineError::Gas(GasOptimizationError::Rejected(_))));
+
+        let error = crate::error::Error::from(crate::contracts::deploy::DeployError::Offline(error));
+        assert!(matches!(
+            error.root(),
+            crate::error::Error::ExecutionReverted { reason: Some(reason), .. } if reason == "paused"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_checks_gas_under_network_hardfork() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x38"));
+        mock.on_request("eth_gasPrice", None, serde_json::json!("0x3b9aca00"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        // 24,000 covers the 22,600 of intrinsic gas but not the 25,000 EIP-7623 floor.
+        let request = TransactionRequest {
+            from: signer().address(),
+            to: Some(Address::repeat_byte(0x35)),
+            data: Some(Bytes(vec![0xff; 100])),
+            gas: Some(U256::from(24_000)),
+            nonce: Some(U256::zero()),
+            ..Default::default()
+        };
+        let options = TypedTransactionOptions {
+            transaction_type: Some(TransactionType::Legacy),
+            network: Some(Network::bsc()),
+            ..Default::default()
+        };
+        let result = prepare_transaction(&web3, &NonceManager::new(), request.clone(), &options, "Transfer").await;
+        assert!(result.is_ok());
+
+        let options = TypedTransactionOptions {
+            network: Some(Network::bsc().hardfork(Hardfork::Prague)),
+            ..options
+        };
+        let result = prepare_transaction(&web3, &NonceManager::new(), request, &options, "Transfer").await;
+        assert!(matches!(result, Err(OfflineError::InvalidTransaction)));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_reserves_nonce_until_released() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x1"));
+        mock.on_request("eth_gasPrice", None, serde_json::json!("0x3b9aca00"));
+        mock.on_request("eth_getTransactionCount", None, serde_json::json!("0x5"));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+        let nonces = NonceManager::new();
+        let options = TypedTransactionOptions { transaction_type: Some(TransactionType::Legacy), ..Default::default() };
+        let request = TransactionRequest {
+            from: signer().address(),
+            to: Some(Address::repeat_byte(0x35)),
+            gas: Some(U256::from(21_000)),
+            ..Default::default()
+        };
+
+        // An explicit nonce is kept and nothing is reserved.
+        let explicit = TransactionRequest { nonce: Some(U256::from(9)), ..request.clone() };
+        let prepared = prepare_transaction(&web3, &nonces, explicit, &options, "Transfer").await.unwrap();
+        assert_eq!(prepared.transaction.nonce, U256::from(9));
+
+        let prepared = prepare_transaction(&web3, &nonces, request, &options, "Transfer").await.unwrap();
+        assert_eq!(prepared.transaction.nonce, U256::from(5));
+        assert!(nonces.find_gaps(&web3, prepared.from).await.unwrap().is_empty());
+
+        // A prepared transaction that is never broadcast gives its nonce back.
+        prepared.release_nonce(&nonces);
+        assert_eq!(nonces.find_gaps(&web3, prepared.from).await.unwrap(), vec![U256::from(5)]);
+        assert_eq!(nonces.resync(&web3, prepared.from).await.unwrap(), U256::from(5));
+    }
+
+    #[test]
+    fn test_missing_fields() {
+        let request = TransactionRequest { from: Address::repeat_byte(1), ..Default::default() };
+        assert!(matches!(unsigned_transaction(&request, 1), Err(OfflineError::InvalidTransaction)));
+    }
+}
@@ -0,0 +1,540 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::contracts::fees::{FeeEstimates, FeeOracle};
+use crate::contracts::gas_strategy::GasStrategy;
+use crate::contracts::network::{Network, NetworkError};
+use tokio::time::sleep;
+use web3::types::{BlockNumber, Bytes, TransactionId, TransactionReceipt, TransactionRequest, H256, U256, U64};
+use web3::{Transport, Web3};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::fmt;
+
+/// Nodes reject replacements that do not raise the fees by at least this percentage.
+pub const MIN_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+
+/// Gas used by a plain ether transfer, which is what a cancellation sends.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Errors that can occur while tracking a sent transaction.
+#[derive(Debug)]
+pub enum PendingTransactionError {
+    QueryFailed,
+    SendFailed,
+    WrongNetwork,
+    Timeout,
+    Reverted,
+    Replaced,
+    Dropped,
+}
+
+impl fmt::Display for PendingTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PendingTransactionError::QueryFailed => write!(f, "transaction query failed"),
+            PendingTransactionError::SendFailed => write!(f, "sending the replacement transaction failed"),
+            PendingTransactionError::WrongNetwork => write!(f, "the node serves another network"),
+            PendingTransactionError::Timeout => write!(f, "timed out waiting for the transaction"),
+            PendingTransactionError::Reverted => write!(f, "transaction reverted"),
+            PendingTransactionError::Replaced => write!(f, "transaction replaced"),
+            PendingTransactionError::Dropped => write!(f, "transaction dropped"),
+        }
+    }
+}
+
+impl std::error::Error for PendingTransactionError {}
+
+/// Where a sent transaction currently is in its lifecycle.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionStatus {
+    /// The node knows the transaction but it has not been mined yet.
+    Pending,
+    /// Mined, but with fewer confirmations than requested.
+    Mined { block_number: U64, confirmations: u64 },
+    /// Mined with at least the requested number of confirmations.
+    Confirmed { block_number: U64, confirmations: u64 },
+    /// Mined, but execution reverted.
+    Reverted { block_number: U64 },
+    /// The nonce was used by a transaction that was not sent through this handle.
+    Replaced,
+    /// Neither mined nor known to the node any more.
+    Dropped,
+}
+
+type StatusCallback = Box<dyn Fn(H256, &TransactionStatus) + Send + Sync>;
+
+/// A handle on a sent transaction that follows it until it is confirmed, replaced or dropped.
+///
+/// Every broadcast made through the handle (the original, speed-ups and cancellations) shares
+/// one nonce, so whichever of them is mined settles the handle.
+pub struct PendingTransaction {
+    request: TransactionRequest,
+    hashes: Vec<H256>,
+    confirmations: u64,
+    poll_interval: Duration,
+    timeout: Option<Duration>,
+    auto_speed_up: Option<(Duration, u64)>,
+    network: Option<Network>,
+    gas_strategy: Option<Arc<dyn GasStrategy>>,
+    fee_oracle: FeeOracle,
+    sent_at: Instant,
+    callbacks: Vec<StatusCallback>,
+}
+
+impl std::fmt::Debug for PendingTransaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PendingTransaction")
+            .field("hashes", &self.hashes)
+            .field("nonce", &self.request.nonce)
+            .field("confirmations", &self.confirmations)
+            .finish()
+    }
+}
+
+impl PendingTransaction {
+    /// Creates a handle for a transaction that has already been sent.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash returned when the transaction was sent.
+    /// * `request` - The request that was sent. A missing nonce or fee is read back from the
+    ///   node when the transaction is sped up or cancelled.
+    pub fn new(hash: H256, request: TransactionRequest) -> Self {
+        Self {
+            request,
+            hashes: vec![hash],
+            confirmations: 1,
+            poll_interval: Duration::from_secs(2),
+            timeout: None,
+            auto_speed_up: None,
+            network: None,
+            gas_strategy: None,
+            fee_oracle: FeeOracle::default(),
+            sent_at: Instant::now(),
+            callbacks: Vec::new(),
+        }
+    }
+
+    /// Sets how many confirmations `wait` requires. Defaults to 1.
+    pub fn confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Sets how often the node is polled. Defaults to 2 seconds.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Makes `wait` give up after `timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Makes `wait` speed the transaction up by `bump_percent` whenever it has been pending
+    /// for `after` since the last broadcast.
+    pub fn auto_speed_up(mut self, after: Duration, bump_percent: u64) -> Self {
+        self.auto_speed_up = Some((after, bump_percent));
+        self
+    }
+
+    /// Checks the node's chain id against a network profile before every speed-up or
+    /// cancellation, since replacements are signed by the node.
+    pub fn network(mut self, network: Network) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Lets a gas strategy decide the fees of speed-ups and cancellations from the time since
+    /// the transaction was first sent. The fees still rise by at least the requested bump.
+    pub fn gas_strategy(mut self, gas_strategy: Arc<dyn GasStrategy>) -> Self {
+        self.gas_strategy = Some(gas_strategy);
+        self
+    }
+
+    /// Sets the oracle whose estimates the gas strategy decides from on EIP-1559 chains.
+    pub fn fee_oracle(mut self, fee_oracle: FeeOracle) -> Self {
+        self.fee_oracle = fee_oracle;
+        self
+    }
+
+    /// Registers a callback that `wait` invokes with the current hash whenever the status changes.
+    pub fn on_status<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(H256, &TransactionStatus) + Send + Sync + 'static,
+    {
+        self.callbacks.push(Box::new(callback));
+        self
+    }
+
+    /// Returns the hash of the latest broadcast, or of the broadcast that was mined.
+    pub fn hash(&self) -> H256 {
+        *self.hashes.last().expect("at least one hash")
+    }
+
+    /// Returns the hashes of every broadcast made through this handle, oldest first.
+    pub fn hashes(&self) -> &[H256] {
+        &self.hashes
+    }
+
+    /// Queries the node once for the current status.
+    ///
+    /// # Returns
+    /// Result<TransactionStatus, PendingTransactionError> - Returns the status, otherwise returns an error.
+    pub async fn status<T: Transport>(&mut self, web3: &Web3<T>) -> Result<TransactionStatus, PendingTransactionError> {
+        // Read the nonce before the receipts: if it is already used and none of our broadcasts
+        // has a receipt afterwards, another transaction took the nonce.
+        let nonce_used = match self.request.nonce {
+            Some(nonce) => {
+                let mined_count = web3
+                    .eth()
+                    .transaction_count(self.request.from, Some(BlockNumber::Latest))
+                    .await
+                    .map_err(query_failed)?;
+                mined_count > nonce
+            }
+            None => false,
+        };
+
+        for (index, hash) in self.hashes.iter().enumerate().rev() {
+            let receipt = web3.eth().transaction_receipt(*hash).await.map_err(query_failed)?;
+            if let Some(receipt) = receipt.filter(|receipt| receipt.block_number.is_some()) {
+                // Settle on the broadcast that was actually mined.
+                self.hashes.truncate(index + 1);
+                let latest = web3.eth().block_number().await.map_err(query_failed)?;
+                return Ok(mined_status(&receipt, latest, self.confirmations));
+            }
+        }
+
+        if nonce_used {
+            return Ok(TransactionStatus::Replaced);
+        }
+        for hash in &self.hashes {
+            let known = web3.eth().transaction(TransactionId::Hash(*hash)).await.map_err(query_failed)?;
+            if known.is_some() {
+                return Ok(TransactionStatus::Pending);
+            }
+        }
+        Ok(TransactionStatus::Dropped)
+    }
+
+    /// Polls until the transaction has the requested confirmations.
+    ///
+    /// A transaction is only treated as dropped once it has been missing for two polls in a
+    /// row, since load-balanced nodes may briefly not know a fresh transaction.
+    ///
+    /// # Returns
+    /// Result<TransactionReceipt, PendingTransactionError> - Returns the receipt of the mined
+    /// broadcast, otherwise returns an error if it reverted, was replaced, dropped or timed out.
+    pub async fn wait<T: Transport>(&mut self, web3: &Web3<T>) -> Result<TransactionReceipt, PendingTransactionError> {
+        let started = Instant::now();
+        let mut last_broadcast = Instant::now();
+        let mut last_status: Option<TransactionStatus> = None;
+
+        loop {
+            let status = self.status(web3).await?;
+            let dropped_twice = status == TransactionStatus::Dropped && last_status.as_ref() == Some(&status);
+            if last_status.as_ref() != Some(&status) {
+                log_info(&format!("Transaction {:?}: {:?}", self.hash(), status));
+                for callback in &self.callbacks {
+                    callback(self.hash(), &status);
+                }
+            }
+
+            match status {
+                TransactionStatus::Confirmed { .. } => {
+                    return web3
+                        .eth()
+                        .transaction_receipt(self.hash())
+                        .await
+                        .map_err(query_failed)?
+                        .ok_or(PendingTransactionError::QueryFailed);
+                }
+                TransactionStatus::Reverted { .. } => return Err(PendingTransactionError::Reverted),
+                TransactionStatus::Replaced => return Err(PendingTransactionError::Replaced),
+                TransactionStatus::Dropped if dropped_twice => return Err(PendingTransactionError::Dropped),
+                TransactionStatus::Pending => {
+                    if let Some((after, bump_percent)) = self.auto_speed_up {
+                        if last_broadcast.elapsed() >= after {
+                            self.speed_up(web3, bump_percent).await?;
+                            last_broadcast = Instant::now();
+                        }
+                    }
+                }
+                _ => {}
+            }
+            last_status = Some(status);
+
+            if let Some(timeout) = self.timeout {
+                if started.elapsed() >= timeout {
+                    log_warn(&format!("Timed out waiting for transaction {:?}", self.hash()));
+                    return Err(PendingTransactionError::Timeout);
+                }
+            }
+            sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Rebroadcasts the transaction with the same nonce and fees raised by `bump_percent`
+    /// (at least [`MIN_REPLACEMENT_BUMP_PERCENT`]), or to what the gas strategy pays by now
+    /// if that is more.
+    ///
+    /// When the sender's nonces come from a `NonceManager`, pass the new hash to its
+    /// `record_sent` so that gap detection follows the replacement.
+    ///
+    /// # Returns
+    /// Result<H256, PendingTransactionError> - Returns the hash of the replacement.
+    pub async fn speed_up<T: Transport>(&mut self, web3: &Web3<T>, bump_percent: u64) -> Result<H256, PendingTransactionError> {
+        self.resolve(web3).await?;
+        let replacement = self.escalate(web3, bump_fees(&self.request, bump_percent)).await?;
+        self.replace(web3, replacement).await
+    }
+
+    /// Replaces the transaction with a zero-value transfer from the sender to itself, using the
+    /// same nonce and fees raised by `bump_percent` (at least [`MIN_REPLACEMENT_BUMP_PERCENT`]),
+    /// or to what the gas strategy pays by now if that is more.
+    ///
+    /// # Returns
+    /// Result<H256, PendingTransactionError> - Returns the hash of the cancellation.
+    pub async fn cancel<T: Transport>(&mut self, web3: &Web3<T>, bump_percent: u64) -> Result<H256, PendingTransactionError> {
+        self.resolve(web3).await?;
+        let bumped = self.escalate(web3, bump_fees(&self.request, bump_percent)).await?;
+        self.replace(web3, cancel_request(&bumped)).await
+    }
+
+    /// Sends a replacement and makes it the request that later replacements build on.
+    async fn replace<T: Transport>(
+        &mut self,
+        web3: &Web3<T>,
+        replacement: TransactionRequest,
+    ) -> Result<H256, PendingTransactionError> {
+        if let Some(network) = &self.network {
+            network.verify_chain(web3).await.map_err(|e| match e {
+                NetworkError::ChainIdQueryFailed => PendingTransactionError::QueryFailed,
+                _ => PendingTransactionError::WrongNetwork,
+            })?;
+        }
+        let hash = web3.eth().send_transaction(replacement.clone()).await.map_err(|e| {
+            log_error(&format!("Failed to replace transaction {:?}: {}", self.hash(), e));
+            PendingTransactionError::SendFailed
+        })?;
+
+        log_info(&format!("Replaced transaction {:?} with {:?}", self.hash(), hash));
+        self.request = replacement;
+        self.hashes.push(hash);
+        Ok(hash)
+    }
+
+    /// Raises the fees of a replacement to what the gas strategy pays after the time since the
+    /// transaction was first sent, if there is a strategy and it pays more.
+    async fn escalate<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        mut replacement: TransactionRequest,
+    ) -> Result<TransactionRequest, PendingTransactionError> {
+        let strategy = match &self.gas_strategy {
+            Some(strategy) => strategy,
+            None => return Ok(replacement),
+        };
+        let elapsed = self.sent_at.elapsed();
+
+        if let Some(gas_price) = replacement.gas_price {
+            let current = web3.eth().gas_price().await.map_err(query_failed)?;
+            let fees = strategy.fees(&FeeEstimates::from_gas_price(current), elapsed);
+            replacement.gas_price = Some(gas_price.max(fees.max_fee_per_gas));
+        } else {
+            let estimates = self.fee_oracle.estimate(web3).await.map_err(|e| {
+                log_error(&format!("Failed to estimate fees for a replacement: {}", e));
+                PendingTransactionError::QueryFailed
+            })?;
+            let fees = strategy.fees(&estimates, elapsed);
+            replacement.max_fee_per_gas = replacement.max_fee_per_gas.map(|fee| fee.max(fees.max_fee_per_gas));
+            replacement.max_priority_fee_per_gas =
+                replacement.max_priority_fee_per_gas.map(|fee| fee.max(fees.max_priority_fee_per_gas));
+        }
+        log_info(&format!("Gas strategy priced the replacement after {:?} pending.", elapsed));
+        Ok(replacement)
+    }
+
+    /// Fills in the nonce and fees from the node's copy of the transaction when the request
+    /// left them to the node.
+    async fn resolve<T: Transport>(&mut self, web3: &Web3<T>) -> Result<(), PendingTransactionError> {
+        let has_fees = self.request.gas_price.is_some() || self.request.max_fee_per_gas.is_some();
+        if self.request.nonce.is_some() && has_fees && self.request.gas.is_some() {
+            return Ok(());
+        }
+
+        let sent = web3
+            .eth()
+            .transaction(TransactionId::Hash(self.hash()))
+            .await
+            .map_err(query_failed)?
+            .ok_or(PendingTransactionError::Dropped)?;
+
+        self.request.nonce = Some(sent.nonce);
+        self.request.gas.get_or_insert(sent.gas);
+        if !has_fees {
+            if sent.max_fee_per_gas.is_some() {
+                self.request.max_fee_per_gas = sent.max_fee_per_gas;
+                self.request.max_priority_fee_per_gas = sent.max_priority_fee_per_gas;
+                self.request.transaction_type = sent.transaction_type;
+            } else {
+                self.request.gas_price = sent.gas_price;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Builds the status of a mined transaction from its receipt.
+fn mined_status(receipt: &TransactionReceipt, latest_block: U64, required: u64) -> TransactionStatus {
+    let block_number = receipt.block_number.unwrap_or_default();
+    if receipt.status == Some(U64::zero()) {
+        return TransactionStatus::Reverted { block_number };
+    }
+
+    let confirmations = latest_block.saturating_sub(block_number).as_u64() + 1;
+    if confirmations >= required {
+        TransactionStatus::Confirmed { block_number, confirmations }
+    } else {
+        TransactionStatus::Mined { block_number, confirmations }
+    }
+}
+
+/// Raises every fee set on a request by `bump_percent`, rounding up.
+fn bump_fees(request: &TransactionRequest, bump_percent: u64) -> TransactionRequest {
+    let bump_percent = U256::from(bump_percent.max(MIN_REPLACEMENT_BUMP_PERCENT) + 100);
+    let bump = |fee: U256| (fee * bump_percent + U256::from(99)) / U256::from(100);
+
+    let mut bumped = request.clone();
+    bumped.gas_price = request.gas_price.map(bump);
+    bumped.max_fee_per_gas = request.max_fee_per_gas.map(bump);
+    bumped.max_priority_fee_per_gas = request.max_priority_fee_per_gas.map(bump);
+    bumped
+}
+
+/// Turns a request into a zero-value self-transfer with the same sender, nonce and fees.
+fn cancel_request(request: &TransactionRequest) -> TransactionRequest {
+    TransactionRequest {
+        to: Some(request.from),
+        value: Some(U256::zero()),
+        data: Some(Bytes::default()),
+        gas: Some(U256::from(TRANSFER_GAS)),
+        access_list: None,
+        ..request.clone()
+    }
+}
+
+fn query_failed(e: web3::Error) -> PendingTransactionError {
+    log_error(&format!("Failed to query transaction status: {}", e));
+    PendingTransactionError::QueryFailed
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use web3::types::Address;
+
+    fn legacy_request() -> TransactionRequest {
+        TransactionRequest {
+            from: Address::repeat_byte(1),
+            to: Some(Address::repeat_byte(2)),
+            value: Some(U256::from(5)),
+            data: Some(Bytes(vec![0xde, 0xad])),
+            gas: Some(U256::from(60_000)),
+            gas_price: Some(U256::from(1_000)),
+            nonce: Some(U256::from(7)),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_bump_fees() {
+        let bumped = bump_fees(&legacy_request(), 25);
+        assert_eq!(bumped.gas_price, Some(U256::from(1_250)));
+        assert_eq!(bumped.nonce, Some(U256::from(7)));
+
+        // Bumps below the node minimum are raised to it, and results round up.
+        let eip1559 = TransactionRequest {
+            gas_price: None,
+            max_fee_per_gas: Some(U256::from(101)),
+            max_priority_fee_per_gas: Some(U256::from(1)),
+            ..legacy_request()
+        };
+        let bumped = bump_fees(&eip1559, 1);
+        assert_eq!(bumped.max_fee_per_gas, Some(U256::from(112)));
+        assert_eq!(bumped.max_priority_fee_per_gas, Some(U256::from(2)));
+        assert_eq!(bumped.gas_price, None);
+    }
+
+    #[test]
+    fn test_cancel_request() {
+        let cancellation = cancel_request(&legacy_request());
+        assert_eq!(cancellation.to, Some(Address::repeat_byte(1)));
+        assert_eq!(cancellation.value, Some(U256::zero()));
+        assert_eq!(cancellation.data, Some(Bytes::default()));
+        assert_eq!(cancellation.gas, Some(U256::from(TRANSFER_GAS)));
+        assert_eq!(cancellation.nonce, Some(U256::from(7)));
+        assert_eq!(cancellation.gas_price, Some(U256::from(1_000)));
+    }
+
+    #[tokio::test]
+    async fn test_replacement_refused_on_wrong_chain() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_chainId", None, serde_json::json!("0x89"));
+        mock.on_request("eth_sendTransaction", None, serde_json::json!(H256::repeat_byte(2)));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let mut pending = PendingTransaction::new(H256::repeat_byte(1), legacy_request()).network(Network::mainnet());
+        assert!(matches!(pending.speed_up(&web3, 10).await, Err(PendingTransactionError::WrongNetwork)));
+        assert!(matches!(pending.cancel(&web3, 10).await, Err(PendingTransactionError::WrongNetwork)));
+        assert!(web3.transport().provider().requests().iter().all(|(method, _)| method != "eth_sendTransaction"));
+        assert_eq!(pending.hashes(), &[H256::repeat_byte(1)]);
+    }
+
+    #[tokio::test]
+    async fn test_speed_up_escalates_with_elapsed_time() {
+        let mock = crate::framework::mock::MockProvider::new();
+        mock.on_request("eth_gasPrice", None, serde_json::json!("0x3e8"));
+        mock.on_request("eth_sendTransaction", None, serde_json::json!(H256::repeat_byte(2)));
+        let web3 = Web3::new(crate::framework::provider::ProviderTransport::new(mock));
+
+        let escalator = crate::contracts::gas_strategy::LinearEscalator {
+            speed: crate::contracts::fees::FeeSpeed::Normal,
+            increment: U256::from(1_000),
+            every: Duration::from_millis(20),
+            max_fee_per_gas: None,
+        };
+        let mut pending = PendingTransaction::new(H256::repeat_byte(1), legacy_request()).gas_strategy(Arc::new(escalator));
+        sleep(Duration::from_millis(50)).await;
+
+        // Two intervals have passed, so the escalator pays more than the 10% bump.
+        pending.speed_up(&web3, 10).await.unwrap();
+        assert!(pending.request.gas_price.unwrap() >= U256::from(3_000));
+        assert_eq!(pending.hashes(), &[H256::repeat_byte(1), H256::repeat_byte(2)]);
+    }
+
+    #[test]
+    fn test_mined_status() {
+        let mut receipt = TransactionReceipt {
+            block_number: Some(U64::from(100)),
+            status: Some(U64::one()),
+            ..Default::default()
+        };
+        assert_eq!(
+            mined_status(&receipt, U64::from(101), 3),
+            TransactionStatus::Mined { block_number: U64::from(100), confirmations: 2 }
+        );
+        assert_eq!(
+            mined_status(&receipt, U64::from(102), 3),
+            TransactionStatus::Confirmed { block_number: U64::from(100), confirmations: 3 }
+        );
+
+        receipt.status = Some(U64::zero());
+        assert_eq!(
+            mined_status(&receipt, U64::from(102), 3),
+            TransactionStatus::Reverted { block_number: U64::from(100) }
+        );
+    }
+}
@@ -0,0 +1,592 @@
+use crate::framework::logging::{log_info, log_error};
+use crate::contracts::abi::{to_hex, AbiFunction};
+use crate::error::{hex_bytes, Error};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use web3::types::{Address, Bytes, H256, U256};
+use web3::{Transport, Web3};
+
+/// Errors that can occur while profiling a transaction.
+#[derive(Debug)]
+pub enum ProfilerError {
+    /// The node could not trace the transaction, e.g. because `debug_traceTransaction` is disabled.
+    Rejected(Box<Error>),
+    TransactionNotFound,
+    InvalidTrace(String),
+}
+
+impl fmt::Display for ProfilerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfilerError::Rejected(error) => write!(f, "tracing rejected: {}", error),
+            ProfilerError::TransactionNotFound => write!(f, "transaction not found"),
+            ProfilerError::InvalidTrace(reason) => write!(f, "invalid trace: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ProfilerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProfilerError::Rejected(error) => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// One step of a `debug_traceTransaction` struct-log trace.
+///
+/// # Fields
+/// - `op`: The opcode mnemonic.
+/// - `gas`: The gas left before the step.
+/// - `gas_cost`: The cost of the step as reported by the node; for calls it includes the gas
+///   forwarded to the callee.
+/// - `depth`: The call depth, starting at 1.
+/// - `stack`: The stack before the step, bottom first, if the node recorded it.
+/// - `memory`: The memory before the step as 32-byte hex words, if the node recorded it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLog {
+    pub pc: u64,
+    pub op: String,
+    pub gas: u64,
+    pub gas_cost: u64,
+    pub depth: u64,
+    #[serde(default)]
+    pub stack: Option<Vec<String>>,
+    #[serde(default)]
+    pub memory: Option<Vec<String>>,
+}
+
+impl StructLog {
+    /// Returns the stack item `index` places below the top.
+    fn stack_item(&self, index: usize) -> Option<U256> {
+        let stack = self.stack.as_ref()?;
+        let item = stack.get(stack.len().checked_sub(index + 1)?)?;
+        U256::from_str_radix(item.trim_start_matches("0x"), 16).ok()
+    }
+
+    /// Reads `length` bytes of memory at `offset`, or `None` if they were not recorded.
+    fn memory_slice(&self, offset: usize, length: usize) -> Option<Vec<u8>> {
+        let memory = self.memory.as_ref()?;
+        let mut bytes = Vec::with_capacity(length);
+        for position in offset..offset.checked_add(length)? {
+            let word = hex_bytes(memory.get(position / 32)?.trim_start_matches("0x"))?;
+            bytes.push(*word.get(position % 32)?);
+        }
+        Some(bytes)
+    }
+}
+
+/// The result of `debug_traceTransaction` with the default struct-log tracer.
+///
+/// # Fields
+/// - `gas`: The gas used by the transaction, including intrinsic gas.
+/// - `failed`: Whether the transaction reverted.
+/// - `struct_logs`: The executed steps.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StructLogTrace {
+    pub gas: u64,
+    #[serde(default)]
+    pub failed: bool,
+    pub struct_logs: Vec<StructLog>,
+}
+
+impl StructLogTrace {
+    /// Parses a trace saved as JSON, either the trace itself or a full JSON-RPC response.
+    pub fn from_json(json: &str) -> Result<Self, ProfilerError> {
+        let mut value: Value = serde_json::from_str(json).map_err(|e| ProfilerError::InvalidTrace(e.to_string()))?;
+        if let Some(result) = value.get_mut("result") {
+            value = result.take();
+        }
+        serde_json::from_value(value).map_err(|e| ProfilerError::InvalidTrace(e.to_string()))
+    }
+}
+
+/// Fetches the struct-log trace of a mined transaction, with memory so that call selectors
+/// can be read.
+///
+/// # Arguments
+/// * `web3` - The connection to a node with the `debug` namespace enabled.
+/// * `transaction_hash` - The hash of the transaction.
+///
+/// # Returns
+/// Result<StructLogTrace, ProfilerError> - Returns the trace, otherwise returns an error.
+pub async fn trace_transaction<T: Transport>(
+    web3: &Web3<T>,
+    transaction_hash: H256,
+) -> Result<StructLogTrace, ProfilerError> {
+    let params = vec![json!(transaction_hash), json!({"enableMemory": true, "disableStorage": true})];
+    let value = web3.transport().execute("debug_traceTransaction", params).await.map_err(|e| {
+        log_error(&format!("Failed to trace transaction {:?}: {}", transaction_hash, e));
+        ProfilerError::Rejected(Box::new(Error::from(e)))
+    })?;
+    serde_json::from_value(value).map_err(|e| ProfilerError::InvalidTrace(e.to_string()))
+}
+
+/// The fields of a transaction needed to label the top-level call.
+#[derive(Debug, Deserialize)]
+struct TracedTransaction {
+    to: Option<Address>,
+    input: Bytes,
+}
+
+/// How a call frame was entered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    /// The transaction itself.
+    Transaction,
+    Call,
+    StaticCall,
+    DelegateCall,
+    CallCode,
+    Create,
+    Create2,
+}
+
+impl CallKind {
+    fn from_op(op: &str) -> Option<Self> {
+        match op {
+            "CALL" => Some(CallKind::Call),
+            "STATICCALL" => Some(CallKind::StaticCall),
+            "DELEGATECALL" => Some(CallKind::DelegateCall),
+            "CALLCODE" => Some(CallKind::CallCode),
+            "CREATE" => Some(CallKind::Create),
+            "CREATE2" => Some(CallKind::Create2),
+            _ => None,
+        }
+    }
+
+    fn is_create(&self) -> bool {
+        matches!(self, CallKind::Create | CallKind::Create2)
+    }
+}
+
+/// The gas used by one call frame.
+///
+/// # Fields
+/// - `kind`: How the frame was entered.
+/// - `address`: The contract whose code ran; for creations, the new contract if it was deployed.
+/// - `selector`: The selector of the called function, if the calldata was recorded.
+/// - `label`: `Contract::function` from the registered labels and ABIs, or the raw address and selector.
+/// - `gas_used`: The gas used by the frame and its children.
+/// - `self_gas`: The gas used by the frame's own instructions, including `sload_gas`, `sstore_gas` and `call_gas`.
+/// - `sload_gas`, `sstore_gas`: The gas of the frame's storage reads and writes.
+/// - `sloads`, `sstores`: The number of storage reads and writes.
+/// - `call_gas`: The gas of the frame's calls and creations themselves (access, value transfer,
+///   memory), excluding what the callees used.
+/// - `children`: The frames called from this one, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameProfile {
+    pub kind: CallKind,
+    pub address: Option<Address>,
+    pub selector: Option<[u8; 4]>,
+    pub label: String,
+    pub gas_used: u64,
+    pub self_gas: u64,
+    pub sload_gas: u64,
+    pub sstore_gas: u64,
+    pub sloads: usize,
+    pub sstores: usize,
+    pub call_gas: u64,
+    pub children: Vec<FrameProfile>,
+}
+
+impl FrameProfile {
+    fn new(kind: CallKind, address: Option<Address>, selector: Option<[u8; 4]>) -> Self {
+        Self {
+            kind,
+            address,
+            selector,
+            label: String::new(),
+            gas_used: 0,
+            self_gas: 0,
+            sload_gas: 0,
+            sstore_gas: 0,
+            sloads: 0,
+            sstores: 0,
+            call_gas: 0,
+            children: Vec::new(),
+        }
+    }
+
+    /// Returns the self gas not spent on storage or calls.
+    pub fn execution_gas(&self) -> u64 {
+        self.self_gas.saturating_sub(self.sload_gas + self.sstore_gas + self.call_gas)
+    }
+
+    fn write_tree(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        write!(f, "{:indent$}{} {} gas (self {}", "", self.label, self.gas_used, self.self_gas, indent = indent)?;
+        if self.sloads > 0 {
+            write!(f, ", SLOAD {} x{}", self.sload_gas, self.sloads)?;
+        }
+        if self.sstores > 0 {
+            write!(f, ", SSTORE {} x{}", self.sstore_gas, self.sstores)?;
+        }
+        if self.call_gas > 0 {
+            write!(f, ", calls {}", self.call_gas)?;
+        }
+        writeln!(f, ")")?;
+        for child in &self.children {
+            child.write_tree(f, indent + 2)?;
+        }
+        Ok(())
+    }
+
+    fn write_folded(&self, prefix: &str, out: &mut String) {
+        let path = if prefix.is_empty() { self.label.clone() } else { format!("{};{}", prefix, self.label) };
+        for (leaf, gas) in [
+            ("", self.execution_gas()),
+            (";SLOAD", self.sload_gas),
+            (";SSTORE", self.sstore_gas),
+            (";CALL", self.call_gas),
+        ] {
+            if gas > 0 {
+                out.push_str(&format!("{}{} {}\n", path, leaf, gas));
+            }
+        }
+        for child in &self.children {
+            child.write_folded(&path, out);
+        }
+    }
+}
+
+/// Where the gas of a transaction went.
+///
+/// # Fields
+/// - `total_gas`: The gas used by the transaction, including intrinsic gas and refunds.
+/// - `failed`: Whether the transaction reverted.
+/// - `root`: The top-level call frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasProfile {
+    pub total_gas: u64,
+    pub failed: bool,
+    pub root: FrameProfile,
+}
+
+impl GasProfile {
+    /// Returns the profile in the folded-stack format read by `flamegraph.pl` and `inferno`.
+    /// Each line is a path of frames, optionally ending in `SLOAD`, `SSTORE` or `CALL`, and its gas.
+    pub fn folded(&self) -> String {
+        let mut out = String::new();
+        self.root.write_folded("", &mut out);
+        out
+    }
+}
+
+impl fmt::Display for GasProfile {
+    /// Formats the frames as an indented tree.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Transaction: {} gas{}", self.total_gas, if self.failed { " (failed)" } else { "" })?;
+        self.root.write_tree(f, 2)
+    }
+}
+
+/// Attributes the gas of traced transactions to call frames, functions, storage access and calls.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    functions: HashMap<[u8; 4], String>,
+    labels: HashMap<Address, String>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Names frames calling these functions by their signature instead of their selector.
+    pub fn abi(mut self, functions: &[AbiFunction]) -> Self {
+        for function in functions {
+            self.functions.insert(function.selector(), function.signature());
+        }
+        self
+    }
+
+    /// Names frames running at an address, e.g. `Token`.
+    pub fn label(mut self, address: Address, name: &str) -> Self {
+        self.labels.insert(address, name.to_string());
+        self
+    }
+
+    /// Traces a mined transaction and profiles its gas.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to a node with the `debug` namespace enabled.
+    /// * `transaction_hash` - The hash of the transaction.
+    ///
+    /// # Returns
+    /// Result<GasProfile, ProfilerError> - Returns the profile, otherwise returns an error.
+    pub async fn profile_transaction<T: Transport>(
+        &self,
+        web3: &Web3<T>,
+        transaction_hash: H256,
+    ) -> Result<GasProfile, ProfilerError> {
+        let value = web3
+            .transport()
+            .execute("eth_getTransactionByHash", vec![json!(transaction_hash)])
+            .await
+            .map_err(|e| ProfilerError::Rejected(Box::new(Error::from(e))))?;
+        if value.is_null() {
+            return Err(ProfilerError::TransactionNotFound);
+        }
+        let transaction: TracedTransaction =
+            serde_json::from_value(value).map_err(|e| ProfilerError::InvalidTrace(e.to_string()))?;
+        let trace = trace_transaction(web3, transaction_hash).await?;
+
+        let profile = self.profile(&trace, transaction.to, &transaction.input.0);
+        log_info(&format!(
+            "Profiled transaction {:?}: {} gas, {} in execution.",
+            transaction_hash, profile.total_gas, profile.root.gas_used
+        ));
+        Ok(profile)
+    }
+
+    /// Profiles a trace, e.g. one loaded with [`StructLogTrace::from_json`].
+    ///
+    /// A step's gas is the drop in gas left until the next step of the same frame, so calls are
+    /// split into the callee's gas and the call's own cost.
+    ///
+    /// # Arguments
+    /// * `trace` - The struct-log trace.
+    /// * `to` - The recipient of the transaction; `None` for a contract creation.
+    /// * `input` - The calldata of the transaction.
+    pub fn profile(&self, trace: &StructLogTrace, to: Option<Address>, input: &[u8]) -> GasProfile {
+        let kind = if to.is_some() { CallKind::Transaction } else { CallKind::Create };
+        let root = FrameProfile::new(kind, to, selector(input));
+        // The open frames, each with the gas left at the step that entered it.
+        let mut frames: Vec<(FrameProfile, u64)> = vec![(root, 0)];
+        let logs = &trace.struct_logs;
+
+        for (index, log) in logs.iter().enumerate() {
+            let next = logs.get(index + 1);
+            let call_kind = CallKind::from_op(&log.op);
+            match next {
+                Some(next) if next.depth > log.depth => {
+                    // Entering a callee; its cost is known once it returns.
+                    let child = match call_kind {
+                        Some(kind) => self.callee(kind, log),
+                        None => FrameProfile::new(CallKind::Call, None, None),
+                    };
+                    frames.push((child, log.gas));
+                    continue;
+                }
+                Some(next) if next.depth == log.depth => {
+                    let cost = log.gas.saturating_sub(next.gas);
+                    charge(&mut frames.last_mut().expect("a frame is open").0, &log.op, call_kind, cost);
+                }
+                _ => charge(&mut frames.last_mut().expect("a frame is open").0, &log.op, call_kind, log.gas_cost),
+            }
+
+            // Close the frames this step returns from.
+            let depth = next.map_or(0, |next| next.depth);
+            while frames.len() > 1 && depth < frames.len() as u64 {
+                let (mut child, gas_at_call) = frames.pop().expect("a frame is open");
+                finish(&mut child);
+                let parent_depth = frames.len() as u64;
+                let parent = &mut frames.last_mut().expect("a frame is open").0;
+                if let Some(next) = next.filter(|next| next.depth == parent_depth) {
+                    if child.kind.is_create() {
+                        child.address = next.stack_item(0).filter(|a| !a.is_zero()).map(address_from_word);
+                    }
+                    let cost = gas_at_call.saturating_sub(next.gas);
+                    let overhead = cost.saturating_sub(child.gas_used);
+                    parent.self_gas += overhead;
+                    parent.call_gas += overhead;
+                }
+                parent.children.push(child);
+            }
+        }
+
+        let (mut root, _) = frames.pop().expect("the root frame is open");
+        finish(&mut root);
+        let mut profile = GasProfile { total_gas: trace.gas, failed: trace.failed, root };
+        self.label_frames(&mut profile.root);
+        profile
+    }
+
+    /// Builds the frame entered by a call or creation step.
+    fn callee(&self, kind: CallKind, log: &StructLog) -> FrameProfile {
+        if kind.is_create() {
+            return FrameProfile::new(kind, None, None);
+        }
+        let address = log.stack_item(1).map(address_from_word);
+        // CALL and CALLCODE take a value before the calldata arguments.
+        let arguments = if matches!(kind, CallKind::Call | CallKind::CallCode) { 3 } else { 2 };
+        let calldata = match (log.stack_item(arguments), log.stack_item(arguments + 1)) {
+            (Some(offset), Some(length)) if length >= U256::from(4) && offset < U256::from(u32::MAX) => {
+                log.memory_slice(offset.as_usize(), 4)
+            }
+            (Some(_), Some(_)) => Some(Vec::new()),
+            _ => None,
+        };
+        FrameProfile::new(kind, address, calldata.and_then(|calldata| selector(&calldata)))
+    }
+
+    fn label_frames(&self, frame: &mut FrameProfile) {
+        let contract = match frame.address {
+            Some(address) => self.labels.get(&address).cloned().unwrap_or_else(|| format!("{:?}", address)),
+            None => "<new contract>".to_string(),
+        };
+        let function = match (frame.kind.is_create(), frame.selector) {
+            (true, _) => "constructor".to_string(),
+            (false, Some(selector)) => {
+                self.functions.get(&selector).cloned().unwrap_or_else(|| format!("0x{}", to_hex(&selector)))
+            }
+            (false, None) => "fallback".to_string(),
+        };
+        frame.label = format!("{}::{}", contract, function);
+        for child in &mut frame.children {
+            self.label_frames(child);
+        }
+    }
+}
+
+/// Adds the cost of a step to its frame.
+fn charge(frame: &mut FrameProfile, op: &str, call_kind: Option<CallKind>, cost: u64) {
+    frame.self_gas += cost;
+    match op {
+        "SLOAD" => {
+            frame.sload_gas += cost;
+            frame.sloads += 1;
+        }
+        "SSTORE" => {
+            frame.sstore_gas += cost;
+            frame.sstores += 1;
+        }
+        // Calls to precompiles and accounts without code stay in the caller's frame.
+        _ if call_kind.is_some() => frame.call_gas += cost,
+        _ => {}
+    }
+}
+
+/// Sets the inclusive gas of a frame once its children are known.
+fn finish(frame: &mut FrameProfile) {
+    frame.gas_used = frame.self_gas + frame.children.iter().map(|child| child.gas_used).sum::<u64>();
+}
+
+fn selector(calldata: &[u8]) -> Option<[u8; 4]> {
+    calldata.get(..4).map(|bytes| [bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn address_from_word(word: U256) -> Address {
+    let mut bytes = [0u8; 32];
+    word.to_big_endian(&mut bytes);
+    Address::from_slice(&bytes[12..])
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+
+    /// `Token::transfer` reads a slot, then calls `Vault::balanceOf`, which writes a slot.
+    fn trace_json() -> Value {
+        let selector_word = format!("70a08231{}", "0".repeat(56));
+        json!({
+            "gas": 46_000,
+            "failed": false,
+            "returnValue": "",
+            "structLogs": [
+                {"pc": 0, "op": "PUSH1", "gas": 100_000, "gasCost": 3, "depth": 1, "stack": []},
+                {"pc": 2, "op": "SLOAD", "gas": 99_997, "gasCost": 2_100, "depth": 1, "stack": ["0x0"]},
+                {
+                    "pc": 3, "op": "CALL", "gas": 97_897, "gasCost": 62_000, "depth": 1,
+                    "stack": ["0x20", "0x0", "0x24", "0x0", "0x0", format!("{:?}", Address::repeat_byte(0x22)), "0xea60"],
+                    "memory": [selector_word, "0".repeat(64)]
+                },
+                {"pc": 0, "op": "PUSH1", "gas": 60_000, "gasCost": 3, "depth": 2, "stack": []},
+                {"pc": 2, "op": "SSTORE", "gas": 59_997, "gasCost": 20_000, "depth": 2, "stack": ["0x1", "0x0"]},
+                {"pc": 3, "op": "RETURN", "gas": 39_997, "gasCost": 0, "depth": 2, "stack": ["0x20", "0x0"]},
+                {"pc": 4, "op": "POP", "gas": 75_294, "gasCost": 2, "depth": 1, "stack": ["0x1"]},
+                {"pc": 5, "op": "STOP", "gas": 75_292, "gasCost": 0, "depth": 1, "stack": []}
+            ]
+        })
+    }
+
+    fn profiler() -> Profiler {
+        let abi = crate::contracts::abi::parse_abi(
+            r#"[
+                {"name": "transfer", "inputs": ["address", "uint256"], "outputs": ["bool"], "payable": false, "constant": false},
+                {"name": "balanceOf", "inputs": ["address"], "outputs": ["uint256"], "payable": false, "constant": true}
+            ]"#,
+        )
+        .unwrap();
+        Profiler::new()
+            .abi(&abi)
+            .label(Address::repeat_byte(0x11), "Token")
+            .label(Address::repeat_byte(0x22), "Vault")
+    }
+
+    fn transfer_input() -> Vec<u8> {
+        let mut input = vec![0xa9, 0x05, 0x9c, 0xbb];
+        input.extend([0u8; 64]);
+        input
+    }
+
+    #[test]
+    fn test_profile_frames() {
+        let trace = StructLogTrace::from_json(&json!({"jsonrpc": "2.0", "id": 1, "result": trace_json()}).to_string()).unwrap();
+        let profile = profiler().profile(&trace, Some(Address::repeat_byte(0x11)), &transfer_input());
+
+        let root = &profile.root;
+        assert_eq!(root.label, "Token::transfer(address,uint256)");
+        assert_eq!(root.gas_used, 24_708);
+        assert_eq!(root.self_gas, 4_705);
+        assert_eq!((root.sload_gas, root.sloads), (2_100, 1));
+        assert_eq!(root.call_gas, 2_600);
+
+        let vault = &root.children[0];
+        assert_eq!(vault.kind, CallKind::Call);
+        assert_eq!(vault.label, "Vault::balanceOf(address)");
+        assert_eq!(vault.gas_used, 20_003);
+        assert_eq!((vault.sstore_gas, vault.sstores), (20_000, 1));
+
+        assert!(profile.to_string().contains("    Vault::balanceOf(address) 20003 gas (self 20003, SSTORE 20000 x1)"));
+    }
+
+    #[test]
+    fn test_folded_stacks() {
+        let trace: StructLogTrace = serde_json::from_value(trace_json()).unwrap();
+        let profile = profiler().profile(&trace, Some(Address::repeat_byte(0x11)), &transfer_input());
+        assert_eq!(
+            profile.folded(),
+            "Token::transfer(address,uint256) 5\n\
+             Token::transfer(address,uint256);SLOAD 2100\n\
+             Token::transfer(address,uint256);CALL 2600\n\
+             Token::transfer(address,uint256);Vault::balanceOf(address) 3\n\
+             Token::transfer(address,uint256);Vault::balanceOf(address);SSTORE 20000\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_profile_transaction() {
+        let hash = H256::repeat_byte(0xab);
+        let mock = MockProvider::new();
+        mock.on_request(
+            "eth_getTransactionByHash",
+            None,
+            json!({"to": Address::repeat_byte(0x11), "input": Bytes(transfer_input())}),
+        );
+        mock.on_request("debug_traceTransaction", None, trace_json());
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let profile = profiler().profile_transaction(&web3, hash).await.unwrap();
+        assert_eq!(profile.total_gas, 46_000);
+        assert_eq!(profile.root.children.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tracing_disabled() {
+        let mock = MockProvider::new();
+        mock.on_error("debug_traceTransaction", None, -32601, "the method debug_traceTransaction does not exist");
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let result = trace_transaction(&web3, H256::zero()).await;
+        assert!(matches!(result, Err(ProfilerError::Rejected(_))));
+    }
+}
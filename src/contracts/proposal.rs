@@ -0,0 +1,246 @@
+use crate::contracts::abi::{encode_call, to_hex};
+use web3::ethabi::{encode, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, H256, U256};
+
+/// Type string of the Safe EIP-712 domain, used by Safe >= 1.3.0.
+const SAFE_DOMAIN_TYPEHASH: &str = "EIP712Domain(uint256 chainId,address verifyingContract)";
+
+/// Type string hashed into the `SafeTx` EIP-712 struct hash.
+const SAFE_TX_TYPEHASH: &str = "SafeTx(address to,uint256 value,bytes data,uint8 operation,uint256 safeTxGas,uint256 baseGas,uint256 gasPrice,address gasToken,address refundReceiver,uint256 nonce)";
+
+/// Kind of call a Safe performs when executing a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SafeOperation {
+    Call = 0,
+    DelegateCall = 1,
+}
+
+/// A Safe multisig transaction ready to be proposed to the Safe Transaction Service
+/// or signed by the owners.
+///
+/// # Fields
+/// - `safe`: The address of the Safe that will execute the transaction.
+/// - `chain_id`: The chain the Safe is deployed on, part of the EIP-712 domain.
+/// - `to`: The target contract of the transaction.
+/// - `value`: The amount of Ether sent with the transaction.
+/// - `data`: The calldata executed against `to`.
+/// - `operation`: Whether the Safe performs a `CALL` or a `DELEGATECALL`.
+/// - `nonce`: The Safe nonce the transaction is proposed for.
+///
+/// The refund fields (`safe_tx_gas`, `base_gas`, `gas_price`, `gas_token`, `refund_receiver`)
+/// are zero by default, which is what the Safe UI uses for owner-paid execution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SafeTransaction {
+    pub safe: Address,
+    pub chain_id: U256,
+    pub to: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub operation: SafeOperation,
+    pub safe_tx_gas: U256,
+    pub base_gas: U256,
+    pub gas_price: U256,
+    pub gas_token: Address,
+    pub refund_receiver: Address,
+    pub nonce: U256,
+}
+
+impl SafeTransaction {
+    /// Creates a zero-value `CALL` transaction with no gas refund.
+    pub fn new(safe: Address, chain_id: U256, to: Address, data: Vec<u8>, nonce: U256) -> Self {
+        Self {
+            safe,
+            chain_id,
+            to,
+            value: U256::zero(),
+            data,
+            operation: SafeOperation::Call,
+            safe_tx_gas: U256::zero(),
+            base_gas: U256::zero(),
+            gas_price: U256::zero(),
+            gas_token: Address::zero(),
+            refund_receiver: Address::zero(),
+            nonce,
+        }
+    }
+
+    /// Returns the EIP-712 domain separator of the Safe.
+    pub fn domain_separator(&self) -> H256 {
+        H256(keccak256(&encode(&[
+            Token::FixedBytes(keccak256(SAFE_DOMAIN_TYPEHASH.as_bytes()).to_vec()),
+            Token::Uint(self.chain_id),
+            Token::Address(self.safe),
+        ])))
+    }
+
+    /// Returns the EIP-712 struct hash of the `SafeTx`.
+    pub fn struct_hash(&self) -> H256 {
+        H256(keccak256(&encode(&[
+            Token::FixedBytes(keccak256(SAFE_TX_TYPEHASH.as_bytes()).to_vec()),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+            Token::Uint(U256::from(self.operation as u8)),
+            Token::Uint(self.safe_tx_gas),
+            Token::Uint(self.base_gas),
+            Token::Uint(self.gas_price),
+            Token::Address(self.gas_token),
+            Token::Address(self.refund_receiver),
+            Token::Uint(self.nonce),
+        ])))
+    }
+
+    /// Returns the `safeTxHash` the owners sign, as computed by `Safe.getTransactionHash`.
+    pub fn safe_tx_hash(&self) -> H256 {
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(&[0x19, 0x01]);
+        message.extend_from_slice(self.domain_separator().as_bytes());
+        message.extend_from_slice(self.struct_hash().as_bytes());
+        H256(keccak256(&message))
+    }
+
+    /// Serializes the transaction in the format accepted by the Safe Transaction Service.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "safe": format!("{:?}", self.safe),
+            "to": format!("{:?}", self.to),
+            "value": self.value.to_string(),
+            "data": format!("0x{}", to_hex(&self.data)),
+            "operation": self.operation as u8,
+            "safeTxGas": self.safe_tx_gas.to_string(),
+            "baseGas": self.base_gas.to_string(),
+            "gasPrice": self.gas_price.to_string(),
+            "gasToken": format!("{:?}", self.gas_token),
+            "refundReceiver": format!("{:?}", self.refund_receiver),
+            "nonce": self.nonce.to_string(),
+            "contractTransactionHash": format!("{:?}", self.safe_tx_hash()),
+        })
+    }
+}
+
+/// An operation on an OpenZeppelin `TimelockController`.
+///
+/// # Fields
+/// - `timelock`: The address of the timelock.
+/// - `target`: The contract the timelock calls once the delay has passed.
+/// - `value`: The amount of Ether sent with the call.
+/// - `data`: The calldata executed against `target`.
+/// - `predecessor`: The operation that must be executed first, or zero for none.
+/// - `salt`: A value distinguishing otherwise identical operations.
+/// - `delay`: The delay in seconds requested when scheduling.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelockOperation {
+    pub timelock: Address,
+    pub target: Address,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub predecessor: H256,
+    pub salt: H256,
+    pub delay: U256,
+}
+
+impl TimelockOperation {
+    /// Returns the operation id, as computed by `TimelockController.hashOperation`.
+    pub fn id(&self) -> H256 {
+        H256(keccak256(&encode(&[
+            Token::Address(self.target),
+            Token::Uint(self.value),
+            Token::Bytes(self.data.clone()),
+            Token::FixedBytes(self.predecessor.as_bytes().to_vec()),
+            Token::FixedBytes(self.salt.as_bytes().to_vec()),
+        ])))
+    }
+
+    /// Returns the calldata for `schedule(address,uint256,bytes,bytes32,bytes32,uint256)`.
+    pub fn schedule_calldata(&self) -> Vec<u8> {
+        encode_call(
+            "schedule(address,uint256,bytes,bytes32,bytes32,uint256)",
+            &[
+                Token::Address(self.target),
+                Token::Uint(self.value),
+                Token::Bytes(self.data.clone()),
+                Token::FixedBytes(self.predecessor.as_bytes().to_vec()),
+                Token::FixedBytes(self.salt.as_bytes().to_vec()),
+                Token::Uint(self.delay),
+            ],
+        )
+    }
+
+    /// Returns the calldata for `execute(address,uint256,bytes,bytes32,bytes32)`.
+    pub fn execute_calldata(&self) -> Vec<u8> {
+        encode_call(
+            "execute(address,uint256,bytes,bytes32,bytes32)",
+            &[
+                Token::Address(self.target),
+                Token::Uint(self.value),
+                Token::Bytes(self.data.clone()),
+                Token::FixedBytes(self.predecessor.as_bytes().to_vec()),
+                Token::FixedBytes(self.salt.as_bytes().to_vec()),
+            ],
+        )
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> Address {
+        Address::repeat_byte(byte)
+    }
+
+    #[test]
+    fn test_timelock_selectors() {
+        let operation = TimelockOperation {
+            timelock: address(1),
+            target: address(2),
+            value: U256::zero(),
+            data: vec![0xde, 0xad],
+            predecessor: H256::zero(),
+            salt: H256::zero(),
+            delay: U256::from(172_800),
+        };
+
+        assert_eq!(operation.schedule_calldata()[..4], [0x01, 0xd5, 0x06, 0x2a]);
+        assert_eq!(operation.execute_calldata()[..4], [0x13, 0x40, 0x08, 0xd3]);
+    }
+
+    #[test]
+    fn test_timelock_id_depends_on_salt() {
+        let mut operation = TimelockOperation {
+            timelock: address(1),
+            target: address(2),
+            value: U256::zero(),
+            data: vec![],
+            predecessor: H256::zero(),
+            salt: H256::zero(),
+            delay: U256::zero(),
+        };
+        let first = operation.id();
+        operation.salt = H256::repeat_byte(1);
+        assert_ne!(first, operation.id());
+    }
+
+    #[test]
+    fn test_safe_typehashes() {
+        assert_eq!(
+            to_hex(&keccak256(SAFE_TX_TYPEHASH.as_bytes())),
+            "bb8310d486368db6bd6f849402fdd73ad53d316b5a4b2644ad6efe0f941286d8"
+        );
+        assert_eq!(
+            to_hex(&keccak256(SAFE_DOMAIN_TYPEHASH.as_bytes())),
+            "47e79534a245952e8b16893a336b85a3d9ea9fa8c573f3d803afb92a79469218"
+        );
+    }
+
+    #[test]
+    fn test_safe_json_contains_hash() {
+        let transaction = SafeTransaction::new(address(1), U256::from(1), address(2), vec![0x01], U256::from(7));
+        let json = transaction.to_json();
+        assert_eq!(json["nonce"], "7");
+        assert_eq!(json["data"], "0x01");
+        assert_eq!(json["contractTransactionHash"], format!("{:?}", transaction.safe_tx_hash()));
+    }
+}
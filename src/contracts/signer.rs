@@ -0,0 +1,421 @@
+use crate::contracts::gas::TransactionType;
+use crate::contracts::keystore::decrypt_keystore;
+use crate::contracts::mnemonic::derive_private_key;
+use crate::framework::logging::log_error;
+use rlp::RlpStream;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, PublicKey, Secp256k1, SecretKey};
+use web3::signing::keccak256;
+use web3::types::{AccessList, Address, Bytes, H256, U256};
+use std::fmt;
+
+/// Errors that can occur while loading keys or signing.
+#[derive(Debug)]
+pub enum SignerError {
+    InvalidKey,
+    InvalidKeystore,
+    WrongPassword,
+    InvalidMnemonic,
+    InvalidDerivationPath,
+    SigningFailed,
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerError::InvalidKey => write!(f, "invalid private key"),
+            SignerError::InvalidKeystore => write!(f, "invalid keystore"),
+            SignerError::WrongPassword => write!(f, "wrong keystore password"),
+            SignerError::InvalidMnemonic => write!(f, "invalid mnemonic"),
+            SignerError::InvalidDerivationPath => write!(f, "invalid derivation path"),
+            SignerError::SigningFailed => write!(f, "signing failed"),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {}
+
+/// A secp256k1 signature with its recovery value.
+///
+/// `v` is `0`/`1` for typed transactions, `27`/`28` for messages and
+/// `chain_id * 2 + 35`/`36` for EIP-155 legacy transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub v: u64,
+    pub r: H256,
+    pub s: H256,
+}
+
+impl Signature {
+    /// Returns the 65-byte `r || s || v` encoding used for message signatures.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(65);
+        bytes.extend_from_slice(self.r.as_bytes());
+        bytes.extend_from_slice(self.s.as_bytes());
+        bytes.push(self.v as u8);
+        bytes
+    }
+}
+
+/// A transaction with every field needed for signing, independent of any node.
+///
+/// Legacy and EIP-2930 transactions use `gas_price`; EIP-1559 transactions use
+/// `max_fee_per_gas` and `max_priority_fee_per_gas`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnsignedTransaction {
+    pub transaction_type: TransactionType,
+    pub chain_id: u64,
+    pub nonce: U256,
+    pub to: Option<Address>,
+    pub value: U256,
+    pub data: Vec<u8>,
+    pub gas: U256,
+    pub gas_price: U256,
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    pub access_list: AccessList,
+}
+
+impl UnsignedTransaction {
+    /// Returns the RLP payload that is hashed for signing.
+    pub fn encode_unsigned(&self) -> Vec<u8> {
+        self.encode(None)
+    }
+
+    /// Returns the hash the sender signs.
+    pub fn signing_hash(&self) -> H256 {
+        H256(keccak256(&self.encode_unsigned()))
+    }
+
+    /// Returns the raw signed transaction accepted by `eth_sendRawTransaction`.
+    pub fn encode_signed(&self, signature: &Signature) -> Vec<u8> {
+        self.encode(Some(signature))
+    }
+
+    fn encode(&self, signature: Option<&Signature>) -> Vec<u8> {
+        let mut stream = RlpStream::new();
+        match self.transaction_type {
+            TransactionType::Legacy => {
+                stream.begin_list(9);
+                self.append_legacy_fields(&mut stream);
+                match signature {
+                    Some(signature) => append_signature(&mut stream, signature),
+                    None => {
+                        // EIP-155: the chain id takes the place of `v`, with empty `r` and `s`.
+                        stream.append(&self.chain_id);
+                        stream.append(&0u8);
+                        stream.append(&0u8);
+                    }
+                }
+                stream.out().to_vec()
+            }
+            TransactionType::AccessList => {
+                stream.begin_list(if signature.is_some() { 11 } else { 8 });
+                stream.append(&self.chain_id);
+                self.append_legacy_fields(&mut stream);
+                self.append_access_list(&mut stream);
+                if let Some(signature) = signature {
+                    append_signature(&mut stream, signature);
+                }
+                [&[0x01], stream.as_raw()].concat()
+            }
+            TransactionType::Eip1559 => {
+                stream.begin_list(if signature.is_some() { 12 } else { 9 });
+                stream.append(&self.chain_id);
+                stream.append(&self.nonce);
+                stream.append(&self.max_priority_fee_per_gas);
+                stream.append(&self.max_fee_per_gas);
+                stream.append(&self.gas);
+                self.append_to(&mut stream);
+                stream.append(&self.value);
+                stream.append(&self.data);
+                self.append_access_list(&mut stream);
+                if let Some(signature) = signature {
+                    append_signature(&mut stream, signature);
+                }
+                [&[0x02], stream.as_raw()].concat()
+            }
+        }
+    }
+
+    fn append_legacy_fields(&self, stream: &mut RlpStream) {
+        stream.append(&self.nonce);
+        stream.append(&self.gas_price);
+        stream.append(&self.gas);
+        self.append_to(stream);
+        stream.append(&self.value);
+        stream.append(&self.data);
+    }
+
+    fn append_to(&self, stream: &mut RlpStream) {
+        match self.to {
+            Some(to) => stream.append(&to),
+            None => stream.append(&""),
+        };
+    }
+
+    fn append_access_list(&self, stream: &mut RlpStream) {
+        stream.begin_list(self.access_list.len());
+        for item in &self.access_list {
+            stream.begin_list(2);
+            stream.append(&item.address);
+            stream.begin_list(item.storage_keys.len());
+            for key in &item.storage_keys {
+                stream.append(key);
+            }
+        }
+    }
+}
+
+fn append_signature(stream: &mut RlpStream, signature: &Signature) {
+    stream.append(&signature.v);
+    stream.append(&U256::from_big_endian(signature.r.as_bytes()));
+    stream.append(&U256::from_big_endian(signature.s.as_bytes()));
+}
+
+/// A transaction signed by a [`Signer`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignedTransaction {
+    pub raw_transaction: Bytes,
+    pub transaction_hash: H256,
+    pub signature: Signature,
+}
+
+/// Something that can sign transactions and messages on behalf of an address.
+pub trait Signer {
+    /// Returns the address of the signing account.
+    fn address(&self) -> Address;
+
+    /// Signs a 32-byte hash, returning `v` as the recovery id (`0` or `1`).
+    fn sign_hash(&self, hash: H256) -> Result<Signature, SignerError>;
+
+    /// Signs a transaction, applying EIP-155 replay protection to legacy transactions.
+    fn sign_transaction(&self, transaction: &UnsignedTransaction) -> Result<SignedTransaction, SignerError> {
+        let mut signature = self.sign_hash(transaction.signing_hash())?;
+        if transaction.transaction_type == TransactionType::Legacy {
+            signature.v += 35 + transaction.chain_id * 2;
+        }
+
+        let raw = transaction.encode_signed(&signature);
+        Ok(SignedTransaction {
+            transaction_hash: H256(keccak256(&raw)),
+            raw_transaction: Bytes(raw),
+            signature,
+        })
+    }
+
+    /// Signs a message with the EIP-191 `personal_sign` prefix, returning `v` as `27` or `28`.
+    fn sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let mut signature = self.sign_hash(hash_message(message))?;
+        signature.v += 27;
+        Ok(signature)
+    }
+}
+
+/// Returns the EIP-191 hash of a message: `keccak256("\x19Ethereum Signed Message:\n" + len + message)`.
+pub fn hash_message(message: &[u8]) -> H256 {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    H256(keccak256(&prefixed))
+}
+
+/// Recovers the address that produced a signature over a hash.
+pub fn recover_address(hash: H256, signature: &Signature) -> Result<Address, SignerError> {
+    let recovery_id = match signature.v {
+        0 | 1 => signature.v,
+        27 | 28 => signature.v - 27,
+        v => v.checked_sub(35).map(|v| v % 2).ok_or(SignerError::SigningFailed)?,
+    };
+    let mut compact = [0u8; 64];
+    compact[..32].copy_from_slice(signature.r.as_bytes());
+    compact[32..].copy_from_slice(signature.s.as_bytes());
+
+    let recovery_id = RecoveryId::from_i32(recovery_id as i32).map_err(|_| SignerError::SigningFailed)?;
+    let recoverable = RecoverableSignature::from_compact(&compact, recovery_id).map_err(|_| SignerError::SigningFailed)?;
+    let message = Message::from_slice(hash.as_bytes()).map_err(|_| SignerError::SigningFailed)?;
+    let public_key = Secp256k1::verification_only()
+        .recover_ecdsa(&message, &recoverable)
+        .map_err(|_| SignerError::SigningFailed)?;
+    Ok(public_key_address(&public_key))
+}
+
+fn public_key_address(public_key: &PublicKey) -> Address {
+    let hash = keccak256(&public_key.serialize_uncompressed()[1..]);
+    Address::from_slice(&hash[12..])
+}
+
+/// A signer holding a raw secp256k1 private key in memory.
+#[derive(Clone)]
+pub struct PrivateKeySigner {
+    key: SecretKey,
+    address: Address,
+}
+
+impl std::fmt::Debug for PrivateKeySigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the key itself.
+        f.debug_struct("PrivateKeySigner").field("address", &self.address).finish()
+    }
+}
+
+impl PrivateKeySigner {
+    /// Creates a signer from a 32-byte private key.
+    pub fn from_bytes(key: &[u8]) -> Result<Self, SignerError> {
+        let key = SecretKey::from_slice(key).map_err(|_| SignerError::InvalidKey)?;
+        Ok(Self::from_secret_key(key))
+    }
+
+    /// Creates a signer from a hex private key, with or without a `0x` prefix.
+    pub fn from_hex(key: &str) -> Result<Self, SignerError> {
+        let key = key.trim_start_matches("0x");
+        if key.len() != 64 {
+            return Err(SignerError::InvalidKey);
+        }
+        let bytes = (0..64)
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&key[i..i + 2], 16).map_err(|_| SignerError::InvalidKey))
+            .collect::<Result<Vec<u8>, _>>()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Creates a signer from an encrypted Ethereum keystore v3 JSON file.
+    pub fn from_keystore(keystore_json: &str, password: &str) -> Result<Self, SignerError> {
+        use crate::contracts::keystore::KeystoreError;
+
+        let key = decrypt_keystore(keystore_json, password).map_err(|e| match e {
+            KeystoreError::WrongPassword => SignerError::WrongPassword,
+            _ => SignerError::InvalidKeystore,
+        })?;
+        Self::from_bytes(&key)
+    }
+
+    /// Creates a signer from a BIP-39 mnemonic and a BIP-32/44 derivation path.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str, path: &str) -> Result<Self, SignerError> {
+        use crate::contracts::mnemonic::MnemonicError;
+
+        let key = derive_private_key(phrase, passphrase, path).map_err(|e| {
+            log_error(&format!("Failed to derive key at {}: {:?}", path, e));
+            match e {
+                MnemonicError::InvalidDerivationPath => SignerError::InvalidDerivationPath,
+                _ => SignerError::InvalidMnemonic,
+            }
+        })?;
+        Ok(Self::from_secret_key(key))
+    }
+
+    fn from_secret_key(key: SecretKey) -> Self {
+        let public_key = PublicKey::from_secret_key(&Secp256k1::signing_only(), &key);
+        Self { key, address: public_key_address(&public_key) }
+    }
+}
+
+impl Signer for PrivateKeySigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    fn sign_hash(&self, hash: H256) -> Result<Signature, SignerError> {
+        let message = Message::from_slice(hash.as_bytes()).map_err(|_| SignerError::SigningFailed)?;
+        let (recovery_id, compact) = Secp256k1::signing_only()
+            .sign_ecdsa_recoverable(&message, &self.key)
+            .serialize_compact();
+
+        Ok(Signature {
+            v: recovery_id.to_i32() as u64,
+            r: H256::from_slice(&compact[..32]),
+            s: H256::from_slice(&compact[32..]),
+        })
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contracts::abi::to_hex;
+    use std::str::FromStr;
+
+    fn eip155_transaction() -> UnsignedTransaction {
+        UnsignedTransaction {
+            transaction_type: TransactionType::Legacy,
+            chain_id: 1,
+            nonce: U256::from(9),
+            to: Some(Address::repeat_byte(0x35)),
+            value: U256::from(1_000_000_000_000_000_000u64),
+            data: vec![],
+            gas: U256::from(21_000),
+            gas_price: U256::from(20_000_000_000u64),
+            max_fee_per_gas: U256::zero(),
+            max_priority_fee_per_gas: U256::zero(),
+            access_list: vec![],
+        }
+    }
+
+    #[test]
+    fn test_eip155_example() {
+        // The example transaction from EIP-155.
+        let signer = PrivateKeySigner::from_bytes(&[0x46; 32]).unwrap();
+        let transaction = eip155_transaction();
+
+        assert_eq!(
+            to_hex(&transaction.encode_unsigned()),
+            "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080"
+        );
+        assert_eq!(
+            format!("{:?}", transaction.signing_hash()),
+            "0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"
+        );
+
+        let signed = signer.sign_transaction(&transaction).unwrap();
+        assert_eq!(signed.signature.v, 37);
+        assert_eq!(
+            to_hex(&signed.raw_transaction.0),
+            "f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        );
+    }
+
+    #[test]
+    fn test_typed_transaction_recovers_sender() {
+        let signer = PrivateKeySigner::from_hex("0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80").unwrap();
+        assert_eq!(signer.address(), Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap());
+
+        let mut transaction = eip155_transaction();
+        transaction.transaction_type = TransactionType::Eip1559;
+        transaction.max_fee_per_gas = U256::from(30_000_000_000u64);
+        transaction.max_priority_fee_per_gas = U256::from(1_000_000_000u64);
+
+        let signed = signer.sign_transaction(&transaction).unwrap();
+        assert_eq!(signed.raw_transaction.0[0], 0x02);
+        assert!(signed.signature.v <= 1);
+        assert_eq!(recover_address(transaction.signing_hash(), &signed.signature).unwrap(), signer.address());
+    }
+
+    #[test]
+    fn test_sign_message() {
+        let signer = PrivateKeySigner::from_bytes(&[0x46; 32]).unwrap();
+        let signature = signer.sign_message(b"hello").unwrap();
+        assert!(signature.v == 27 || signature.v == 28);
+        assert_eq!(signature.to_bytes().len(), 65);
+        assert_eq!(recover_address(hash_message(b"hello"), &signature).unwrap(), signer.address());
+
+        let invalid = Signature { v: 5, ..signature };
+        assert!(matches!(recover_address(hash_message(b"hello"), &invalid), Err(SignerError::SigningFailed)));
+    }
+
+    #[test]
+    fn test_mnemonic_signer() {
+        let signer = PrivateKeySigner::from_mnemonic(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "",
+            crate::contracts::mnemonic::DEFAULT_DERIVATION_PATH,
+        )
+        .unwrap();
+        assert_eq!(signer.address(), Address::from_str("0x9858EfFD232B4033E47d90003D41EC34EcaEda94").unwrap());
+    }
+
+    #[test]
+    fn test_invalid_key() {
+        assert!(matches!(PrivateKeySigner::from_hex("0x1234"), Err(SignerError::InvalidKey)));
+        assert!(matches!(PrivateKeySigner::from_bytes(&[0; 32]), Err(SignerError::InvalidKey)));
+    }
+}
@@ -0,0 +1,332 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use web3::ethabi::{encode, Token};
+use web3::signing::keccak256;
+use web3::types::{Address, H256, U256};
+
+/// A position in contract storage: the 32-byte slot and the byte offset of a packed value
+/// within it, counted from the least significant byte as solc does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageLocation {
+    pub slot: U256,
+    pub offset: usize,
+}
+
+impl StorageLocation {
+    /// A value occupying a slot on its own.
+    pub fn slot(slot: U256) -> Self {
+        Self { slot, offset: 0 }
+    }
+
+    /// Returns the slot as the key passed to `eth_getStorageAt`.
+    pub fn key(&self) -> H256 {
+        u256_to_h256(self.slot)
+    }
+}
+
+/// One step into a nested storage value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageKey {
+    /// A key into a mapping.
+    Key(Token),
+    /// An index into a static or dynamic array.
+    Index(U256),
+    /// A member of a struct.
+    Member(String),
+}
+
+/// Returns the slot holding the value of a mapping at `base_slot` for `key`.
+///
+/// Value-type keys are padded to 32 bytes; `string` and `bytes` keys are hashed unpadded.
+pub fn mapping_slot(base_slot: U256, key: &Token) -> U256 {
+    let mut preimage = match key {
+        Token::String(value) => value.as_bytes().to_vec(),
+        Token::Bytes(value) => value.clone(),
+        _ => encode(std::slice::from_ref(key)),
+    };
+    preimage.extend_from_slice(u256_to_h256(base_slot).as_bytes());
+    U256::from_big_endian(&keccak256(&preimage))
+}
+
+/// Returns the slot where the elements of a dynamic array at `base_slot` start.
+pub fn dynamic_array_data_slot(base_slot: U256) -> U256 {
+    U256::from_big_endian(&keccak256(u256_to_h256(base_slot).as_bytes()))
+}
+
+/// Returns the location of element `index` of an array whose elements start at `data_slot`.
+///
+/// Elements of 16 bytes or less are packed several to a slot; larger elements take
+/// `ceil(element_size / 32)` slots each. `element_size` must be non-zero.
+pub fn array_element_location(data_slot: U256, index: U256, element_size: usize) -> StorageLocation {
+    if element_size <= 16 {
+        let per_slot = U256::from(32 / element_size);
+        StorageLocation {
+            slot: data_slot + index / per_slot,
+            offset: (index % per_slot).as_usize() * element_size,
+        }
+    } else {
+        let slots_per_element = U256::from(element_size.div_ceil(32));
+        StorageLocation::slot(data_slot + index * slots_per_element)
+    }
+}
+
+/// Extracts a packed value of `size` bytes at `offset` from a storage word.
+pub fn extract_packed(word: H256, offset: usize, size: usize) -> Vec<u8> {
+    let end = 32 - offset;
+    word.as_bytes()[end - size..end].to_vec()
+}
+
+fn u256_to_h256(value: U256) -> H256 {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    H256(bytes)
+}
+
+/// A state variable entry of a solc `storageLayout` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageEntry {
+    pub label: String,
+    pub offset: usize,
+    pub slot: String,
+    #[serde(rename = "type")]
+    pub type_id: String,
+}
+
+/// A type entry of a solc `storageLayout` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageType {
+    pub encoding: String,
+    pub label: String,
+    #[serde(rename = "numberOfBytes")]
+    pub number_of_bytes: String,
+    pub key: Option<String>,
+    pub value: Option<String>,
+    pub base: Option<String>,
+    pub members: Option<Vec<StorageEntry>>,
+}
+
+/// The storage layout solc emits with `--storage-layout`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StorageLayout {
+    pub storage: Vec<StorageEntry>,
+    #[serde(default)]
+    pub types: HashMap<String, StorageType>,
+}
+
+impl StorageLayout {
+    /// Parses the `storageLayout` JSON of a contract, rejecting types with an invalid size.
+    pub fn parse(layout_json: &str) -> Result<Self, String> {
+        let layout: Self =
+            serde_json::from_str(layout_json).map_err(|e| format!("Failed to parse storage layout: {}", e))?;
+        for type_id in layout.types.keys() {
+            layout.size_of(type_id)?;
+        }
+        Ok(layout)
+    }
+
+    /// Resolves a state variable, optionally followed by mapping keys, array indexes and
+    /// struct members, to its storage location and type id.
+    pub fn resolve(&self, label: &str, path: &[StorageKey]) -> Result<(StorageLocation, String), String> {
+        let entry = self
+            .storage
+            .iter()
+            .find(|entry| entry.label == label)
+            .ok_or_else(|| format!("No state variable named '{}'.", label))?;
+
+        let mut location = StorageLocation { slot: parse_slot(&entry.slot)?, offset: entry.offset };
+        let mut type_id = entry.type_id.clone();
+
+        for step in path {
+            let storage_type = self.storage_type(&type_id)?;
+            match (storage_type.encoding.as_str(), step) {
+                ("mapping", StorageKey::Key(key)) => {
+                    location = StorageLocation::slot(mapping_slot(location.slot, key));
+                    type_id = storage_type.value.clone().ok_or("Mapping type without a value.")?;
+                }
+                ("dynamic_array", StorageKey::Index(index)) => {
+                    let base = storage_type.base.clone().ok_or("Array type without a base.")?;
+                    let element_size = self.size_of(&base)?;
+                    location = array_element_location(dynamic_array_data_slot(location.slot), *index, element_size);
+                    type_id = base;
+                }
+                ("inplace", StorageKey::Index(index)) if storage_type.base.is_some() => {
+                    let base = storage_type.base.clone().unwrap_or_default();
+                    let element_size = self.size_of(&base)?;
+                    location = array_element_location(location.slot, *index, element_size);
+                    type_id = base;
+                }
+                ("inplace", StorageKey::Member(name)) if storage_type.members.is_some() => {
+                    let member = storage_type
+                        .members
+                        .iter()
+                        .flatten()
+                        .find(|member| &member.label == name)
+                        .ok_or_else(|| format!("No member '{}' in {}.", name, storage_type.label))?;
+                    location = StorageLocation {
+                        slot: location.slot + parse_slot(&member.slot)?,
+                        offset: member.offset,
+                    };
+                    type_id = member.type_id.clone();
+                }
+                _ => return Err(format!("Cannot apply {:?} to {}.", step, storage_type.label)),
+            }
+        }
+
+        Ok((location, type_id))
+    }
+
+    /// Decodes the value of a value type from the storage word at its location.
+    pub fn decode(&self, type_id: &str, location: &StorageLocation, word: H256) -> Result<Token, String> {
+        let storage_type = self.storage_type(type_id)?;
+        let size = self.size_of(type_id)?;
+        if storage_type.encoding != "inplace" || size > 32 || location.offset + size > 32 {
+            return Err(format!("Cannot decode {} from a single slot.", storage_type.label));
+        }
+
+        let bytes = extract_packed(word, location.offset, size);
+        let label = storage_type.label.as_str();
+        let token = if label == "bool" {
+            Token::Bool(bytes.iter().any(|b| *b != 0))
+        } else if label == "address" || label == "address payable" || label.starts_with("contract ") {
+            Token::Address(Address::from_slice(&bytes))
+        } else if label.starts_with("uint") || label.starts_with("enum ") {
+            Token::Uint(U256::from_big_endian(&bytes))
+        } else if label.starts_with("int") {
+            // Sign-extend the packed value to 256 bits.
+            let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0x00 };
+            let mut extended = vec![fill; 32 - size];
+            extended.extend_from_slice(&bytes);
+            Token::Int(U256::from_big_endian(&extended))
+        } else if label.starts_with("bytes") {
+            Token::FixedBytes(bytes)
+        } else {
+            return Err(format!("Unsupported storage type {}.", label));
+        };
+        Ok(token)
+    }
+
+    fn storage_type(&self, type_id: &str) -> Result<&StorageType, String> {
+        self.types.get(type_id).ok_or_else(|| format!("Unknown storage type '{}'.", type_id))
+    }
+
+    /// Returns the size of a type in bytes; value types must fit in a single slot.
+    fn size_of(&self, type_id: &str) -> Result<usize, String> {
+        let storage_type = self.storage_type(type_id)?;
+        let size: usize = storage_type
+            .number_of_bytes
+            .parse()
+            .map_err(|_| format!("Invalid size for {}.", storage_type.label))?;
+        let value_type =
+            storage_type.encoding == "inplace" && storage_type.base.is_none() && storage_type.members.is_none();
+        if size == 0 || (value_type && size > 32) {
+            return Err(format!("Invalid size {} for {}.", size, storage_type.label));
+        }
+        Ok(size)
+    }
+}
+
+fn parse_slot(slot: &str) -> Result<U256, String> {
+    U256::from_dec_str(slot).map_err(|_| format!("Invalid storage slot '{}'.", slot))
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: &str = r#"
+    {
+        "storage": [
+            {"label": "owner", "offset": 0, "slot": "0", "type": "t_address"},
+            {"label": "paused", "offset": 20, "slot": "0", "type": "t_bool"},
+            {"label": "balances", "offset": 0, "slot": "1", "type": "t_mapping(t_address,t_uint256)"},
+            {"label": "allowances", "offset": 0, "slot": "2", "type": "t_mapping(t_address,t_mapping(t_address,t_uint256))"},
+            {"label": "checkpoints", "offset": 0, "slot": "3", "type": "t_array(t_uint64)dyn_storage"},
+            {"label": "config", "offset": 0, "slot": "4", "type": "t_struct(Config)"}
+        ],
+        "types": {
+            "t_address": {"encoding": "inplace", "label": "address", "numberOfBytes": "20"},
+            "t_bool": {"encoding": "inplace", "label": "bool", "numberOfBytes": "1"},
+            "t_uint64": {"encoding": "inplace", "label": "uint64", "numberOfBytes": "8"},
+            "t_int16": {"encoding": "inplace", "label": "int16", "numberOfBytes": "2"},
+            "t_uint256": {"encoding": "inplace", "label": "uint256", "numberOfBytes": "32"},
+            "t_mapping(t_address,t_uint256)": {"encoding": "mapping", "label": "mapping(address => uint256)", "numberOfBytes": "32", "key": "t_address", "value": "t_uint256"},
+            "t_mapping(t_address,t_mapping(t_address,t_uint256))": {"encoding": "mapping", "label": "mapping(address => mapping(address => uint256))", "numberOfBytes": "32", "key": "t_address", "value": "t_mapping(t_address,t_uint256)"},
+            "t_array(t_uint64)dyn_storage": {"encoding": "dynamic_array", "label": "uint64[]", "numberOfBytes": "32", "base": "t_uint64"},
+            "t_struct(Config)": {"encoding": "inplace", "label": "struct Config", "numberOfBytes": "64", "members": [
+                {"label": "fee", "offset": 0, "slot": "0", "type": "t_uint256"},
+                {"label": "delta", "offset": 0, "slot": "1", "type": "t_int16"}
+            ]}
+        }
+    }
+    "#;
+
+    #[test]
+    fn test_known_slots() {
+        assert_eq!(
+            format!("{:?}", u256_to_h256(dynamic_array_data_slot(U256::zero()))),
+            "0x290decd9548b62a8d60345a988386fc84ba6bc95484008f6362f93160ef3e563"
+        );
+        assert_eq!(
+            format!("{:?}", u256_to_h256(mapping_slot(U256::zero(), &Token::Uint(U256::zero())))),
+            "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5"
+        );
+    }
+
+    #[test]
+    fn test_resolve_nested_mapping() {
+        let layout = StorageLayout::parse(LAYOUT).unwrap();
+        let owner = Token::Address(Address::repeat_byte(1));
+        let spender = Token::Address(Address::repeat_byte(2));
+
+        let (location, type_id) = layout
+            .resolve("allowances", &[StorageKey::Key(owner.clone()), StorageKey::Key(spender.clone())])
+            .unwrap();
+        assert_eq!(location.slot, mapping_slot(mapping_slot(U256::from(2), &owner), &spender));
+        assert_eq!(type_id, "t_uint256");
+    }
+
+    #[test]
+    fn test_resolve_packed_array_and_struct() {
+        let layout = StorageLayout::parse(LAYOUT).unwrap();
+
+        let (location, _) = layout.resolve("checkpoints", &[StorageKey::Index(U256::from(5))]).unwrap();
+        assert_eq!(location.slot, dynamic_array_data_slot(U256::from(3)) + U256::one());
+        assert_eq!(location.offset, 8);
+
+        let (location, type_id) = layout.resolve("config", &[StorageKey::Member("delta".to_string())]).unwrap();
+        assert_eq!(location, StorageLocation::slot(U256::from(5)));
+        assert_eq!(type_id, "t_int16");
+
+        assert!(layout.resolve("owner", &[StorageKey::Index(U256::zero())]).is_err());
+        assert!(layout.resolve("missing", &[]).is_err());
+    }
+
+    #[test]
+    fn test_decode_packed_values() {
+        let layout = StorageLayout::parse(LAYOUT).unwrap();
+        let mut word = [0u8; 32];
+        word[11] = 0x01; // `paused` at offset 20
+        word[12..32].copy_from_slice(&[0xaa; 20]); // `owner` at offset 0
+        let word = H256(word);
+
+        let (owner, owner_type) = layout.resolve("owner", &[]).unwrap();
+        assert_eq!(layout.decode(&owner_type, &owner, word).unwrap(), Token::Address(Address::repeat_byte(0xaa)));
+
+        let (paused, paused_type) = layout.resolve("paused", &[]).unwrap();
+        assert_eq!(layout.decode(&paused_type, &paused, word).unwrap(), Token::Bool(true));
+
+        let negative = H256::from_low_u64_be(0xfffe);
+        let token = layout.decode("t_int16", &StorageLocation::slot(U256::zero()), negative).unwrap();
+        assert_eq!(token, Token::Int(U256::MAX - U256::one()));
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_sizes() {
+        let zero = r#"{"storage": [], "types": {"t_uint0": {"encoding": "inplace", "label": "uint0", "numberOfBytes": "0"}}}"#;
+        assert!(StorageLayout::parse(zero).is_err());
+
+        let wide = r#"{"storage": [], "types": {"t_uint512": {"encoding": "inplace", "label": "uint512", "numberOfBytes": "64"}}}"#;
+        assert!(StorageLayout::parse(wide).is_err());
+    }
+}
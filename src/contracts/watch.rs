@@ -1,7 +1,14 @@
-use crate::framework::logging::{log_info, log_error};
-use web3::types::Address;
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::framework::transport::NodeTransport;
+use futures::StreamExt;
+use tokio::time::sleep;
+use web3::signing::keccak256;
+use web3::types::{Address, BlockNumber, FilterBuilder, Log, H256, U256, U64};
+use web3::Web3;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::time::Duration;
+use std::fmt;
 
 /// Errors that can occur during contract watching.
 #[derive(Debug)]
@@ -10,6 +17,54 @@ pub enum WatchError {
     EventListeningFailed,
 }
 
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchError::InvalidAddress => write!(f, "invalid address"),
+            WatchError::EventListeningFailed => write!(f, "event listening failed"),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {}
+
+/// Tracks which logs were already delivered, so that logs fetched while catching up and logs
+/// pushed by a subscription are each delivered once.
+#[derive(Debug)]
+struct LogCursor {
+    /// Every log in an earlier block has been delivered.
+    next_block: U64,
+    /// Logs delivered from `next_block` onwards.
+    seen: HashSet<(U64, Option<H256>, Option<U256>)>,
+}
+
+impl LogCursor {
+    fn new(next_block: U64) -> Self {
+        Self { next_block, seen: HashSet::new() }
+    }
+
+    /// Returns `true` if the log has not been delivered yet and records it.
+    fn accept(&mut self, log: &Log) -> bool {
+        let block = match log.block_number {
+            Some(block) => block,
+            None => return true,
+        };
+        let key = (block, log.transaction_hash, log.log_index);
+        if log.removed == Some(true) {
+            // Reorged out: always deliver, and allow the log to be delivered again if re-mined.
+            self.seen.remove(&key);
+            return true;
+        }
+        block >= self.next_block && self.seen.insert(key)
+    }
+
+    /// Marks every block before `next_block` as delivered.
+    fn advance(&mut self, next_block: U64) {
+        self.next_block = next_block;
+        self.seen.retain(|(block, _, _)| *block >= next_block);
+    }
+}
+
 /// Watches for events from a smart contract with security checks and error handling.
 /// 
 /// # Arguments
@@ -43,12 +98,167 @@ pub async fn watch_contract_events(
     }
 }
 
+/// Delivers the logs of a contract as they are emitted, over any transport.
+///
+/// Over WebSocket and IPC the logs are pushed through an `eth_subscribe` subscription, which
+/// is re-established whenever it drops; over HTTP the node is polled with `eth_getLogs`. In
+/// both cases logs emitted while not subscribed are fetched, so none are missed or repeated.
+///
+/// # Arguments
+/// * `web3` - The connection to the node.
+/// * `contract_address` - The address of the contract.
+/// * `event_signature` - The event to watch, e.g. `Transfer(address,address,uint256)`; all events when `None`.
+/// * `poll_interval` - How often to poll over HTTP, and to retry a failed subscription.
+/// * `on_log` - Called with every log; watching stops when it returns `false`.
+///
+/// # Returns
+/// Result<(), WatchError> - Returns Ok once `on_log` stops the watch, otherwise returns an error.
+pub async fn watch_contract_logs<F>(
+    web3: &Web3<NodeTransport>,
+    contract_address: &str,
+    event_signature: Option<&str>,
+    poll_interval: Duration,
+    mut on_log: F,
+) -> Result<(), WatchError>
+where
+    F: FnMut(&Log) -> bool,
+{
+    let address = Address::from_str(contract_address).map_err(|_| WatchError::InvalidAddress)?;
+    let topic = event_signature.map(|signature| H256(keccak256(signature.as_bytes())));
+    let latest = web3.eth().block_number().await.map_err(|e| {
+        log_error(&format!("Failed to read the latest block: {}", e));
+        WatchError::EventListeningFailed
+    })?;
+    let mut cursor = LogCursor::new(latest + 1);
+
+    log_info(&format!("Watching logs of {} from block {}", contract_address, cursor.next_block));
+
+    loop {
+        if !web3.transport().supports_subscriptions() {
+            if !catch_up(web3, address, topic, &mut cursor, &mut on_log).await? {
+                return Ok(());
+            }
+            sleep(poll_interval).await;
+            continue;
+        }
+
+        let filter = FilterBuilder::default().address(vec![address]).topics(topic.map(|t| vec![t]), None, None, None).build();
+        let mut stream = match web3.eth_subscribe().subscribe_logs(filter).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                log_warn(&format!("Failed to subscribe to logs of {}: {}", contract_address, e));
+                sleep(poll_interval).await;
+                continue;
+            }
+        };
+        // Deliver what was emitted before the subscription started.
+        if !catch_up(web3, address, topic, &mut cursor, &mut on_log).await? {
+            return Ok(());
+        }
+
+        while let Some(log) = stream.next().await {
+            match log {
+                Ok(log) => {
+                    if cursor.accept(&log) && !on_log(&log) {
+                        return Ok(());
+                    }
+                }
+                Err(e) => {
+                    log_warn(&format!("Invalid log notification: {}", e));
+                    break;
+                }
+            }
+        }
+        log_warn(&format!("Log subscription of {} ended, resubscribing.", contract_address));
+    }
+}
+
+/// Delivers the logs from the cursor up to the latest block. Returns `false` if `on_log` stopped the watch.
+async fn catch_up<F>(
+    web3: &Web3<NodeTransport>,
+    address: Address,
+    topic: Option<H256>,
+    cursor: &mut LogCursor,
+    on_log: &mut F,
+) -> Result<bool, WatchError>
+where
+    F: FnMut(&Log) -> bool,
+{
+    let latest = match web3.eth().block_number().await {
+        Ok(latest) => latest,
+        Err(e) => {
+            // Transient while a connection is re-established; the next round retries.
+            log_warn(&format!("Failed to read the latest block: {}", e));
+            return Ok(true);
+        }
+    };
+    if latest < cursor.next_block {
+        return Ok(true);
+    }
+
+    let filter = FilterBuilder::default()
+        .address(vec![address])
+        .topics(topic.map(|t| vec![t]), None, None, None)
+        .from_block(BlockNumber::Number(cursor.next_block))
+        .to_block(BlockNumber::Number(latest))
+        .build();
+    let logs = web3.eth().logs(filter).await.map_err(|e| {
+        log_error(&format!("Failed to fetch logs: {}", e));
+        WatchError::EventListeningFailed
+    })?;
+
+    for log in &logs {
+        if cursor.accept(log) && !on_log(log) {
+            return Ok(false);
+        }
+    }
+    cursor.advance(latest + 1);
+    Ok(true)
+}
+
 // Unit test example
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::framework::mock::MockProvider;
+    use serde_json::json;
     use std::time::Duration;
 
+    fn log_at(block: u64, index: u64) -> Log {
+        Log {
+            address: Address::zero(),
+            topics: vec![],
+            data: Default::default(),
+            block_hash: None,
+            block_number: Some(U64::from(block)),
+            transaction_hash: Some(H256::repeat_byte(block as u8)),
+            transaction_index: None,
+            log_index: Some(U256::from(index)),
+            transaction_log_index: None,
+            log_type: None,
+            removed: None,
+        }
+    }
+
+    #[test]
+    fn test_log_cursor_delivers_once() {
+        let mut cursor = LogCursor::new(U64::from(10));
+        assert!(!cursor.accept(&log_at(9, 0)));
+        assert!(cursor.accept(&log_at(10, 0)));
+        assert!(!cursor.accept(&log_at(10, 0)));
+        assert!(cursor.accept(&log_at(10, 1)));
+
+        cursor.advance(U64::from(11));
+        assert!(!cursor.accept(&log_at(10, 2)));
+        assert!(cursor.accept(&log_at(11, 0)));
+        assert!(cursor.seen.len() == 1);
+
+        let mut removed = log_at(11, 0);
+        removed.removed = Some(true);
+        assert!(cursor.accept(&removed));
+        assert!(cursor.accept(&log_at(11, 0)));
+    }
+
     #[tokio::test]
     async fn test_invalid_contract_address() {
         let result = watch_contract_events("invalid", "TestEvent", Duration::from_secs(5)).await;
@@ -66,4 +276,35 @@ mod tests {
         let result = watch_contract_events("0x1234567890abcdef1234567890abcdef12345678", "MissingEvent", Duration::from_secs(5)).await;
         assert!(matches!(result, Err(WatchError::EventListeningFailed)));
     }
+
+    #[tokio::test]
+    async fn test_watch_logs_against_mock() {
+        let contract = "0x1234567890abcdef1234567890abcdef12345678";
+        let mock = MockProvider::new();
+        mock.on_request_once("eth_blockNumber", None, json!("0x10"));
+        mock.on_request("eth_blockNumber", None, json!("0x12"));
+        mock.on_request(
+            "eth_getLogs",
+            Some(json!([{"address": contract, "fromBlock": "0x11", "toBlock": "0x12"}])),
+            json!([{
+                "address": contract,
+                "topics": [format!("{:?}", H256(keccak256(b"Transfer(address,address,uint256)")))],
+                "data": "0x",
+                "blockNumber": "0x12",
+                "transactionHash": format!("{:?}", H256::repeat_byte(1)),
+                "logIndex": "0x0"
+            }]),
+        );
+
+        let web3 = NodeTransport::from_provider(mock).into_web3();
+        let mut logs = Vec::new();
+        let result = watch_contract_logs(&web3, contract, Some("Transfer(address,address,uint256)"), Duration::from_millis(1), |log| {
+            logs.push(log.clone());
+            false
+        })
+        .await;
+        assert!(matches!(result, Ok(())));
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].block_number, Some(U64::from(0x12)));
+    }
 }
@@ -0,0 +1,477 @@
+//! The error type shared by every module of the crate.
+//!
+//! Each module keeps its own error enum, and all of them convert into [`Error`]. Errors
+//! returned by the node are classified from their JSON-RPC code and message, so that callers
+//! can react to a nonce that is too low or a revert without matching on log text.
+
+use crate::contracts::bytecode::BytecodeError;
+use crate::contracts::contract_update::UpdateError;
+use crate::contracts::deploy::DeployError;
+use crate::contracts::gas::GasOptimizationError;
+use crate::contracts::interaction::InteractionError;
+use crate::contracts::keystore::KeystoreError;
+use crate::contracts::mnemonic::MnemonicError;
+use crate::contracts::monitor::MonitorError;
+use crate::contracts::network::NetworkError;
+use crate::contracts::nonce::NonceError;
+use crate::contracts::offline::OfflineError;
+use crate::contracts::pending::PendingTransactionError;
+use crate::contracts::profiler::ProfilerError;
+use crate::contracts::signer::SignerError;
+use crate::contracts::watch::WatchError;
+use crate::framework::mock::MockError;
+use crate::framework::provider::ProviderError;
+use crate::framework::rpc_batch::{BatchError, RpcError};
+use crate::framework::snapshot::SnapshotError;
+use crate::framework::transport::TransportError;
+use serde_json::Value;
+use web3::ethabi::{decode, ParamType, Token};
+use std::fmt;
+
+/// `bytes4(keccak256("Error(string)"))`, the selector of `require` and `revert` messages.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// `bytes4(keccak256("Panic(uint256)"))`, the selector of failed assertions and arithmetic errors.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Any error returned by the crate.
+///
+/// Node errors are classified into `NonceTooLow`, `InsufficientFunds`, `ExecutionReverted`
+/// and `ReplacementUnderpriced`; other JSON-RPC errors are kept as `Rpc`. Module errors that
+/// were caused by the node keep that error as their source, and [`Error::root`] returns it.
+#[derive(Debug)]
+pub enum Error {
+    /// The transaction nonce was already used by the sender.
+    NonceTooLow(RpcError),
+    /// The sender cannot pay for the gas and value of the transaction.
+    InsufficientFunds(RpcError),
+    /// The call or transaction reverted. `reason` is decoded from `Error(string)` or
+    /// `Panic(uint256)` revert data when present.
+    ExecutionReverted { reason: Option<String>, error: RpcError },
+    /// A transaction with the same nonce is pending and the replacement does not pay enough more.
+    ReplacementUnderpriced(RpcError),
+    /// Any other JSON-RPC error.
+    Rpc(RpcError),
+    /// The request did not get a JSON-RPC response, e.g. a connection or decoding failure.
+    Transport(web3::Error),
+    Deploy(DeployError),
+    Interaction(InteractionError),
+    Update(UpdateError),
+    Watch(WatchError),
+    Monitor(MonitorError),
+    Gas(GasOptimizationError),
+    Nonce(NonceError),
+    Provider(ProviderError),
+    Offline(OfflineError),
+    Signer(SignerError),
+    Keystore(KeystoreError),
+    Mnemonic(MnemonicError),
+    PendingTransaction(PendingTransactionError),
+    Network(NetworkError),
+    /// Connecting to the node through a [`NodeTransport`](crate::framework::transport::NodeTransport) failed.
+    Connection(TransportError),
+    Batch(BatchError),
+    Mock(MockError),
+    Bytecode(BytecodeError),
+    Profiler(ProfilerError),
+    Snapshot(SnapshotError),
+    /// A failed asynchronous operation.
+    Operation(String),
+}
+
+impl Error {
+    /// Classifies a JSON-RPC error returned by the node.
+    pub fn from_rpc(error: RpcError) -> Self {
+        let message = error.message.to_lowercase();
+        if message.contains("nonce too low") || message.contains("nonce has already been used") {
+            Error::NonceTooLow(error)
+        } else if message.contains("insufficient funds") {
+            Error::InsufficientFunds(error)
+        } else if message.contains("replacement transaction underpriced") || message.contains("replacement underpriced") {
+            Error::ReplacementUnderpriced(error)
+        } else if error.code == 3 || message.contains("reverted") {
+            Error::ExecutionReverted { reason: revert_reason(&error), error }
+        } else {
+            Error::Rpc(error)
+        }
+    }
+
+    /// Returns the error at the bottom of the chain of crate errors: for a deployment rejected
+    /// because the nonce was too low, this is [`Error::NonceTooLow`].
+    pub fn root(&self) -> &Error {
+        let cause = match self {
+            Error::Deploy(DeployError::Rejected(cause))
+            | Error::Interaction(InteractionError::Rejected(cause))
+            | Error::Update(UpdateError::Rejected(cause))
+            | Error::Nonce(NonceError::Rejected(cause))
+            | Error::Gas(GasOptimizationError::Rejected(cause))
+            | Error::Profiler(ProfilerError::Rejected(cause))
+            | Error::Deploy(DeployError::Gas(GasOptimizationError::Rejected(cause)))
+            | Error::Interaction(InteractionError::Gas(GasOptimizationError::Rejected(cause)))
+            | Error::Update(UpdateError::Gas(GasOptimizationError::Rejected(cause))) => cause,
+            Error::Deploy(DeployError::Offline(error))
+            | Error::Interaction(InteractionError::Offline(error))
+            | Error::Update(UpdateError::Offline(error))
+            | Error::Offline(error) => match error {
+                OfflineError::Gas(GasOptimizationError::Rejected(cause))
+                | OfflineError::Nonce(NonceError::Rejected(cause)) => cause,
+                _ => return self,
+            },
+            _ => return self,
+        };
+        cause.root()
+    }
+
+    /// Returns the JSON-RPC error returned by the node, if the chain ends in one.
+    pub fn rpc_error(&self) -> Option<&RpcError> {
+        match self.root() {
+            Error::NonceTooLow(error)
+            | Error::InsufficientFunds(error)
+            | Error::ExecutionReverted { error, .. }
+            | Error::ReplacementUnderpriced(error)
+            | Error::Rpc(error)
+            | Error::Provider(ProviderError::Rpc(error)) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NonceTooLow(error) => write!(f, "nonce too low: {}", error.message),
+            Error::InsufficientFunds(error) => write!(f, "insufficient funds: {}", error.message),
+            Error::ExecutionReverted { reason: Some(reason), .. } => write!(f, "execution reverted: {}", reason),
+            Error::ExecutionReverted { reason: None, .. } => write!(f, "execution reverted"),
+            Error::ReplacementUnderpriced(error) => write!(f, "replacement transaction underpriced: {}", error.message),
+            Error::Rpc(error) => write!(f, "{}", error),
+            Error::Transport(error) => write!(f, "transport error: {}", error),
+            Error::Deploy(error) => write!(f, "deployment error: {}", error),
+            Error::Interaction(error) => write!(f, "interaction error: {}", error),
+            Error::Update(error) => write!(f, "update error: {}", error),
+            Error::Watch(error) => write!(f, "watch error: {}", error),
+            Error::Monitor(error) => write!(f, "monitor error: {}", error),
+            Error::Gas(error) => write!(f, "gas error: {}", error),
+            Error::Nonce(error) => write!(f, "nonce error: {}", error),
+            Error::Provider(error) => write!(f, "provider error: {}", error),
+            Error::Offline(error) => write!(f, "offline transaction error: {}", error),
+            Error::Signer(error) => write!(f, "signer error: {}", error),
+            Error::Keystore(error) => write!(f, "keystore error: {}", error),
+            Error::Mnemonic(error) => write!(f, "mnemonic error: {}", error),
+            Error::PendingTransaction(error) => write!(f, "pending transaction error: {}", error),
+            Error::Network(error) => write!(f, "network error: {}", error),
+            Error::Connection(error) => write!(f, "connection error: {}", error),
+            Error::Batch(error) => write!(f, "batch error: {}", error),
+            Error::Mock(error) => write!(f, "mock provider error: {}", error),
+            Error::Bytecode(error) => write!(f, "bytecode error: {}", error),
+            Error::Profiler(error) => write!(f, "profiler error: {}", error),
+            Error::Snapshot(error) => write!(f, "snapshot error: {}", error),
+            Error::Operation(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::NonceTooLow(error)
+            | Error::InsufficientFunds(error)
+            | Error::ExecutionReverted { error, .. }
+            | Error::ReplacementUnderpriced(error) => Some(error),
+            Error::Rpc(_) | Error::Operation(_) => None,
+            Error::Transport(error) => Some(error),
+            Error::Deploy(error) => Some(error),
+            Error::Interaction(error) => Some(error),
+            Error::Update(error) => Some(error),
+            Error::Watch(error) => Some(error),
+            Error::Monitor(error) => Some(error),
+            Error::Gas(error) => Some(error),
+            Error::Nonce(error) => Some(error),
+            Error::Provider(error) => Some(error),
+            Error::Offline(error) => Some(error),
+            Error::Signer(error) => Some(error),
+            Error::Keystore(error) => Some(error),
+            Error::Mnemonic(error) => Some(error),
+            Error::PendingTransaction(error) => Some(error),
+            Error::Network(error) => Some(error),
+            Error::Connection(error) => Some(error),
+            Error::Batch(error) => Some(error),
+            Error::Mock(error) => Some(error),
+            Error::Bytecode(error) => Some(error),
+            Error::Profiler(error) => Some(error),
+            Error::Snapshot(error) => Some(error),
+        }
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(error: RpcError) -> Self {
+        Error::from_rpc(error)
+    }
+}
+
+impl From<web3::Error> for Error {
+    fn from(error: web3::Error) -> Self {
+        match error {
+            web3::Error::Rpc(error) => Error::from_rpc(RpcError {
+                code: error.code.code(),
+                message: error.message,
+                data: error.data,
+            }),
+            other => Error::Transport(other),
+        }
+    }
+}
+
+impl From<ProviderError> for Error {
+    fn from(error: ProviderError) -> Self {
+        match error {
+            ProviderError::Rpc(error) => Error::from_rpc(error),
+            other => Error::Provider(other),
+        }
+    }
+}
+
+impl From<DeployError> for Error {
+    fn from(error: DeployError) -> Self {
+        Error::Deploy(error)
+    }
+}
+
+impl From<InteractionError> for Error {
+    fn from(error: InteractionError) -> Self {
+        Error::Interaction(error)
+    }
+}
+
+impl From<UpdateError> for Error {
+    fn from(error: UpdateError) -> Self {
+        Error::Update(error)
+    }
+}
+
+impl From<WatchError> for Error {
+    fn from(error: WatchError) -> Self {
+        Error::Watch(error)
+    }
+}
+
+impl From<MonitorError> for Error {
+    fn from(error: MonitorError) -> Self {
+        Error::Monitor(error)
+    }
+}
+
+impl From<GasOptimizationError> for Error {
+    fn from(error: GasOptimizationError) -> Self {
+        Error::Gas(error)
+    }
+}
+
+impl From<NonceError> for Error {
+    fn from(error: NonceError) -> Self {
+        Error::Nonce(error)
+    }
+}
+
+impl From<OfflineError> for Error {
+    fn from(error: OfflineError) -> Self {
+        Error::Offline(error)
+    }
+}
+
+impl From<SignerError> for Error {
+    fn from(error: SignerError) -> Self {
+        Error::Signer(error)
+    }
+}
+
+impl From<KeystoreError> for Error {
+    fn from(error: KeystoreError) -> Self {
+        Error::Keystore(error)
+    }
+}
+
+impl From<MnemonicError> for Error {
+    fn from(error: MnemonicError) -> Self {
+        Error::Mnemonic(error)
+    }
+}
+
+impl From<PendingTransactionError> for Error {
+    fn from(error: PendingTransactionError) -> Self {
+        Error::PendingTransaction(error)
+    }
+}
+
+impl From<NetworkError> for Error {
+    fn from(error: NetworkError) -> Self {
+        Error::Network(error)
+    }
+}
+
+impl From<TransportError> for Error {
+    fn from(error: TransportError) -> Self {
+        Error::Connection(error)
+    }
+}
+
+impl From<BatchError> for Error {
+    fn from(error: BatchError) -> Self {
+        Error::Batch(error)
+    }
+}
+
+impl From<MockError> for Error {
+    fn from(error: MockError) -> Self {
+        Error::Mock(error)
+    }
+}
+
+impl From<BytecodeError> for Error {
+    fn from(error: BytecodeError) -> Self {
+        Error::Bytecode(error)
+    }
+}
+
+impl From<ProfilerError> for Error {
+    fn from(error: ProfilerError) -> Self {
+        Error::Profiler(error)
+    }
+}
+
+impl From<SnapshotError> for Error {
+    fn from(error: SnapshotError) -> Self {
+        Error::Snapshot(error)
+    }
+}
+
+/// Decodes the reason of a revert from the error data, falling back to the text after
+/// `execution reverted:` in the message.
+fn revert_reason(error: &RpcError) -> Option<String> {
+    let data = match &error.data {
+        Some(Value::String(data)) => Some(data.as_str()),
+        // Some nodes nest the revert data, e.g. `{"data": "0x08c379a0..."}`.
+        Some(Value::Object(object)) => object.get("data").and_then(Value::as_str),
+        _ => None,
+    };
+    if let Some(reason) = data.and_then(decode_revert_data) {
+        return Some(reason);
+    }
+    error
+        .message
+        .split_once("reverted:")
+        .map(|(_, reason)| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+}
+
+/// Decodes `Error(string)` and `Panic(uint256)` revert data.
+fn decode_revert_data(data: &str) -> Option<String> {
+    let bytes = hex_bytes(data.trim_start_matches("0x"))?;
+    if bytes.len() < 4 {
+        return None;
+    }
+    let (selector, payload) = bytes.split_at(4);
+    if selector == ERROR_SELECTOR {
+        match decode(&[ParamType::String], payload).ok()?.pop()? {
+            Token::String(reason) => Some(reason),
+            _ => None,
+        }
+    } else if selector == PANIC_SELECTOR {
+        match decode(&[ParamType::Uint(256)], payload).ok()?.pop()? {
+            Token::Uint(code) => Some(format!("panic code {:#x}", code)),
+            _ => None,
+        }
+    } else {
+        None
+    }
+}
+
+pub(crate) fn hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::error::Error as _;
+
+    fn rpc_error(code: i64, message: &str, data: Option<Value>) -> RpcError {
+        RpcError { code, message: message.to_string(), data }
+    }
+
+    #[test]
+    fn test_classify_rpc_errors() {
+        assert!(matches!(Error::from_rpc(rpc_error(-32000, "nonce too low", None)), Error::NonceTooLow(_)));
+        assert!(matches!(
+            Error::from_rpc(rpc_error(-32000, "insufficient funds for gas * price + value", None)),
+            Error::InsufficientFunds(_)
+        ));
+        assert!(matches!(
+            Error::from_rpc(rpc_error(-32000, "replacement transaction underpriced", None)),
+            Error::ReplacementUnderpriced(_)
+        ));
+        assert!(matches!(Error::from_rpc(rpc_error(-32601, "method not found", None)), Error::Rpc(_)));
+    }
+
+    #[test]
+    fn test_revert_reasons() {
+        // Error("Not owner")
+        let data = "0x08c379a0\
+            0000000000000000000000000000000000000000000000000000000000000020\
+            0000000000000000000000000000000000000000000000000000000000000009\
+            4e6f74206f776e65720000000000000000000000000000000000000000000000";
+        let error = Error::from_rpc(rpc_error(3, "execution reverted", Some(json!(data))));
+        assert!(matches!(&error, Error::ExecutionReverted { reason: Some(reason), .. } if reason == "Not owner"));
+        assert_eq!(error.to_string(), "execution reverted: Not owner");
+
+        // Panic(0x11), an arithmetic overflow.
+        let data = "0x4e487b710000000000000000000000000000000000000000000000000000000000000011";
+        let error = Error::from_rpc(rpc_error(3, "execution reverted", Some(json!({ "data": data }))));
+        assert!(matches!(error, Error::ExecutionReverted { reason: Some(reason), .. } if reason == "panic code 0x11"));
+
+        let error = Error::from_rpc(rpc_error(-32000, "execution reverted: paused", None));
+        assert!(matches!(error, Error::ExecutionReverted { reason: Some(reason), .. } if reason == "paused"));
+    }
+
+    #[test]
+    fn test_source_chain() {
+        let cause = Error::from(web3::Error::Rpc(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::ServerError(-32000),
+            message: "nonce too low".to_string(),
+            data: None,
+        }));
+        let error = Error::from(DeployError::Rejected(Box::new(Error::Nonce(NonceError::Rejected(Box::new(cause))))));
+
+        assert!(matches!(error.root(), Error::NonceTooLow(_)));
+        assert_eq!(error.rpc_error().unwrap().code, -32000);
+
+        let mut depth = 0;
+        let mut source = error.source();
+        while let Some(error) = source {
+            depth += 1;
+            source = error.source();
+        }
+        // DeployError -> Error::Nonce -> NonceError -> Error::NonceTooLow -> RpcError
+        assert_eq!(depth, 5);
+    }
+
+    #[test]
+    fn test_module_errors_convert() {
+        let error = Error::from(NetworkError::ChainIdMismatch { expected: 1, actual: 5 });
+        assert_eq!(error.to_string(), "network error: expected chain 1 but the node serves chain 5");
+        assert!(error.source().is_some());
+
+        assert!(matches!(Error::from(OfflineError::WrongNetwork), Error::Offline(_)));
+        assert!(matches!(Error::from(SignerError::InvalidKey), Error::Signer(_)));
+        assert!(matches!(Error::from(TransportError::ConnectionFailed), Error::Connection(_)));
+        assert!(matches!(Error::from(PendingTransactionError::Dropped), Error::PendingTransaction(_)));
+    }
+}
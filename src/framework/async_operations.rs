@@ -1,11 +1,12 @@
 use tokio::time::{sleep, Duration};
 use crate::framework::logging::{log_info, log_error};
+use crate::error::Error;
 
 /// Performs asynchronous operations with error handling and logging.
 /// 
 /// # Returns
-/// Result<(), String> - Returns Ok if operations succeed, otherwise returns an error message.
-pub async fn perform_optimized_operations() -> Result<(), String> {
+/// Result<(), Error> - Returns Ok if operations succeed, otherwise returns an error.
+pub async fn perform_optimized_operations() -> Result<(), Error> {
     log_info("Starting optimized asynchronous operations...");
 
     // Simulate an asynchronous operation (real logic would go here)
@@ -17,7 +18,7 @@ pub async fn perform_optimized_operations() -> Result<(), String> {
         Ok(())
     } else {
         log_error("Asynchronous operation failed.");
-        Err("Operation failed".into())
+        Err(Error::Operation("Operation failed".to_string()))
     }
 }
 
@@ -0,0 +1,365 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::framework::provider::{Provider, ProviderFuture};
+use crate::framework::rpc_batch::RpcError;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Mutex;
+use web3::types::Address;
+use std::fmt;
+
+/// The error code returned for requests that no expectation matches.
+const NO_MOCK_RESPONSE: i64 = -32000;
+
+/// Errors that can occur while reading or writing fixtures.
+#[derive(Debug)]
+pub enum MockError {
+    FixtureReadFailed,
+    FixtureWriteFailed,
+    InvalidFixture,
+}
+
+impl fmt::Display for MockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MockError::FixtureReadFailed => write!(f, "failed to read fixture"),
+            MockError::FixtureWriteFailed => write!(f, "failed to write fixture"),
+            MockError::InvalidFixture => write!(f, "invalid fixture"),
+        }
+    }
+}
+
+impl std::error::Error for MockError {}
+
+/// A JSON-RPC request and the node's answer, as stored in fixture files.
+///
+/// # Fields
+/// - `method`: The JSON-RPC method.
+/// - `params`: The request parameters.
+/// - `result`: The result, if the node answered successfully.
+/// - `error`: The JSON-RPC error object, if the node answered with an error.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    method: String,
+    /// Parameters the request must contain; any parameters match when `None`.
+    params: Option<Value>,
+    response: Result<Value, RpcError>,
+    /// How many more requests this expectation answers; unlimited when `None`.
+    remaining: Option<usize>,
+}
+
+/// A [`Provider`] that answers from programmed expectations or a recorded fixture, without a node.
+///
+/// Requests are matched against expectations in the order they were added. Parameters match
+/// when every value in the expectation is present in the request; strings are compared
+/// case-insensitively, so addresses and hex data match regardless of checksum casing.
+/// Requests without a matching expectation get a JSON-RPC error.
+#[derive(Debug, Default)]
+pub struct MockProvider {
+    expectations: Mutex<Vec<Expectation>>,
+    received: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockProvider {
+    /// Creates a provider without expectations.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a provider that replays recorded exchanges.
+    ///
+    /// Identical requests are answered in the order they were recorded; the last answer to a
+    /// request is repeated once the earlier ones are used up.
+    pub fn from_exchanges(exchanges: Vec<Exchange>) -> Self {
+        let mock = Self::new();
+        let count = exchanges.len();
+        for (index, exchange) in exchanges.iter().enumerate() {
+            let repeated_later = exchanges[index + 1..count]
+                .iter()
+                .any(|later| later.method == exchange.method && later.params == exchange.params);
+            let response = match &exchange.error {
+                Some(error) => Err(RpcError::from_json(error)),
+                None => Ok(exchange.result.clone().unwrap_or(Value::Null)),
+            };
+            mock.push(&exchange.method, Some(exchange.params.clone()), response, repeated_later.then_some(1));
+        }
+        mock
+    }
+
+    /// Creates a provider that replays a fixture file written by [`RecordingProvider::save`].
+    ///
+    /// # Returns
+    /// Result<MockProvider, MockError> - Returns the provider, otherwise returns an error.
+    pub fn from_fixture<P: AsRef<Path>>(path: P) -> Result<Self, MockError> {
+        let content = std::fs::read_to_string(path.as_ref()).map_err(|e| {
+            log_error(&format!("Failed to read fixture {}: {}", path.as_ref().display(), e));
+            MockError::FixtureReadFailed
+        })?;
+        let exchanges: Vec<Exchange> = serde_json::from_str(&content).map_err(|e| {
+            log_error(&format!("Invalid fixture {}: {}", path.as_ref().display(), e));
+            MockError::InvalidFixture
+        })?;
+        Ok(Self::from_exchanges(exchanges))
+    }
+
+    /// Answers every matching request with `result`.
+    ///
+    /// # Arguments
+    /// * `method` - The JSON-RPC method, e.g. `eth_getBalance`.
+    /// * `params` - Parameters the request must contain, e.g. `json!([address])`; any when `None`.
+    /// * `result` - The result to return.
+    pub fn on_request(&self, method: &str, params: Option<Value>, result: Value) {
+        self.push(method, params, Ok(result), None);
+    }
+
+    /// Answers the next matching request with `result`, then stops matching.
+    pub fn on_request_once(&self, method: &str, params: Option<Value>, result: Value) {
+        self.push(method, params, Ok(result), Some(1));
+    }
+
+    /// Answers every matching request with a JSON-RPC error.
+    pub fn on_error(&self, method: &str, params: Option<Value>, code: i64, message: &str) {
+        let error = RpcError { code, message: message.to_string(), data: None };
+        self.push(method, params, Err(error), None);
+    }
+
+    /// Answers every `eth_call` to `to` with calldata `data` with `output`.
+    pub fn on_call(&self, to: Address, data: &[u8], output: &[u8]) {
+        self.on_request(
+            "eth_call",
+            Some(json!([{"to": to, "data": hex_string(data)}])),
+            Value::String(hex_string(output)),
+        );
+    }
+
+    /// Returns the method and parameters of every request received so far, in order.
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.received.lock().unwrap().clone()
+    }
+
+    fn push(&self, method: &str, params: Option<Value>, response: Result<Value, RpcError>, remaining: Option<usize>) {
+        self.expectations.lock().unwrap().push(Expectation {
+            method: method.to_string(),
+            params,
+            response,
+            remaining,
+        });
+    }
+
+    /// Answers a single JSON-RPC request object.
+    fn respond(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let method = request.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+        self.received.lock().unwrap().push((method.to_string(), params.clone()));
+
+        let mut expectations = self.expectations.lock().unwrap();
+        let matching = expectations.iter_mut().find(|expectation| {
+            expectation.remaining != Some(0)
+                && expectation.method == method
+                && expectation.params.as_ref().is_none_or(|expected| json_contains(&params, expected))
+        });
+        match matching {
+            Some(expectation) => {
+                if let Some(remaining) = expectation.remaining.as_mut() {
+                    *remaining -= 1;
+                }
+                response_json(id, &expectation.response)
+            }
+            None => {
+                log_warn(&format!("No mock response for {} {}", method, params));
+                let error = RpcError { code: NO_MOCK_RESPONSE, message: format!("No mock response for {}", method), data: None };
+                response_json(id, &Err(error))
+            }
+        }
+    }
+}
+
+impl Provider for MockProvider {
+    fn send_raw(&self, payload: Value) -> ProviderFuture<'_, Value> {
+        Box::pin(async move {
+            Ok(match payload {
+                Value::Array(requests) => Value::Array(requests.iter().map(|request| self.respond(request)).collect()),
+                request => self.respond(&request),
+            })
+        })
+    }
+}
+
+/// A [`Provider`] that forwards requests to another provider and records every exchange,
+/// so that they can be saved as a fixture and replayed by [`MockProvider::from_fixture`].
+#[derive(Debug)]
+pub struct RecordingProvider<P> {
+    inner: P,
+    exchanges: Mutex<Vec<Exchange>>,
+}
+
+impl<P: Provider> RecordingProvider<P> {
+    /// Records the exchanges of `inner`, e.g. a `FailoverProvider` connected to a real node.
+    pub fn new(inner: P) -> Self {
+        Self { inner, exchanges: Mutex::new(Vec::new()) }
+    }
+
+    /// Returns the exchanges recorded so far, in order.
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.exchanges.lock().unwrap().clone()
+    }
+
+    /// Writes the recorded exchanges to a fixture file.
+    ///
+    /// # Returns
+    /// Result<(), MockError> - Returns Ok if the fixture was written, otherwise returns an error.
+    pub fn save<Q: AsRef<Path>>(&self, path: Q) -> Result<(), MockError> {
+        let content = serde_json::to_string_pretty(&self.exchanges()).map_err(|_| MockError::FixtureWriteFailed)?;
+        std::fs::write(path.as_ref(), content).map_err(|e| {
+            log_error(&format!("Failed to write fixture {}: {}", path.as_ref().display(), e));
+            MockError::FixtureWriteFailed
+        })?;
+        log_info(&format!("Saved {} exchange(s) to {}", self.exchanges.lock().unwrap().len(), path.as_ref().display()));
+        Ok(())
+    }
+
+    fn record(&self, request: &Value, response: &Value) {
+        let exchange = Exchange {
+            method: request.get("method").and_then(Value::as_str).unwrap_or_default().to_string(),
+            params: request.get("params").cloned().unwrap_or_else(|| json!([])),
+            result: response.get("result").cloned(),
+            error: response.get("error").cloned(),
+        };
+        self.exchanges.lock().unwrap().push(exchange);
+    }
+}
+
+impl<P: Provider> Provider for RecordingProvider<P> {
+    fn send_raw(&self, payload: Value) -> ProviderFuture<'_, Value> {
+        Box::pin(async move {
+            let response = self.inner.send_raw(payload.clone()).await?;
+            match (&payload, &response) {
+                (Value::Array(requests), Value::Array(responses)) => {
+                    // Batch responses may come back in any order.
+                    for request in requests {
+                        if let Some(matching) = responses.iter().find(|response| response.get("id") == request.get("id")) {
+                            self.record(request, matching);
+                        }
+                    }
+                }
+                (request, response) => self.record(request, response),
+            }
+            Ok(response)
+        })
+    }
+}
+
+fn response_json(id: Value, response: &Result<Value, RpcError>) -> Value {
+    match response {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": {"code": error.code, "message": error.message, "data": error.data},
+        }),
+    }
+}
+
+/// Returns `true` if every value in `expected` is present in `actual`.
+///
+/// Objects may have extra keys and arrays extra trailing elements; strings compare
+/// case-insensitively.
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|actual| json_contains(actual, value))),
+        (Value::Array(actual), Value::Array(expected)) => {
+            expected.len() <= actual.len() && expected.iter().zip(actual).all(|(expected, actual)| json_contains(actual, expected))
+        }
+        (Value::String(actual), Value::String(expected)) => actual.eq_ignore_ascii_case(expected),
+        _ => actual == expected,
+    }
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    format!("0x{}", crate::contracts::abi::to_hex(bytes))
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::provider::ProviderTransport;
+    use web3::types::{Bytes, CallRequest};
+    use web3::Web3;
+
+    #[test]
+    fn test_json_contains() {
+        let actual = json!([{"to": "0xABCD", "data": "0x12", "from": "0x01"}, "latest"]);
+        assert!(json_contains(&actual, &json!([{"to": "0xabcd"}])));
+        assert!(json_contains(&actual, &json!([{"to": "0xabcd"}, "latest"])));
+        assert!(!json_contains(&actual, &json!([{"to": "0xabce"}])));
+        assert!(!json_contains(&actual, &json!([{"value": "0x1"}])));
+    }
+
+    #[tokio::test]
+    async fn test_expectations() {
+        let mock = MockProvider::new();
+        let token = Address::repeat_byte(0xaa);
+        mock.on_call(token, &[0x18, 0x16, 0x0d, 0xdd], &[0x2a]);
+        mock.on_request_once("eth_blockNumber", None, json!("0x1"));
+        mock.on_request("eth_blockNumber", None, json!("0x2"));
+
+        let web3 = Web3::new(ProviderTransport::new(mock));
+        let request = CallRequest { to: Some(token), data: Some(Bytes(vec![0x18, 0x16, 0x0d, 0xdd])), ..Default::default() };
+        assert_eq!(web3.eth().call(request, None).await.unwrap(), Bytes(vec![0x2a]));
+        assert_eq!(web3.eth().block_number().await.unwrap().as_u64(), 1);
+        assert_eq!(web3.eth().block_number().await.unwrap().as_u64(), 2);
+        assert_eq!(web3.eth().block_number().await.unwrap().as_u64(), 2);
+
+        // Unmatched requests fail like a node error.
+        assert!(web3.eth().balance(token, None).await.is_err());
+        assert_eq!(web3.transport().provider().requests().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let node = MockProvider::new();
+        node.on_request_once("eth_getBalance", None, json!("0x64"));
+        node.on_request("eth_getBalance", None, json!("0xc8"));
+        node.on_error("eth_getCode", None, -32602, "invalid argument");
+
+        let recorder = RecordingProvider::new(node);
+        let address = json!("0x1111111111111111111111111111111111111111");
+        for _ in 0..2 {
+            recorder.request("eth_getBalance", vec![address.clone(), json!("latest")]).await.unwrap();
+        }
+        assert!(recorder.request("eth_getCode", vec![address.clone(), json!("latest")]).await.is_err());
+
+        let path = std::env::temp_dir().join(format!("wasmify-fixture-{}.json", std::process::id()));
+        recorder.save(&path).unwrap();
+        let replay = MockProvider::from_fixture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let params = vec![address.clone(), json!("latest")];
+        for expected in ["0x64", "0xc8", "0xc8"] {
+            assert_eq!(replay.request("eth_getBalance", params.clone()).await.unwrap(), json!(expected));
+        }
+        match replay.request("eth_getCode", vec![address.clone(), json!("latest")]).await {
+            Err(crate::framework::provider::ProviderError::Rpc(error)) => assert_eq!(error.code, -32602),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_missing_fixture() {
+        assert!(matches!(MockProvider::from_fixture("/nonexistent/fixture.json"), Err(MockError::FixtureReadFailed)));
+    }
+}
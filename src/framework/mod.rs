@@ -6,4 +6,9 @@ pub mod init;
 pub mod run;
 pub mod optimize;
 pub mod async_operations;
-pub mod logging;
\ No newline at end of file
+pub mod logging;
+pub mod rpc_batch;
+pub mod provider;
+pub mod transport;
+pub mod mock;
+pub mod snapshot;
\ No newline at end of file
@@ -1,4 +1,6 @@
 use crate::framework::logging::log_info;
+use crate::contracts::fees::FeeEstimates;
+use crate::contracts::gas_strategy::adjust_gas_limit;
 use web3::types::U256;
 
 /// Optimizes the gas usage for a smart contract based on historical gas prices.
@@ -6,10 +8,11 @@ use web3::types::U256;
 /// # Arguments
 /// * `historical_gas_prices` - A list of historical gas prices.
 /// * `current_gas_limit` - The current gas limit for the contract.
+/// * `fees` - The fees suggested by a [`FeeOracle`](crate::contracts::fees::FeeOracle); an average above the normal `maxFeePerGas` counts as high.
 ///
 /// # Returns
 /// U256 - The optimized gas limit.
-pub fn optimize_gas_usage(historical_gas_prices: Vec<U256>, current_gas_limit: U256) -> U256 {
+pub fn optimize_gas_usage(historical_gas_prices: Vec<U256>, current_gas_limit: U256, fees: &FeeEstimates) -> U256 {
     let average_gas_price = if !historical_gas_prices.is_empty() {
         let sum: U256 = historical_gas_prices.iter().sum();
         sum / U256::from(historical_gas_prices.len())
@@ -17,13 +20,7 @@ pub fn optimize_gas_usage(historical_gas_prices: Vec<U256>, current_gas_limit: U
         U256::from(0)
     };
 
-    let adjustment_factor = if average_gas_price > U256::from(100) {
-        U256::from(90) // Reduce gas usage if average gas price is high
-    } else {
-        U256::from(110) // Increase gas usage if average gas price is low
-    };
-
-    let optimized_gas_limit = current_gas_limit * adjustment_factor / U256::from(100);
+    let optimized_gas_limit = adjust_gas_limit(current_gas_limit, average_gas_price, fees.normal.max_fee_per_gas);
     log_info(&format!("Optimized gas limit: {:?}", optimized_gas_limit)); // Corrected log usage
 
     optimized_gas_limit
@@ -38,14 +35,14 @@ mod tests {
     #[test]
     fn test_gas_optimization_high_prices() {
         let historical_gas_prices = vec![U256::from(120), U256::from(130)];
-        let optimized_gas = optimize_gas_usage(historical_gas_prices, U256::from(10000));
+        let optimized_gas = optimize_gas_usage(historical_gas_prices, U256::from(10000), &FeeEstimates::from_gas_price(U256::from(100)));
         assert!(optimized_gas < U256::from(10000));
     }
 
     #[test]
     fn test_gas_optimization_low_prices() {
         let historical_gas_prices = vec![U256::from(80), U256::from(90)];
-        let optimized_gas = optimize_gas_usage(historical_gas_prices, U256::from(10000));
+        let optimized_gas = optimize_gas_usage(historical_gas_prices, U256::from(10000), &FeeEstimates::from_gas_price(U256::from(100)));
         assert!(optimized_gas > U256::from(10000));
     }
 }
@@ -0,0 +1,506 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::framework::rpc_batch::RpcError;
+use serde_json::{json, Value};
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::time::sleep;
+use web3::{BatchTransport, RequestId, Transport, Web3};
+use std::fmt;
+
+/// JSON-RPC error code used by most providers when a rate limit is exceeded.
+const LIMIT_EXCEEDED: i64 = -32005;
+
+/// Errors that can occur while sending a request through a provider.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderError {
+    NoEndpoints,
+    InvalidEndpoint,
+    AllEndpointsFailed,
+    InvalidResponse,
+    Rpc(RpcError),
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProviderError::NoEndpoints => write!(f, "no endpoints configured"),
+            ProviderError::InvalidEndpoint => write!(f, "invalid endpoint"),
+            ProviderError::AllEndpointsFailed => write!(f, "all endpoints failed"),
+            ProviderError::InvalidResponse => write!(f, "invalid JSON-RPC response"),
+            ProviderError::Rpc(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ProviderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProviderError::Rpc(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// The future returned by [`Provider`] methods.
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, ProviderError>> + Send + 'a>>;
+
+/// A source of JSON-RPC responses.
+///
+/// Implementations decide where a payload goes and how failures are handled; contract
+/// modules reach a provider through [`ProviderTransport`], which adapts it to `web3`.
+pub trait Provider: std::fmt::Debug + Send + Sync {
+    /// Sends a raw JSON-RPC payload, either a single request or a batch array, and returns
+    /// the raw response.
+    fn send_raw(&self, payload: Value) -> ProviderFuture<'_, Value>;
+
+    /// Sends a single request and returns its result.
+    ///
+    /// # Returns
+    /// Result<Value, ProviderError> - Returns the result, or [`ProviderError::Rpc`] if the node
+    /// answered with an error.
+    fn request<'a>(&'a self, method: &'a str, params: Vec<Value>) -> ProviderFuture<'a, Value> {
+        Box::pin(async move {
+            let response = self
+                .send_raw(json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+                .await?;
+            response_result(response)
+        })
+    }
+}
+
+impl<P: Provider + ?Sized> Provider for Box<P> {
+    fn send_raw(&self, payload: Value) -> ProviderFuture<'_, Value> {
+        (**self).send_raw(payload)
+    }
+}
+
+/// Retry, timeout and rate limit settings for a [`FailoverProvider`].
+///
+/// # Fields
+/// - `max_retries`: How many more rounds over all endpoints are tried after the first one fails.
+/// - `initial_backoff`: The pause after the first failed round; doubled after each further round.
+/// - `max_backoff`: The upper bound of the pause between rounds.
+/// - `request_timeout`: How long a single endpoint may take to answer.
+/// - `requests_per_second`: The rate limit applied to each endpoint, if any.
+/// - `unhealthy_cooldown`: How long a failed endpoint is tried only after the healthy ones.
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub request_timeout: Duration,
+    pub requests_per_second: Option<u32>,
+    pub unhealthy_cooldown: Duration,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(15),
+            requests_per_second: None,
+            unhealthy_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Health and rate limit bookkeeping for one endpoint.
+#[derive(Debug)]
+struct EndpointState {
+    /// The earliest time the next request may be sent.
+    next_request: Instant,
+    /// When the endpoint last failed, if it has not succeeded since.
+    failed_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    url: String,
+    state: Mutex<EndpointState>,
+}
+
+/// Why a single attempt against an endpoint failed.
+enum AttemptError {
+    /// Worth trying again, possibly on another endpoint.
+    Retryable(String),
+    /// The node answered and retrying will not change the answer.
+    Fatal(ProviderError),
+}
+
+/// A [`Provider`] over several HTTP JSON-RPC endpoints, in order of preference.
+///
+/// Each request goes to the first endpoint that is healthy. Timeouts, connection errors,
+/// HTTP 429/5xx responses and rate limit errors mark an endpoint unhealthy and fail over to
+/// the next one; once every endpoint has failed, the provider backs off exponentially with
+/// jitter and starts another round.
+#[derive(Debug)]
+pub struct FailoverProvider {
+    client: reqwest::Client,
+    endpoints: Vec<Endpoint>,
+    config: ProviderConfig,
+}
+
+impl FailoverProvider {
+    /// Creates a provider over one or more endpoints.
+    ///
+    /// # Arguments
+    /// * `urls` - The HTTP endpoints, most preferred first.
+    /// * `config` - Retry, timeout and rate limit settings.
+    ///
+    /// # Returns
+    /// Result<FailoverProvider, ProviderError> - Returns the provider, otherwise returns an error
+    /// if no endpoint was given or one is not a valid URL.
+    pub fn new(urls: &[&str], config: ProviderConfig) -> Result<Self, ProviderError> {
+        if urls.is_empty() {
+            return Err(ProviderError::NoEndpoints);
+        }
+        let now = Instant::now();
+        let endpoints = urls
+            .iter()
+            .map(|url| {
+                reqwest::Url::parse(url).map_err(|_| ProviderError::InvalidEndpoint)?;
+                Ok(Endpoint {
+                    url: url.to_string(),
+                    state: Mutex::new(EndpointState { next_request: now, failed_at: None }),
+                })
+            })
+            .collect::<Result<Vec<_>, ProviderError>>()?;
+
+        log_info(&format!("Using {} RPC endpoint(s), primary: {}", endpoints.len(), urls[0]));
+        Ok(Self { client: reqwest::Client::new(), endpoints, config })
+    }
+
+    /// Returns a `web3` client that sends every request through this provider.
+    pub fn into_web3(self) -> Web3<ProviderTransport<Self>> {
+        Web3::new(ProviderTransport::new(self))
+    }
+
+    /// Sends `eth_blockNumber` to every endpoint and records which ones answered.
+    ///
+    /// # Returns
+    /// Vec<bool> - Whether each endpoint is healthy, in the order they were given.
+    pub async fn check_health(&self) -> Vec<bool> {
+        let payload = json!({"jsonrpc": "2.0", "id": 0, "method": "eth_blockNumber", "params": []});
+        let mut health = Vec::with_capacity(self.endpoints.len());
+        for endpoint in &self.endpoints {
+            let healthy = match self.attempt(endpoint, &payload).await {
+                Ok(response) => response_result(response).is_ok(),
+                Err(_) => false,
+            };
+            self.record_outcome(endpoint, healthy);
+            health.push(healthy);
+        }
+        health
+    }
+
+    /// Runs [`FailoverProvider::check_health`] every `interval` until the returned task is aborted.
+    pub fn spawn_health_checks(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                let health = self.check_health().await;
+                if health.iter().any(|healthy| !healthy) {
+                    log_warn(&format!("RPC endpoint health: {:?}", health));
+                }
+                sleep(interval).await;
+            }
+        })
+    }
+
+    /// Returns the endpoints to try, healthy ones first, each group in order of preference.
+    fn endpoint_order(&self) -> Vec<&Endpoint> {
+        let now = Instant::now();
+        let (healthy, unhealthy): (Vec<&Endpoint>, Vec<&Endpoint>) = self.endpoints.iter().partition(|endpoint| {
+            match endpoint.state.lock().unwrap().failed_at {
+                Some(failed_at) => now.duration_since(failed_at) >= self.config.unhealthy_cooldown,
+                None => true,
+            }
+        });
+        healthy.into_iter().chain(unhealthy).collect()
+    }
+
+    fn record_outcome(&self, endpoint: &Endpoint, success: bool) {
+        let mut state = endpoint.state.lock().unwrap();
+        state.failed_at = if success { None } else { Some(Instant::now()) };
+    }
+
+    /// Reserves the next request slot of an endpoint under its rate limit and waits for it.
+    async fn wait_for_rate_limit(&self, endpoint: &Endpoint) {
+        let Some(requests_per_second) = self.config.requests_per_second.filter(|rps| *rps > 0) else {
+            return;
+        };
+        let slot = {
+            let mut state = endpoint.state.lock().unwrap();
+            let slot = state.next_request.max(Instant::now());
+            state.next_request = slot + Duration::from_secs(1) / requests_per_second;
+            slot
+        };
+        let now = Instant::now();
+        if slot > now {
+            sleep(slot - now).await;
+        }
+    }
+
+    /// Sends a payload to a single endpoint.
+    async fn attempt(&self, endpoint: &Endpoint, payload: &Value) -> Result<Value, AttemptError> {
+        self.wait_for_rate_limit(endpoint).await;
+
+        let request = self.client.post(&endpoint.url).json(payload).send();
+        let response = match tokio::time::timeout(self.config.request_timeout, request).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => return Err(AttemptError::Retryable(e.to_string())),
+            Err(_) => return Err(AttemptError::Retryable("request timed out".to_string())),
+        };
+
+        let status = response.status();
+        if status.as_u16() == 429 || status.is_server_error() {
+            return Err(AttemptError::Retryable(format!("HTTP {}", status)));
+        }
+        if !status.is_success() {
+            return Err(AttemptError::Fatal(ProviderError::InvalidResponse));
+        }
+
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| AttemptError::Retryable(format!("invalid response body: {}", e)))?;
+        if body.get("error").and_then(|error| error.get("code")).and_then(Value::as_i64) == Some(LIMIT_EXCEEDED) {
+            return Err(AttemptError::Retryable("rate limit exceeded".to_string()));
+        }
+        Ok(body)
+    }
+}
+
+impl Provider for FailoverProvider {
+    fn send_raw(&self, payload: Value) -> ProviderFuture<'_, Value> {
+        Box::pin(async move {
+            for round in 0..=self.config.max_retries {
+                if round > 0 {
+                    let pause = backoff(&self.config, round);
+                    log_warn(&format!("All RPC endpoints failed, retrying in {:?}", pause));
+                    sleep(pause).await;
+                }
+
+                for endpoint in self.endpoint_order() {
+                    match self.attempt(endpoint, &payload).await {
+                        Ok(response) => {
+                            self.record_outcome(endpoint, true);
+                            return Ok(response);
+                        }
+                        Err(AttemptError::Retryable(reason)) => {
+                            log_warn(&format!("RPC endpoint {} failed: {}", endpoint.url, reason));
+                            self.record_outcome(endpoint, false);
+                        }
+                        Err(AttemptError::Fatal(error)) => return Err(error),
+                    }
+                }
+            }
+
+            log_error(&format!("All RPC endpoints failed after {} retries.", self.config.max_retries));
+            Err(ProviderError::AllEndpointsFailed)
+        })
+    }
+}
+
+/// Adapts a [`Provider`] to a `web3` transport, so that `Web3<ProviderTransport<P>>` can be
+/// passed to every function of the contract modules.
+#[derive(Debug)]
+pub struct ProviderTransport<P> {
+    provider: Arc<P>,
+    next_id: Arc<AtomicUsize>,
+}
+
+impl<P> Clone for ProviderTransport<P> {
+    fn clone(&self) -> Self {
+        Self { provider: self.provider.clone(), next_id: self.next_id.clone() }
+    }
+}
+
+impl<P: Provider> ProviderTransport<P> {
+    /// Wraps a provider.
+    pub fn new(provider: P) -> Self {
+        Self::from_arc(Arc::new(provider))
+    }
+
+    /// Wraps a provider that is shared with other owners, e.g. a health check task.
+    pub fn from_arc(provider: Arc<P>) -> Self {
+        Self { provider, next_id: Arc::new(AtomicUsize::new(1)) }
+    }
+
+    /// Returns the wrapped provider.
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+}
+
+impl<P: Provider + 'static> Transport for ProviderTransport<P> {
+    type Out = Pin<Box<dyn Future<Output = web3::Result<Value>> + Send>>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, jsonrpc_core::Call) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        (id, web3::helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, _id: RequestId, request: jsonrpc_core::Call) -> Self::Out {
+        let provider = self.provider.clone();
+        Box::pin(async move {
+            let payload = serde_json::to_value(&request).map_err(|e| web3::Error::Decoder(e.to_string()))?;
+            let response = provider.send_raw(payload).await.map_err(to_web3_error)?;
+            response_result(response).map_err(to_web3_error)
+        })
+    }
+}
+
+impl<P: Provider + 'static> BatchTransport for ProviderTransport<P> {
+    type Batch = Pin<Box<dyn Future<Output = web3::Result<Vec<web3::Result<Value>>>> + Send>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, jsonrpc_core::Call)>,
+    {
+        let provider = self.provider.clone();
+        let requests: Vec<(RequestId, jsonrpc_core::Call)> = requests.into_iter().collect();
+        Box::pin(async move {
+            let ids: Vec<u64> = requests.iter().map(|(id, _)| *id as u64).collect();
+            let calls: Vec<&jsonrpc_core::Call> = requests.iter().map(|(_, call)| call).collect();
+            let payload = serde_json::to_value(&calls).map_err(|e| web3::Error::Decoder(e.to_string()))?;
+
+            let entries = match provider.send_raw(payload).await.map_err(to_web3_error)? {
+                Value::Array(entries) => entries,
+                other => return Err(web3::Error::InvalidResponse(other.to_string())),
+            };
+            // Match entries back to their requests by id, since nodes may reorder them.
+            Ok(ids
+                .iter()
+                .map(|id| {
+                    entries
+                        .iter()
+                        .find(|entry| entry.get("id").and_then(Value::as_u64) == Some(*id))
+                        .ok_or_else(|| web3::Error::InvalidResponse(format!("no response for request {}", id)))
+                        .and_then(|entry| response_result(entry.clone()).map_err(to_web3_error))
+                })
+                .collect())
+        })
+    }
+}
+
+/// Extracts the result of a single JSON-RPC response.
+fn response_result(response: Value) -> Result<Value, ProviderError> {
+    if let Some(error) = response.get("error") {
+        return Err(ProviderError::Rpc(RpcError::from_json(error)));
+    }
+    response.get("result").cloned().ok_or(ProviderError::InvalidResponse)
+}
+
+fn to_web3_error(error: ProviderError) -> web3::Error {
+    match error {
+        ProviderError::Rpc(error) => web3::Error::Rpc(jsonrpc_core::Error {
+            code: jsonrpc_core::ErrorCode::from(error.code),
+            message: error.message,
+            data: error.data,
+        }),
+        ProviderError::InvalidResponse => web3::Error::InvalidResponse("invalid JSON-RPC response".to_string()),
+        _ => web3::Error::Unreachable,
+    }
+}
+
+/// Returns the pause before retry round `round` (1-based): exponential, capped, with jitter
+/// between half and all of the nominal value.
+fn backoff(config: &ProviderConfig, round: u32) -> Duration {
+    let nominal = config
+        .initial_backoff
+        .checked_mul(1 << (round - 1).min(16))
+        .unwrap_or(config.max_backoff)
+        .min(config.max_backoff);
+    let half = nominal / 2;
+    let jitter = RandomState::new().build_hasher().finish() % (half.as_nanos() as u64 + 1);
+    half + Duration::from_nanos(jitter)
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_config() -> ProviderConfig {
+        ProviderConfig {
+            max_retries: 1,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+            request_timeout: Duration::from_millis(500),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backoff_is_capped_with_jitter() {
+        let config = ProviderConfig::default();
+        for round in 1..=3 {
+            let nominal = config.initial_backoff * (1 << (round - 1));
+            let pause = backoff(&config, round);
+            assert!(pause >= nominal / 2 && pause <= nominal);
+        }
+        assert!(backoff(&config, 40) <= config.max_backoff);
+    }
+
+    #[test]
+    fn test_invalid_endpoints() {
+        assert!(matches!(FailoverProvider::new(&[], ProviderConfig::default()), Err(ProviderError::NoEndpoints)));
+        assert!(matches!(
+            FailoverProvider::new(&["not a url"], ProviderConfig::default()),
+            Err(ProviderError::InvalidEndpoint)
+        ));
+    }
+
+    #[test]
+    fn test_failed_endpoints_are_tried_last() {
+        let provider =
+            FailoverProvider::new(&["http://primary:8545", "http://backup:8545"], ProviderConfig::default()).unwrap();
+        provider.record_outcome(&provider.endpoints[0], false);
+        let order: Vec<&str> = provider.endpoint_order().iter().map(|endpoint| endpoint.url.as_str()).collect();
+        assert_eq!(order, vec!["http://backup:8545", "http://primary:8545"]);
+
+        provider.record_outcome(&provider.endpoints[0], true);
+        assert_eq!(provider.endpoint_order()[0].url, "http://primary:8545");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_spaces_requests() {
+        let config = ProviderConfig { requests_per_second: Some(20), ..Default::default() };
+        let provider = FailoverProvider::new(&["http://localhost:8545"], config).unwrap();
+        let started = Instant::now();
+        for _ in 0..3 {
+            provider.wait_for_rate_limit(&provider.endpoints[0]).await;
+        }
+        assert!(started.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_endpoints_fail() {
+        let provider = FailoverProvider::new(&["http://127.0.0.1:1", "http://127.0.0.1:2"], fast_config()).unwrap();
+        let result = provider.request("eth_blockNumber", vec![]).await;
+        assert_eq!(result, Err(ProviderError::AllEndpointsFailed));
+
+        let web3 = FailoverProvider::new(&["http://127.0.0.1:1"], fast_config()).unwrap().into_web3();
+        assert!(web3.eth().block_number().await.is_err());
+    }
+
+    #[test]
+    fn test_response_result() {
+        assert_eq!(response_result(json!({"id": 1, "result": "0x1"})), Ok(json!("0x1")));
+        let error = response_result(json!({"id": 1, "error": {"code": 3, "message": "execution reverted", "data": "0x08c379a0"}}));
+        match error {
+            Err(ProviderError::Rpc(error)) => {
+                assert_eq!(error.code, 3);
+                assert_eq!(error.data, Some(json!("0x08c379a0")));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use crate::contracts::block::BlockTag;
+use crate::framework::logging::{log_info, log_error};
+use crate::framework::provider::Provider;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use web3::types::{Address, Bytes, H256, U256};
+use std::fmt;
+
+/// Errors that can occur while sending a JSON-RPC batch as a whole.
+#[derive(Debug)]
+pub enum BatchError {
+    RequestFailed,
+    InvalidResponse,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::RequestFailed => write!(f, "batch request failed"),
+            BatchError::InvalidResponse => write!(f, "invalid batch response"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// An error returned by the node for a single request of a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    /// Reads the `error` object of a JSON-RPC response.
+    pub(crate) fn from_json(error: &Value) -> Self {
+        Self {
+            code: error.get("code").and_then(Value::as_i64).unwrap_or_default(),
+            message: error.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            data: error.get("data").cloned(),
+        }
+    }
+
+    fn internal(message: &str) -> Self {
+        Self { code: -32603, message: message.to_string(), data: None }
+    }
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JSON-RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+/// Collects JSON-RPC requests that are sent together as one batch array in a single HTTP request.
+#[derive(Debug, Clone, Default)]
+pub struct RpcBatch {
+    requests: Vec<(String, Vec<Value>)>,
+}
+
+impl RpcBatch {
+    /// Creates an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a request and returns its index in the batch response.
+    pub fn add(&mut self, method: &str, params: Vec<Value>) -> usize {
+        self.requests.push((method.to_string(), params));
+        self.requests.len() - 1
+    }
+
+    /// Queues an `eth_getBalance` request.
+    pub fn get_balance(&mut self, address: Address, block: Option<BlockTag>) -> usize {
+        self.add("eth_getBalance", vec![json!(address), block_param(block)])
+    }
+
+    /// Queues an `eth_getCode` request.
+    pub fn get_code(&mut self, address: Address, block: Option<BlockTag>) -> usize {
+        self.add("eth_getCode", vec![json!(address), block_param(block)])
+    }
+
+    /// Queues an `eth_getStorageAt` request.
+    pub fn get_storage_at(&mut self, address: Address, slot: U256, block: Option<BlockTag>) -> usize {
+        self.add("eth_getStorageAt", vec![json!(address), json!(slot), block_param(block)])
+    }
+
+    /// Returns the number of queued requests.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns `true` if no requests are queued.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Returns the JSON-RPC batch array. Each request uses its index as its id.
+    pub fn to_json(&self) -> Value {
+        Value::Array(
+            self.requests
+                .iter()
+                .enumerate()
+                .map(|(id, (method, params))| json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}))
+                .collect(),
+        )
+    }
+
+    /// Sends the batch through a provider as a single JSON-RPC batch payload.
+    ///
+    /// # Arguments
+    /// * `provider` - The provider the batch is sent through.
+    ///
+    /// # Returns
+    /// Result<BatchResponse, BatchError> - Returns the per-request results, otherwise returns an
+    /// error if the batch as a whole could not be sent or parsed.
+    pub async fn send<P: Provider + ?Sized>(&self, provider: &P) -> Result<BatchResponse, BatchError> {
+        if self.requests.is_empty() {
+            return Ok(BatchResponse { results: Vec::new() });
+        }
+
+        log_info(&format!("Sending JSON-RPC batch of {} request(s)", self.requests.len()));
+
+        let body = provider.send_raw(self.to_json()).await.map_err(|e| {
+            log_error(&format!("JSON-RPC batch request failed: {:?}", e));
+            BatchError::RequestFailed
+        })?;
+
+        BatchResponse::parse(self.requests.len(), body)
+    }
+}
+
+/// The results of a batch, in the order the requests were queued.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchResponse {
+    results: Vec<Result<Value, RpcError>>,
+}
+
+impl BatchResponse {
+    /// Matches the entries of a batch response back to their requests by id.
+    ///
+    /// Nodes may answer batch entries in any order, so entries are matched by id rather than
+    /// by position. Requests without a matching entry get an internal error.
+    pub fn parse(request_count: usize, body: Value) -> Result<Self, BatchError> {
+        let entries = match body {
+            Value::Array(entries) => entries,
+            other => {
+                log_error(&format!("Expected a JSON-RPC batch response, got: {}", other));
+                return Err(BatchError::InvalidResponse);
+            }
+        };
+
+        let mut by_id: HashMap<u64, Result<Value, RpcError>> = HashMap::new();
+        for entry in entries {
+            let id = entry.get("id").and_then(Value::as_u64).ok_or(BatchError::InvalidResponse)?;
+            let result = match (entry.get("result"), entry.get("error")) {
+                (_, Some(error)) => Err(RpcError::from_json(error)),
+                (Some(result), None) => Ok(result.clone()),
+                (None, None) => Err(RpcError::internal("Response has neither result nor error.")),
+            };
+            by_id.insert(id, result);
+        }
+
+        let results = (0..request_count as u64)
+            .map(|id| {
+                by_id
+                    .remove(&id)
+                    .unwrap_or_else(|| Err(RpcError::internal("No response for request.")))
+            })
+            .collect();
+        Ok(Self { results })
+    }
+
+    /// Returns the raw result of the request at `index`.
+    pub fn get(&self, index: usize) -> Result<&Value, RpcError> {
+        match self.results.get(index) {
+            Some(Ok(value)) => Ok(value),
+            Some(Err(error)) => Err(error.clone()),
+            None => Err(RpcError::internal("No request at this index.")),
+        }
+    }
+
+    /// Deserializes the result of the request at `index`, e.g. into `U256`, `Bytes` or `H256`.
+    pub fn decode<T: DeserializeOwned>(&self, index: usize) -> Result<T, RpcError> {
+        let value = self.get(index)?;
+        serde_json::from_value(value.clone()).map_err(|e| RpcError::internal(&e.to_string()))
+    }
+
+    /// Returns the result of a queued `eth_getBalance`.
+    pub fn balance(&self, index: usize) -> Result<U256, RpcError> {
+        self.decode(index)
+    }
+
+    /// Returns the result of a queued `eth_getCode`.
+    pub fn code(&self, index: usize) -> Result<Bytes, RpcError> {
+        self.decode(index)
+    }
+
+    /// Returns the result of a queued `eth_getStorageAt`.
+    pub fn storage(&self, index: usize) -> Result<H256, RpcError> {
+        self.decode(index)
+    }
+
+    /// Returns the number of results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns `true` if the batch was empty.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+fn block_param(block: Option<BlockTag>) -> Value {
+    json!(block.unwrap_or_default())
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_request_format() {
+        let mut batch = RpcBatch::new();
+        let balance = batch.get_balance(Address::repeat_byte(1), None);
+        let storage = batch.get_storage_at(Address::repeat_byte(1), U256::from(3), Some(BlockTag::from(16)));
+
+        let request = batch.to_json();
+        assert_eq!(request[balance]["method"], "eth_getBalance");
+        assert_eq!(request[balance]["params"][1], "latest");
+        assert_eq!(request[storage]["id"], 1);
+        assert_eq!(request[storage]["params"][1], "0x3");
+        assert_eq!(request[storage]["params"][2], "0x10");
+    }
+
+    #[test]
+    fn test_out_of_order_response_with_errors() {
+        let body = json!([
+            {"jsonrpc": "2.0", "id": 2, "error": {"code": -32000, "message": "header not found"}},
+            {"jsonrpc": "2.0", "id": 0, "result": "0x64"},
+            {"jsonrpc": "2.0", "id": 1, "result": "0x6080"}
+        ]);
+
+        let response = BatchResponse::parse(4, body).unwrap();
+        assert_eq!(response.balance(0).unwrap(), U256::from(100));
+        assert_eq!(response.code(1).unwrap(), Bytes(vec![0x60, 0x80]));
+        assert_eq!(response.storage(2).unwrap_err().code, -32000);
+        assert_eq!(response.get(3).unwrap_err().code, -32603);
+    }
+
+    #[test]
+    fn test_non_batch_response() {
+        let body = json!({"jsonrpc": "2.0", "id": null, "error": {"code": -32600, "message": "batch not supported"}});
+        assert!(matches!(BatchResponse::parse(1, body), Err(BatchError::InvalidResponse)));
+    }
+}
@@ -0,0 +1,372 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::contracts::gas::{estimate_transaction_gas, GasEstimateOptions, GasOptimizationError};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use web3::types::TransactionRequest;
+use web3::{Transport, Web3};
+
+/// The environment variable that makes the `gas-snapshot` command rewrite the baseline instead
+/// of comparing against it. Test harnesses read it to choose between [`GasSnapshot::check`]
+/// and [`GasSnapshot::update`]; the library itself never does.
+pub const UPDATE_SNAPSHOT_ENV: &str = "GAS_SNAPSHOT_UPDATE";
+
+/// Errors that can occur while recording or checking gas snapshots.
+#[derive(Debug)]
+pub enum SnapshotError {
+    ReadFailed,
+    WriteFailed,
+    /// The baseline snapshot file does not exist.
+    MissingBaseline,
+    /// A line of the snapshot file is not `name (gas: N)`; holds the line number.
+    InvalidLine(usize),
+    MeasurementFailed(GasOptimizationError),
+    /// Entries that used more gas than the tolerance allows.
+    Regression(Vec<String>),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::ReadFailed => write!(f, "failed to read the gas snapshot"),
+            SnapshotError::WriteFailed => write!(f, "failed to write the gas snapshot"),
+            SnapshotError::MissingBaseline => {
+                write!(f, "the gas snapshot baseline does not exist; set {} to create it", UPDATE_SNAPSHOT_ENV)
+            }
+            SnapshotError::InvalidLine(line) => write!(f, "invalid gas snapshot entry on line {}", line),
+            SnapshotError::MeasurementFailed(error) => write!(f, "gas measurement failed: {}", error),
+            SnapshotError::Regression(names) => write!(f, "gas regressions in {}", names.join(", ")),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SnapshotError::MeasurementFailed(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Named gas measurements, stored one per line as `name (gas: 12345)` like Foundry's `.gas-snapshot`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasSnapshot {
+    pub entries: BTreeMap<String, u64>,
+}
+
+impl GasSnapshot {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses a snapshot. Foundry fuzz entries (`name (runs: 256, μ: 123, ~: 120)`) are read as their mean.
+    pub fn parse(content: &str) -> Result<Self, SnapshotError> {
+        let mut entries = BTreeMap::new();
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || SnapshotError::InvalidLine(index + 1);
+            let open = line.rfind(" (").ok_or_else(invalid)?;
+            let fields = line[open + 2..].strip_suffix(')').ok_or_else(invalid)?;
+            let gas = fields
+                .split(", ")
+                .find_map(|field| field.strip_prefix("gas: ").or_else(|| field.strip_prefix("μ: ")))
+                .and_then(|gas| gas.parse().ok())
+                .ok_or_else(invalid)?;
+            entries.insert(line[..open].to_string(), gas);
+        }
+        Ok(Self { entries })
+    }
+
+    /// Reads a snapshot file, returning `SnapshotError::MissingBaseline` if it does not exist.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        match std::fs::read_to_string(path.as_ref()) {
+            Ok(content) => Self::parse(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                log_error(&format!("Gas snapshot {} does not exist.", path.as_ref().display()));
+                Err(SnapshotError::MissingBaseline)
+            }
+            Err(e) => {
+                log_error(&format!("Failed to read gas snapshot {}: {}", path.as_ref().display(), e));
+                Err(SnapshotError::ReadFailed)
+            }
+        }
+    }
+
+    /// Reads a snapshot file like [`GasSnapshot::load`], treating a missing file as an empty snapshot.
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        match Self::load(path) {
+            Err(SnapshotError::MissingBaseline) => Ok(Self::new()),
+            result => result,
+        }
+    }
+
+    /// Writes the snapshot file, sorted by name so that diffs stay small.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        std::fs::write(path.as_ref(), self.to_string()).map_err(|e| {
+            log_error(&format!("Failed to write gas snapshot {}: {}", path.as_ref().display(), e));
+            SnapshotError::WriteFailed
+        })
+    }
+
+    /// Records a measurement, replacing any earlier one with the same name.
+    pub fn record(&mut self, name: &str, gas: u64) {
+        self.entries.insert(name.to_string(), gas);
+    }
+
+    /// Measures the gas of a transaction through `eth_estimateGas` and records it.
+    ///
+    /// # Arguments
+    /// * `web3` - The connection to the node, e.g. a [`MockProvider`](crate::framework::mock::MockProvider)
+    ///   replaying a fixture or a local development chain.
+    /// * `name` - The name of the measurement.
+    /// * `transaction` - The transaction to measure.
+    ///
+    /// # Returns
+    /// Result<u64, SnapshotError> - Returns the measured gas, otherwise returns an error.
+    pub async fn measure<T: Transport>(
+        &mut self,
+        web3: &Web3<T>,
+        name: &str,
+        transaction: &TransactionRequest,
+    ) -> Result<u64, SnapshotError> {
+        let estimate = estimate_transaction_gas(web3, transaction, &GasEstimateOptions::default())
+            .await
+            .map_err(SnapshotError::MeasurementFailed)?;
+        let gas = estimate.estimated_gas.low_u64();
+        self.record(name, gas);
+        Ok(gas)
+    }
+
+    /// Compares the snapshot against an earlier one.
+    pub fn diff(&self, baseline: &GasSnapshot) -> SnapshotDiff {
+        let mut names: Vec<&String> = baseline.entries.keys().chain(self.entries.keys()).collect();
+        names.sort();
+        names.dedup();
+        let entries = names
+            .into_iter()
+            .map(|name| SnapshotDelta {
+                name: name.clone(),
+                before: baseline.entries.get(name).copied(),
+                after: self.entries.get(name).copied(),
+            })
+            .collect();
+        SnapshotDiff { entries }
+    }
+
+    /// Compares the snapshot against the file at `path`.
+    ///
+    /// # Arguments
+    /// * `path` - The snapshot file, e.g. `.gas-snapshot`.
+    /// * `tolerance_percent` - The largest increase allowed per entry, e.g. `1.0` for 1%.
+    ///
+    /// # Returns
+    /// Result<SnapshotDiff, SnapshotError> - Returns the diff, otherwise returns
+    /// `SnapshotError::Regression` naming the entries over the tolerance, or
+    /// `SnapshotError::MissingBaseline` if the file does not exist.
+    pub fn check<P: AsRef<Path>>(&self, path: P, tolerance_percent: f64) -> Result<SnapshotDiff, SnapshotError> {
+        let path = path.as_ref();
+        let diff = self.diff(&Self::load(path)?);
+        log_info(&format!("Gas snapshot diff against {}:\n{}", path.display(), diff));
+        let regressions: Vec<String> =
+            diff.regressions(tolerance_percent).into_iter().map(|delta| delta.name.clone()).collect();
+        if regressions.is_empty() {
+            Ok(diff)
+        } else {
+            log_warn(&format!("Gas regressions over {}%: {}", tolerance_percent, regressions.join(", ")));
+            Err(SnapshotError::Regression(regressions))
+        }
+    }
+
+    /// Rewrites the file at `path` with this snapshot, making it the new baseline.
+    ///
+    /// # Returns
+    /// Result<(), SnapshotError> - Returns Ok if the file was written, otherwise returns an error.
+    pub fn update<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        let path = path.as_ref();
+        self.save(path)?;
+        log_info(&format!("Wrote gas snapshot {} with {} entries.", path.display(), self.entries.len()));
+        Ok(())
+    }
+}
+
+impl fmt::Display for GasSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, gas) in &self.entries {
+            writeln!(f, "{} (gas: {})", name, gas)?;
+        }
+        Ok(())
+    }
+}
+
+/// The change of one entry between two snapshots.
+///
+/// # Fields
+/// - `name`: The name of the measurement.
+/// - `before`: The gas in the baseline, or `None` for a new entry.
+/// - `after`: The gas now, or `None` for a removed entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDelta {
+    pub name: String,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl SnapshotDelta {
+    /// Returns the change in gas, if the entry is in both snapshots.
+    pub fn delta(&self) -> Option<i64> {
+        Some(self.after? as i64 - self.before? as i64)
+    }
+
+    /// Returns the change as a percentage of the baseline, if the entry is in both snapshots.
+    pub fn percent(&self) -> Option<f64> {
+        match (self.before?, self.delta()?) {
+            (0, 0) => Some(0.0),
+            (0, _) => Some(f64::INFINITY),
+            (before, delta) => Some(delta as f64 * 100.0 / before as f64),
+        }
+    }
+}
+
+impl fmt::Display for SnapshotDelta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.before, self.after, self.delta(), self.percent()) {
+            (Some(before), Some(after), Some(delta), Some(percent)) => write!(
+                f,
+                "{} (gas: {} -> {} | {:+} {:+.3}%)",
+                self.name, before, after, delta, percent
+            ),
+            (None, Some(after), _, _) => write!(f, "{} (gas: {} | new)", self.name, after),
+            (Some(before), None, _, _) => write!(f, "{} (gas: {} | removed)", self.name, before),
+            _ => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The per-entry changes between two snapshots, sorted by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotDiff {
+    pub entries: Vec<SnapshotDelta>,
+}
+
+impl SnapshotDiff {
+    /// Returns the entries whose gas changed, were added or were removed.
+    pub fn changed(&self) -> Vec<&SnapshotDelta> {
+        self.entries.iter().filter(|delta| delta.before != delta.after).collect()
+    }
+
+    /// Returns the entries that grew by more than `tolerance_percent`.
+    pub fn regressions(&self, tolerance_percent: f64) -> Vec<&SnapshotDelta> {
+        self.entries
+            .iter()
+            .filter(|delta| delta.delta().is_some_and(|delta| delta > 0))
+            .filter(|delta| delta.percent().is_some_and(|percent| percent > tolerance_percent))
+            .collect()
+    }
+
+    /// Returns the process exit code for a check: `1` if any entry regressed beyond the tolerance.
+    pub fn exit_code(&self, tolerance_percent: f64) -> i32 {
+        if self.regressions(tolerance_percent).is_empty() { 0 } else { 1 }
+    }
+
+    /// Returns the change of the summed gas of the entries in both snapshots.
+    pub fn total_delta(&self) -> i64 {
+        self.entries.iter().filter_map(SnapshotDelta::delta).sum()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    /// Formats the changed entries, one per line, followed by the overall change.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for delta in self.changed() {
+            writeln!(f, "{}", delta)?;
+        }
+        write!(f, "Overall gas change: {:+}", self.total_delta())
+    }
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::framework::mock::MockProvider;
+    use crate::framework::provider::ProviderTransport;
+    use serde_json::json;
+    use web3::types::{Address, U256};
+
+    const BASELINE: &str = "Token::approve (gas: 46000)\n\
+                            Token::transfer (gas: 51000)\n\
+                            Vault::deposit (runs: 256, μ: 80000, ~: 79500)\n";
+
+    #[test]
+    fn test_parse_and_format() {
+        let snapshot = GasSnapshot::parse(BASELINE).unwrap();
+        assert_eq!(snapshot.entries["Vault::deposit"], 80_000);
+        assert_eq!(snapshot.to_string().lines().nth(1), Some("Token::transfer (gas: 51000)"));
+        assert_eq!(GasSnapshot::parse(&snapshot.to_string()).unwrap(), snapshot);
+
+        assert!(matches!(GasSnapshot::parse("a (gas: 1)\nbroken\n"), Err(SnapshotError::InvalidLine(2))));
+    }
+
+    #[test]
+    fn test_diff() {
+        let baseline = GasSnapshot::parse(BASELINE).unwrap();
+        let mut current = baseline.clone();
+        current.record("Token::transfer", 52_020);
+        current.record("Token::approve", 45_540);
+        current.entries.remove("Vault::deposit");
+        current.record("Vault::withdraw", 60_000);
+
+        let diff = current.diff(&baseline);
+        assert_eq!(diff.changed().len(), 4);
+        let transfer = diff.entries.iter().find(|delta| delta.name == "Token::transfer").unwrap();
+        assert_eq!(transfer.delta(), Some(1_020));
+        assert_eq!(transfer.to_string(), "Token::transfer (gas: 51000 -> 52020 | +1020 +2.000%)");
+        assert_eq!(diff.total_delta(), 560);
+
+        assert_eq!(diff.regressions(1.0).len(), 1);
+        assert_eq!(diff.exit_code(1.0), 1);
+        assert_eq!(diff.exit_code(5.0), 0);
+        assert!(diff.to_string().contains("Vault::deposit (gas: 80000 | removed)"));
+    }
+
+    #[test]
+    fn test_check_against_file() {
+        let path = std::env::temp_dir().join(format!("wasmify-gas-snapshot-{}", std::process::id()));
+        let mut snapshot = GasSnapshot::new();
+        snapshot.record("Token::transfer", 51_000);
+        // A missing baseline fails the check instead of passing silently.
+        assert!(matches!(snapshot.check(&path, 1.0), Err(SnapshotError::MissingBaseline)));
+        assert!(matches!(GasSnapshot::load(&path), Err(SnapshotError::MissingBaseline)));
+        assert_eq!(GasSnapshot::load_or_default(&path).unwrap(), GasSnapshot::new());
+        snapshot.update(&path).unwrap();
+        assert_eq!(GasSnapshot::load(&path).unwrap(), snapshot);
+
+        snapshot.record("Token::transfer", 51_400);
+        assert!(snapshot.check(&path, 1.0).is_ok());
+        snapshot.record("Token::transfer", 52_000);
+        let result = snapshot.check(&path, 1.0);
+        std::fs::remove_file(&path).unwrap();
+        match result {
+            Err(SnapshotError::Regression(names)) => assert_eq!(names, vec!["Token::transfer".to_string()]),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_measure() {
+        let mock = MockProvider::new();
+        mock.on_request("eth_estimateGas", None, json!(U256::from(51_234)));
+        mock.on_request("eth_getBlockByNumber", None, json!({"gasLimit": "0x1c9c380", "baseFeePerGas": "0x1"}));
+        mock.on_request("eth_maxPriorityFeePerGas", None, json!("0x1"));
+        let web3 = Web3::new(ProviderTransport::new(mock));
+
+        let transaction = TransactionRequest { from: Address::repeat_byte(1), to: Some(Address::repeat_byte(2)), ..Default::default() };
+        let mut snapshot = GasSnapshot::new();
+        assert_eq!(snapshot.measure(&web3, "Token::transfer", &transaction).await.unwrap(), 51_234);
+        assert_eq!(snapshot.to_string(), "Token::transfer (gas: 51234)\n");
+    }
+}
@@ -0,0 +1,320 @@
+use crate::framework::logging::{log_info, log_warn, log_error};
+use crate::framework::provider::{FailoverProvider, Provider, ProviderConfig, ProviderTransport};
+use futures::Stream;
+use serde_json::Value;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use web3::api::SubscriptionId;
+use web3::transports::{Ipc, WebSocket};
+use web3::{BatchTransport, DuplexTransport, RequestId, Transport, Web3};
+use std::fmt;
+
+/// How many times a dropped WebSocket connection is re-established before a request fails.
+const RECONNECT_ATTEMPTS: u32 = 5;
+
+/// Errors that can occur while connecting to a node.
+#[derive(Debug)]
+pub enum TransportError {
+    InvalidEndpoint,
+    ConnectionFailed,
+}
+
+impl fmt::Display for TransportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransportError::InvalidEndpoint => write!(f, "invalid endpoint"),
+            TransportError::ConnectionFailed => write!(f, "connection failed"),
+        }
+    }
+}
+
+impl std::error::Error for TransportError {}
+
+/// Where and how to connect to a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransportConfig {
+    /// One or more HTTP endpoints, used through a [`FailoverProvider`].
+    Http(Vec<String>),
+    /// A WebSocket endpoint that is reconnected automatically when it drops.
+    WebSocket(String),
+    /// The path of a node's Unix IPC socket.
+    Ipc(PathBuf),
+}
+
+impl TransportConfig {
+    /// Picks the transport from an endpoint: `http(s)://` and `ws(s)://` URLs, or an IPC
+    /// socket given as `ipc://<path>` or as a path ending in `.ipc`.
+    pub fn parse(endpoint: &str) -> Result<Self, TransportError> {
+        if endpoint.starts_with("http://") || endpoint.starts_with("https://") {
+            Ok(TransportConfig::Http(vec![endpoint.to_string()]))
+        } else if endpoint.starts_with("ws://") || endpoint.starts_with("wss://") {
+            Ok(TransportConfig::WebSocket(endpoint.to_string()))
+        } else if let Some(path) = endpoint.strip_prefix("ipc://") {
+            Ok(TransportConfig::Ipc(PathBuf::from(path)))
+        } else if endpoint.ends_with(".ipc") {
+            Ok(TransportConfig::Ipc(PathBuf::from(endpoint)))
+        } else {
+            Err(TransportError::InvalidEndpoint)
+        }
+    }
+}
+
+/// A WebSocket transport that reconnects when the connection drops.
+///
+/// Requests that fail because the socket is gone are retried once on a fresh connection.
+/// Subscription streams end when their connection drops and must be re-established by the
+/// subscriber, as `watch` does.
+#[derive(Debug, Clone)]
+pub struct ReconnectingWebSocket {
+    url: String,
+    socket: Arc<Mutex<WebSocket>>,
+    /// Incremented on every reconnect, so that concurrent failures reconnect only once.
+    generation: Arc<AtomicUsize>,
+    reconnecting: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ReconnectingWebSocket {
+    /// Opens a connection to a WebSocket endpoint.
+    pub async fn connect(url: &str) -> Result<Self, TransportError> {
+        let socket = WebSocket::new(url).await.map_err(|e| {
+            log_error(&format!("Failed to connect to {}: {}", url, e));
+            TransportError::ConnectionFailed
+        })?;
+        Ok(Self {
+            url: url.to_string(),
+            socket: Arc::new(Mutex::new(socket)),
+            generation: Arc::new(AtomicUsize::new(0)),
+            reconnecting: Arc::new(tokio::sync::Mutex::new(())),
+        })
+    }
+
+    fn current(&self) -> (WebSocket, usize) {
+        let socket = self.socket.lock().unwrap().clone();
+        (socket, self.generation.load(Ordering::Acquire))
+    }
+
+    /// Replaces the connection of `failed_generation`, unless another task already did.
+    async fn reconnect(&self, failed_generation: usize) -> web3::Result<WebSocket> {
+        let _guard = self.reconnecting.lock().await;
+        let (socket, generation) = self.current();
+        if generation != failed_generation {
+            return Ok(socket);
+        }
+
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            match WebSocket::new(&self.url).await {
+                Ok(socket) => {
+                    *self.socket.lock().unwrap() = socket.clone();
+                    self.generation.fetch_add(1, Ordering::AcqRel);
+                    log_info(&format!("Reconnected to {}", self.url));
+                    return Ok(socket);
+                }
+                Err(e) => {
+                    log_warn(&format!("Reconnect attempt {} to {} failed: {}", attempt, self.url, e));
+                    sleep(Duration::from_millis(250) * 2u32.pow(attempt - 1)).await;
+                }
+            }
+        }
+        log_error(&format!("Giving up reconnecting to {}", self.url));
+        Err(web3::Error::Unreachable)
+    }
+}
+
+/// Whether an error means the connection itself is gone, rather than the node rejecting the request.
+fn is_connection_error(error: &web3::Error) -> bool {
+    matches!(error, web3::Error::Unreachable | web3::Error::Transport(_) | web3::Error::Io(_))
+}
+
+/// A connection to a node over HTTP, WebSocket or IPC.
+///
+/// `Web3<NodeTransport>` can be passed to every function of the contract modules, whatever
+/// the underlying transport. Subscriptions are available over WebSocket and IPC.
+#[derive(Debug, Clone)]
+pub enum NodeTransport {
+    /// Request/response only: HTTP endpoints through a [`FailoverProvider`], or any other
+    /// [`Provider`] such as a mock.
+    Provider(ProviderTransport<Box<dyn Provider>>),
+    WebSocket(ReconnectingWebSocket),
+    Ipc(Ipc),
+}
+
+impl NodeTransport {
+    /// Connects to a node.
+    ///
+    /// # Arguments
+    /// * `config` - The transport and endpoint to connect to.
+    /// * `provider_config` - Retry, timeout and rate limit settings, used for HTTP endpoints.
+    ///
+    /// # Returns
+    /// Result<NodeTransport, TransportError> - Returns the connection, otherwise returns an error.
+    pub async fn connect(config: &TransportConfig, provider_config: ProviderConfig) -> Result<Self, TransportError> {
+        match config {
+            TransportConfig::Http(urls) => {
+                let urls: Vec<&str> = urls.iter().map(String::as_str).collect();
+                let provider = FailoverProvider::new(&urls, provider_config).map_err(|_| TransportError::InvalidEndpoint)?;
+                Ok(NodeTransport::from_provider(provider))
+            }
+            TransportConfig::WebSocket(url) => Ok(NodeTransport::WebSocket(ReconnectingWebSocket::connect(url).await?)),
+            TransportConfig::Ipc(path) => {
+                let ipc = Ipc::new(path).await.map_err(|e| {
+                    log_error(&format!("Failed to connect to {}: {}", path.display(), e));
+                    TransportError::ConnectionFailed
+                })?;
+                log_info(&format!("Connected to IPC socket {}", path.display()));
+                Ok(NodeTransport::Ipc(ipc))
+            }
+        }
+    }
+
+    /// Uses a request/response [`Provider`]. Subscriptions are not available.
+    pub fn from_provider<P: Provider + 'static>(provider: P) -> Self {
+        NodeTransport::Provider(ProviderTransport::new(Box::new(provider)))
+    }
+
+    /// Returns a `web3` client over this connection.
+    pub fn into_web3(self) -> Web3<Self> {
+        Web3::new(self)
+    }
+
+    /// Returns `true` if the transport can push subscription notifications.
+    pub fn supports_subscriptions(&self) -> bool {
+        !matches!(self, NodeTransport::Provider(_))
+    }
+}
+
+type BoxedResult<T> = Pin<Box<dyn Future<Output = web3::Result<T>> + Send>>;
+
+/// Request ids are shared by all clones, so that ids stay unique across WebSocket reconnects.
+static NEXT_REQUEST_ID: AtomicUsize = AtomicUsize::new(1);
+
+impl Transport for NodeTransport {
+    type Out = BoxedResult<Value>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, jsonrpc_core::Call) {
+        let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+        (id, web3::helpers::build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: jsonrpc_core::Call) -> Self::Out {
+        match self {
+            NodeTransport::Provider(transport) => transport.send(id, request),
+            NodeTransport::Ipc(ipc) => Box::pin(ipc.send(id, request)),
+            NodeTransport::WebSocket(ws) => {
+                let ws = ws.clone();
+                Box::pin(async move {
+                    let (socket, generation) = ws.current();
+                    match socket.send(id, request.clone()).await {
+                        Err(e) if is_connection_error(&e) => {
+                            log_warn(&format!("WebSocket request failed ({}), reconnecting.", e));
+                            ws.reconnect(generation).await?.send(id, request).await
+                        }
+                        result => result,
+                    }
+                })
+            }
+        }
+    }
+}
+
+impl BatchTransport for NodeTransport {
+    type Batch = BoxedResult<Vec<web3::Result<Value>>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, jsonrpc_core::Call)>,
+    {
+        let requests: Vec<(RequestId, jsonrpc_core::Call)> = requests.into_iter().collect();
+        match self {
+            NodeTransport::Provider(transport) => transport.send_batch(requests),
+            NodeTransport::Ipc(ipc) => Box::pin(ipc.send_batch(requests)),
+            NodeTransport::WebSocket(ws) => {
+                let ws = ws.clone();
+                Box::pin(async move {
+                    let (socket, generation) = ws.current();
+                    match socket.send_batch(requests.clone()).await {
+                        Err(e) if is_connection_error(&e) => {
+                            log_warn(&format!("WebSocket batch failed ({}), reconnecting.", e));
+                            ws.reconnect(generation).await?.send_batch(requests).await
+                        }
+                        result => result,
+                    }
+                })
+            }
+        }
+    }
+}
+
+impl DuplexTransport for NodeTransport {
+    type NotificationStream = Pin<Box<dyn Stream<Item = Value> + Send>>;
+
+    fn subscribe(&self, id: SubscriptionId) -> web3::Result<Self::NotificationStream> {
+        match self {
+            NodeTransport::Provider(_) => Err(subscriptions_unsupported()),
+            NodeTransport::WebSocket(ws) => Ok(Box::pin(ws.current().0.subscribe(id)?)),
+            NodeTransport::Ipc(ipc) => Ok(Box::pin(ipc.subscribe(id)?)),
+        }
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) -> web3::Result<()> {
+        match self {
+            NodeTransport::Provider(_) => Err(subscriptions_unsupported()),
+            NodeTransport::WebSocket(ws) => ws.current().0.unsubscribe(id),
+            NodeTransport::Ipc(ipc) => ipc.unsubscribe(id),
+        }
+    }
+}
+
+fn subscriptions_unsupported() -> web3::Error {
+    web3::Error::Transport(web3::error::TransportError::Message(
+        "Subscriptions need a WebSocket or IPC connection.".to_string(),
+    ))
+}
+
+// Unit test example
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_endpoints() {
+        assert_eq!(
+            TransportConfig::parse("https://rpc.example.org").unwrap(),
+            TransportConfig::Http(vec!["https://rpc.example.org".to_string()])
+        );
+        assert_eq!(
+            TransportConfig::parse("wss://rpc.example.org/ws").unwrap(),
+            TransportConfig::WebSocket("wss://rpc.example.org/ws".to_string())
+        );
+        assert_eq!(
+            TransportConfig::parse("/var/run/geth.ipc").unwrap(),
+            TransportConfig::Ipc(PathBuf::from("/var/run/geth.ipc"))
+        );
+        assert_eq!(
+            TransportConfig::parse("ipc:///tmp/reth.sock").unwrap(),
+            TransportConfig::Ipc(PathBuf::from("/tmp/reth.sock"))
+        );
+        assert!(matches!(TransportConfig::parse("localhost:8545"), Err(TransportError::InvalidEndpoint)));
+    }
+
+    #[tokio::test]
+    async fn test_http_has_no_subscriptions() {
+        let config = TransportConfig::parse("http://localhost:8545").unwrap();
+        let transport = NodeTransport::connect(&config, ProviderConfig::default()).await.unwrap();
+        assert!(!transport.supports_subscriptions());
+        assert!(transport.subscribe(SubscriptionId::from("0x1".to_string())).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_sockets() {
+        let ws = TransportConfig::WebSocket("ws://127.0.0.1:1".to_string());
+        assert!(matches!(NodeTransport::connect(&ws, ProviderConfig::default()).await, Err(TransportError::ConnectionFailed)));
+
+        let ipc = TransportConfig::Ipc(PathBuf::from("/nonexistent/node.ipc"));
+        assert!(matches!(NodeTransport::connect(&ipc, ProviderConfig::default()).await, Err(TransportError::ConnectionFailed)));
+    }
+}
@@ -8,15 +8,36 @@ pub mod contracts;
 /// Framework modules for optimization and asynchronous operations.
 pub mod framework;
 
+/// The error type shared by all modules.
+pub mod error;
+
 // Exported functions and modules for external use.
-pub use contracts::deploy::deploy_contract;
+pub use error::Error;
+pub use contracts::deploy::{deploy_contract, send_deployment, prepare_deployment};
 pub use contracts::abi::parse_abi;
-pub use contracts::gas::{estimate_gas, check_gas_limit, optimize_gas_dynamically};
-pub use contracts::interaction::{call_contract_function, fetch_contract_data};
-pub use contracts::watch::watch_contract_events;
-pub use contracts::contract_update::update_contract;
-pub use contracts::monitor::monitor_contract_activity;
+pub use contracts::gas::{estimate_gas, estimate_gas_limit, estimate_transaction_gas, estimate_gas_offline, GasEstimate, GasEstimateOptions, check_gas_limit, intrinsic_gas, IntrinsicGas, Hardfork, optimize_gas_dynamically, build_typed_transaction};
+pub use contracts::interaction::{call_contract_function, call_view_function, query_contract_function, send_contract_transaction, prepare_contract_transaction, fetch_contract_data, fetch_contract_data_batch, fetch_storage_variable, CallOptions, StateOverride};
+pub use contracts::block::BlockTag;
+pub use contracts::watch::{watch_contract_events, watch_contract_logs};
+pub use contracts::contract_update::{update_contract, propose_upgrade, send_upgrade, prepare_upgrade, update_diamond};
+pub use contracts::monitor::{monitor_contract_activity, poll_contract_activity};
+pub use contracts::fees::{FeeOracle, FeeEstimates, FeeSpeed};
+pub use contracts::gas_strategy::{GasStrategy, FixedGasStrategy, PercentileGasStrategy, LinearEscalator, GeometricEscalator};
+pub use contracts::bytecode::{analyze_bytecode, disassemble, GasAnalysis, FunctionGas, DynamicCost};
+pub use contracts::profiler::{Profiler, GasProfile, FrameProfile, StructLogTrace, trace_transaction};
+pub use contracts::nonce::NonceManager;
+pub use contracts::network::{Network, NetworkFeatures, NativeCurrency, Rollup};
+pub use contracts::l2::{estimate_l2_gas, L2GasEstimate, OpStackFeeParams};
+pub use contracts::pending::{PendingTransaction, TransactionStatus};
+pub use contracts::multicall::Multicall;
+pub use contracts::signer::{Signer, PrivateKeySigner};
+pub use contracts::offline::{OfflineTransaction, broadcast_raw_transaction};
 pub use crate::framework::async_operations::perform_optimized_operations;
+pub use framework::rpc_batch::{RpcBatch, BatchResponse};
+pub use framework::provider::{Provider, FailoverProvider, ProviderConfig, ProviderTransport};
+pub use framework::transport::{NodeTransport, TransportConfig};
+pub use framework::mock::{MockProvider, RecordingProvider};
+pub use framework::snapshot::{GasSnapshot, SnapshotDiff, SnapshotDelta};
 pub use framework::logging::{log_info, log_warn, log_error, log_debug};
 pub use std::time::{Instant, Duration};
 pub use chrono::Local;
@@ -1,3 +1,56 @@
+use std::process;
+use wasmify_rs::framework::snapshot::{GasSnapshot, UPDATE_SNAPSHOT_ENV};
+
 fn main() {
-    println!("Hello, wasmify-rs!");
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("gas-snapshot") => process::exit(gas_snapshot(&args[1..])),
+        _ => println!("Hello, wasmify-rs!"),
+    }
+}
+
+/// `gas-snapshot <baseline> <current> [--tolerance <percent>]`
+///
+/// Prints the per-entry gas changes between two snapshot files and exits with `1` if any
+/// entry grew by more than the tolerance (0% by default), or `2` on invalid input, including a
+/// missing baseline. With `GAS_SNAPSHOT_UPDATE` set, the current file is copied to the baseline.
+fn gas_snapshot(args: &[String]) -> i32 {
+    let (baseline, current, tolerance) = match args {
+        [baseline, current] => (baseline, current, 0.0),
+        [baseline, current, flag, tolerance] if flag == "--tolerance" => match tolerance.parse() {
+            Ok(tolerance) => (baseline, current, tolerance),
+            Err(_) => {
+                eprintln!("Invalid tolerance: {}", tolerance);
+                return 2;
+            }
+        },
+        _ => {
+            eprintln!("Usage: wasmify_rs gas-snapshot <baseline> <current> [--tolerance <percent>]");
+            return 2;
+        }
+    };
+    if std::env::var_os(UPDATE_SNAPSHOT_ENV).is_some() {
+        return match GasSnapshot::load(current).and_then(|current| current.update(baseline)) {
+            Ok(()) => {
+                println!("Updated {} from {}", baseline, current);
+                0
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                2
+            }
+        };
+    }
+    let snapshots = GasSnapshot::load(baseline).and_then(|baseline| Ok((baseline, GasSnapshot::load(current)?)));
+    match snapshots {
+        Ok((baseline, current)) => {
+            let diff = current.diff(&baseline);
+            println!("{}", diff);
+            diff.exit_code(tolerance)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            2
+        }
+    }
 }